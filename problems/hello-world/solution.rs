@@ -0,0 +1,9 @@
+fn main() {
+    let mut s1 = String::from("hello");
+    add_world(&mut s1);
+    println!("{}.", s1);
+}
+
+fn add_world(s: &mut String){
+    s.push_str(" world");
+}
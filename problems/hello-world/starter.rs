@@ -0,0 +1,9 @@
+fn main() {
+    let mut s1 = String::from("hello");
+    // ここを編集して "hello world." を出力してください
+    println!("{}.", s1);
+}
+
+fn add_world(s: &mut String){
+    s.push_str(" world");
+}
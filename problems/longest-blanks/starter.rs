@@ -0,0 +1,12 @@
+fn longest<'a>(x: &'a str, y: &___ str) -> &'a str {
+    if x.len() >= y.len() { x } else { y }
+}
+
+fn main() {
+    let words: ___ = vec!["apple", "banana", "kiwi"];
+    let mut best = "";
+    for w in &words {
+        best = longest(best, w);
+    }
+    println!("{}", best);
+}
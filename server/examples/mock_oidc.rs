@@ -0,0 +1,238 @@
+//! 手元で OIDC ログインを試すための模擬 IdP（本番では使わない）
+//!
+//!   cargo run --example mock_oidc            # http://localhost:9000（MOCK_OIDC_PORT で変更）
+//!
+//! サーバ側は次のように設定する。
+//!   OIDC_ISSUER=http://localhost:9000 OIDC_CLIENT_ID=rust-learning \
+//!   OIDC_REDIRECT_URL=http://localhost:8080/api/oidc/callback OIDC_ROLE_CLAIM=roles
+//!
+//! /authorize はユーザー名・表示名・ロールを入れるフォームを出す。クエリに username（と name, roles）を
+//! 付けると画面を出さずにすぐコードを返すので、curl で流れを追える。
+//! sub は "mock-<username>"、roles はカンマ区切りを配列のクレームにする。鍵は起動のたびに作り直す。
+
+use actix_web::{get, http::header, post, web, App, HttpResponse, HttpServer, Responder};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const KID: &str = "mock-key";
+
+struct Idp {
+    issuer: String,
+    key: EncodingKey,
+    /// JWK の x, y
+    public: (String, String),
+    codes: Mutex<HashMap<String, Grant>>,
+}
+
+/// 発行した認可コードの中身
+struct Grant {
+    client_id: String,
+    redirect_uri: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    username: String,
+    name: String,
+    roles: Vec<String>,
+}
+
+fn random() -> String {
+    let mut bytes = [0u8; 24];
+    SystemRandom::new().fill(&mut bytes).expect("rng");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[get("/.well-known/openid-configuration")]
+async fn discovery(idp: web::Data<Idp>) -> impl Responder {
+    let i = &idp.issuer;
+    HttpResponse::Ok().json(json!({
+        "issuer": i,
+        "authorization_endpoint": format!("{i}/authorize"),
+        "token_endpoint": format!("{i}/token"),
+        "jwks_uri": format!("{i}/jwks"),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["ES256"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+#[get("/jwks")]
+async fn jwks(idp: web::Data<Idp>) -> impl Responder {
+    let (x, y) = &idp.public;
+    HttpResponse::Ok().json(json!({
+        "keys": [{ "kty": "EC", "crv": "P-256", "x": x, "y": y, "kid": KID, "alg": "ES256", "use": "sig" }]
+    }))
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    state: Option<String>,
+    nonce: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    scope: Option<String>,
+    // ここから下はフォーム（またはクエリ）で入れる
+    username: Option<String>,
+    name: Option<String>,
+    roles: Option<String>,
+}
+
+#[get("/authorize")]
+async fn authorize(q: web::Query<AuthorizeQuery>, idp: web::Data<Idp>) -> impl Responder {
+    if q.response_type != "code" {
+        return HttpResponse::BadRequest().body("unsupported response_type");
+    }
+    let Some(challenge) = q.code_challenge.clone().filter(|_| q.code_challenge_method.as_deref() == Some("S256"))
+    else {
+        return HttpResponse::BadRequest().body("PKCE (code_challenge_method=S256) is required");
+    };
+    let Some(username) = q.username.as_deref().map(str::trim).filter(|u| !u.is_empty()) else {
+        return login_form(&q);
+    };
+
+    let code = random();
+    idp.codes.lock().unwrap().insert(
+        code.clone(),
+        Grant {
+            client_id: q.client_id.clone(),
+            redirect_uri: q.redirect_uri.clone(),
+            nonce: q.nonce.clone(),
+            code_challenge: challenge,
+            username: username.to_string(),
+            name: q.name.clone().filter(|n| !n.trim().is_empty()).unwrap_or_else(|| username.to_string()),
+            roles: q.roles.as_deref().unwrap_or_default().split(',').map(str::trim).filter(|r| !r.is_empty())
+                .map(String::from).collect(),
+        },
+    );
+    let mut params = vec![("code", code.as_str())];
+    if let Some(state) = &q.state {
+        params.push(("state", state.as_str()));
+    }
+    match reqwest::Url::parse_with_params(&q.redirect_uri, &params) {
+        Ok(url) => HttpResponse::Found().insert_header((header::LOCATION, url.as_str())).finish(),
+        Err(e) => HttpResponse::BadRequest().body(format!("bad redirect_uri: {e}")),
+    }
+}
+
+fn login_form(q: &AuthorizeQuery) -> HttpResponse {
+    let hidden: String = [
+        ("response_type", Some(&q.response_type)),
+        ("client_id", Some(&q.client_id)),
+        ("redirect_uri", Some(&q.redirect_uri)),
+        ("state", q.state.as_ref()),
+        ("nonce", q.nonce.as_ref()),
+        ("code_challenge", q.code_challenge.as_ref()),
+        ("code_challenge_method", q.code_challenge_method.as_ref()),
+        ("scope", q.scope.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(k, v)| v.map(|v| format!(r#"<input type="hidden" name="{k}" value="{}">"#, escape(v))))
+    .collect();
+    let page = format!(
+        r#"<!doctype html><meta charset="utf-8"><title>Mock IdP</title>
+<h1>Mock IdP</h1>
+<form method="get" action="/authorize">{hidden}
+<p><label>username <input name="username" required autofocus></label></p>
+<p><label>name <input name="name"></label></p>
+<p><label>roles <input name="roles" placeholder="teacher,admin"></label></p>
+<p><button>Sign in</button></p>
+</form>"#
+    );
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(page)
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    code_verifier: String,
+}
+
+fn token_error(error: &str, description: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": error, "error_description": description }))
+}
+
+#[post("/token")]
+async fn token(form: web::Form<TokenForm>, idp: web::Data<Idp>) -> impl Responder {
+    if form.grant_type != "authorization_code" {
+        return token_error("unsupported_grant_type", "only authorization_code is supported");
+    }
+    // コードは一度きり
+    let Some(grant) = idp.codes.lock().unwrap().remove(&form.code) else {
+        return token_error("invalid_grant", "unknown or already used code");
+    };
+    if grant.client_id != form.client_id || grant.redirect_uri != form.redirect_uri {
+        return token_error("invalid_grant", "client_id or redirect_uri does not match");
+    }
+    if URL_SAFE_NO_PAD.encode(Sha256::digest(form.code_verifier.as_bytes())) != grant.code_challenge {
+        return token_error("invalid_grant", "code_verifier does not match code_challenge");
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = json!({
+        "iss": idp.issuer,
+        "sub": format!("mock-{}", grant.username),
+        "aud": grant.client_id,
+        "iat": now,
+        "exp": now + 300,
+        "nonce": grant.nonce,
+        "preferred_username": grant.username,
+        "name": grant.name,
+        "email": format!("{}@example.test", grant.username),
+        "roles": grant.roles,
+    });
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(KID.to_string());
+    match jsonwebtoken::encode(&header, &claims, &idp.key) {
+        Ok(id_token) => HttpResponse::Ok().json(json!({
+            "access_token": random(),
+            "token_type": "Bearer",
+            "expires_in": 300,
+            "id_token": id_token,
+        })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("signing failed: {e}")),
+    }
+}
+
+#[actix_web::main]
+async fn main() -> anyhow::Result<()> {
+    let port: u16 = std::env::var("MOCK_OIDC_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(9000);
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+        .map_err(|_| anyhow::anyhow!("key generation failed"))?;
+    let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+        .map_err(|_| anyhow::anyhow!("key parsing failed"))?;
+    // 非圧縮の点 0x04 || x || y
+    let point = pair.public_key().as_ref();
+    let idp = web::Data::new(Idp {
+        issuer: format!("http://localhost:{port}"),
+        key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+        public: (URL_SAFE_NO_PAD.encode(&point[1..33]), URL_SAFE_NO_PAD.encode(&point[33..65])),
+        codes: Mutex::new(HashMap::new()),
+    });
+    println!("[mock-oidc] issuer: {}", idp.issuer);
+
+    HttpServer::new(move || {
+        App::new().app_data(idp.clone()).service(discovery).service(jwks).service(authorize).service(token)
+    })
+    .bind(("0.0.0.0", port))?
+    .run()
+    .await?;
+    Ok(())
+}
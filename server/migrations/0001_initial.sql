@@ -0,0 +1,28 @@
+-- 初期スキーマ（これまで data/data.db を手で編集して作っていたものと同じ。列の追加は 0002 以降）
+-- 既存の data.db に対しては何もしないよう IF NOT EXISTS にしている
+
+CREATE TABLE IF NOT EXISTS "submissions" (
+	"id"	INTEGER NOT NULL,
+	"problem_id"	INTEGER,
+	"code"	TEXT,
+	"stdout"	INTEGER,
+	"created_at"	TEXT,
+	PRIMARY KEY("id" AUTOINCREMENT)
+);
+
+CREATE TABLE IF NOT EXISTS "problems" (
+	"id"	INTEGER,
+	"slug"	TEXT NOT NULL UNIQUE,
+	"title"	TEXT NOT NULL,
+	"description"	TEXT NOT NULL,
+	"starter_code"	TEXT NOT NULL,
+	"expected_stdout"	TEXT NOT NULL,
+	"fixed_top"	TEXT,
+	"fixed_bottom"	TEXT,
+	"required_snipped"	TEXT,
+	"required_message"	TEXT,
+	"editable_start_marker"	TEXT,
+	"editable_end_marker"	TEXT,
+	"created_at"	TEXT NOT NULL,
+	PRIMARY KEY("id" AUTOINCREMENT)
+);
@@ -0,0 +1,28 @@
+-- 問題ごとのリソース上限（NULL なら既定値）
+ALTER TABLE problems ADD COLUMN memory_limit_kb   INTEGER;
+ALTER TABLE problems ADD COLUMN cpu_time_limit_ms INTEGER;
+
+-- 提出に判定と使ったリソースを残す。
+-- 初期の stdout 列は INTEGER 型だったので（出力が数字だけだと整数として保存される）、
+-- 列名の変更だけでは済まず TEXT の output として作り直す。problems への外部キーもここで付ける
+CREATE TABLE submissions_new (
+    id           INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    problem_id   INTEGER REFERENCES problems(id),
+    code         TEXT,
+    output       TEXT,
+    verdict      TEXT,
+    max_rss_kb   INTEGER,
+    user_time_ms INTEGER,
+    sys_time_ms  INTEGER,
+    created_at   TEXT
+);
+INSERT INTO submissions_new (id, problem_id, code, output, created_at)
+SELECT id,
+       -- 消えた問題への提出は問題なしとして残す
+       CASE WHEN problem_id IN (SELECT id FROM problems) THEN problem_id END,
+       code,
+       CAST(stdout AS TEXT),
+       created_at
+FROM submissions;
+DROP TABLE submissions;
+ALTER TABLE submissions_new RENAME TO submissions;
@@ -0,0 +1,2 @@
+-- 問題ごとの出力の上限（バイト。NULL なら 64 KiB）
+ALTER TABLE problems ADD COLUMN output_limit_bytes INTEGER;
@@ -0,0 +1,5 @@
+-- 問題ごとのコンパイル設定（toolchain が NULL なら既定のツールチェイン）
+ALTER TABLE problems ADD COLUMN edition          TEXT    NOT NULL DEFAULT '2021';
+ALTER TABLE problems ADD COLUMN opt_level        TEXT    NOT NULL DEFAULT '2';
+ALTER TABLE problems ADD COLUMN debug_assertions INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE problems ADD COLUMN toolchain        TEXT;
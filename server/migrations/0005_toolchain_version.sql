@@ -0,0 +1,2 @@
+-- 判定に使った rustc -V（ツールチェインを上げたあとでも、どの版で判定したか分かるように）
+ALTER TABLE submissions ADD COLUMN toolchain_version TEXT;
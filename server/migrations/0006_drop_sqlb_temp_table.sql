@@ -0,0 +1,2 @@
+-- DB Browser for SQLite がテーブル変更の途中で残した一時テーブル
+DROP TABLE IF EXISTS sqlb_temp_table_1;
@@ -0,0 +1,9 @@
+-- 問題ごとの入出力テストケース。無い問題は problems.expected_stdout を唯一のケースとして扱う
+CREATE TABLE test_cases (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id      INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position        INTEGER NOT NULL,
+    stdin           TEXT    NOT NULL DEFAULT '',
+    expected_stdout TEXT    NOT NULL,
+    UNIQUE (problem_id, position)
+);
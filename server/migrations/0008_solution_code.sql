@@ -0,0 +1,2 @@
+-- 問題ごとの模範解答（自己検査で実際に判定へ通す。学習者向け API には出さない）
+ALTER TABLE problems ADD COLUMN solution_code TEXT;
@@ -0,0 +1,12 @@
+-- 問題ごとのヒント（problems の本文と同じく既定ロケール = ja で書く）
+ALTER TABLE problems ADD COLUMN hint TEXT;
+
+-- 既定ロケール以外の翻訳。無い項目は problems 側（ja）にフォールバックする
+CREATE TABLE problem_translations (
+    problem_id  INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    locale      TEXT    NOT NULL,
+    title       TEXT    NOT NULL,
+    description TEXT    NOT NULL DEFAULT '',
+    hint        TEXT,
+    PRIMARY KEY (problem_id, locale)
+);
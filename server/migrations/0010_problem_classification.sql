@@ -0,0 +1,12 @@
+-- 難易度（easy / medium / hard）とトピック分類（ownership, traits など）。どちらも NULL 可
+ALTER TABLE problems ADD COLUMN difficulty TEXT;
+ALTER TABLE problems ADD COLUMN category   TEXT;
+
+-- 自由なタグ（1 問に複数）
+CREATE TABLE problem_tags (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    tag        TEXT    NOT NULL,
+    PRIMARY KEY (problem_id, tag)
+);
+CREATE INDEX idx_problem_tags_tag ON problem_tags(tag);
+CREATE INDEX idx_problems_category ON problems(category);
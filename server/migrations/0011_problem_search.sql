@@ -0,0 +1,30 @@
+-- 問題の全文検索（title / description / tags / starter_code）。
+-- 日本語は空白で区切られないので trigram（3 文字以上の部分一致）で索引する。
+-- 1 行 = 1 問 × 1 ロケール（problems 本体は ja、problem_translations の各ロケール）
+CREATE VIRTUAL TABLE problem_search USING fts5(
+    problem_id UNINDEXED,
+    locale     UNINDEXED,
+    title,
+    description,
+    tags,
+    starter_code,
+    tokenize = 'trigram'
+);
+
+-- 挿入・更新はアプリ側（save_problem）で索引し直す。削除だけはここで追随する
+CREATE TRIGGER problem_search_delete AFTER DELETE ON problems BEGIN
+    DELETE FROM problem_search WHERE problem_id = old.id;
+END;
+
+-- 既存の問題を索引
+INSERT INTO problem_search (problem_id, locale, title, description, tags, starter_code)
+SELECT p.id, 'ja', p.title, p.description,
+       COALESCE((SELECT GROUP_CONCAT(tag, ' ') FROM problem_tags WHERE problem_id = p.id), ''),
+       p.starter_code
+FROM problems p;
+
+INSERT INTO problem_search (problem_id, locale, title, description, tags, starter_code)
+SELECT p.id, t.locale, t.title, t.description,
+       COALESCE((SELECT GROUP_CONCAT(tag, ' ') FROM problem_tags WHERE problem_id = p.id), ''),
+       p.starter_code
+FROM problems p JOIN problem_translations t ON t.problem_id = p.id;
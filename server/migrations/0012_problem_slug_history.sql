@@ -0,0 +1,20 @@
+-- 以前の slug（/p/{slug} のリンクを張り替えたあとも古い URL から辿れるように）
+CREATE TABLE problem_slug_history (
+    slug       TEXT    PRIMARY KEY,
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    changed_at TEXT    NOT NULL
+);
+CREATE INDEX idx_problem_slug_history_problem ON problem_slug_history(problem_id);
+
+-- slug を変えたら旧 slug を記録する（管理 API・import のどちらから変えても）
+CREATE TRIGGER problem_slug_renamed AFTER UPDATE OF slug ON problems
+WHEN old.slug <> new.slug BEGIN
+    INSERT OR REPLACE INTO problem_slug_history (slug, problem_id, changed_at)
+    VALUES (old.slug, new.id, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+    DELETE FROM problem_slug_history WHERE slug = new.slug;
+END;
+
+-- 旧 slug を別の問題が使い始めたら、そちらを優先する
+CREATE TRIGGER problem_slug_reused AFTER INSERT ON problems BEGIN
+    DELETE FROM problem_slug_history WHERE slug = new.slug;
+END;
@@ -0,0 +1,38 @@
+-- コース（順序付きの単元に問題を並べたもの）
+CREATE TABLE courses (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    slug        TEXT    NOT NULL UNIQUE,
+    title       TEXT    NOT NULL,
+    description TEXT    NOT NULL DEFAULT '',
+    -- 一覧での並び順（小さいほど先）
+    position    INTEGER NOT NULL DEFAULT 0,
+    created_at  TEXT    NOT NULL
+);
+
+-- 単元。sequential なら単元内の問題を前から順に解かないと開かない
+CREATE TABLE course_units (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    course_id   INTEGER NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    title       TEXT    NOT NULL,
+    description TEXT    NOT NULL DEFAULT '',
+    sequential  INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (course_id, position)
+);
+
+CREATE TABLE course_problems (
+    unit_id    INTEGER NOT NULL REFERENCES course_units(id) ON DELETE CASCADE,
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    PRIMARY KEY (unit_id, problem_id)
+);
+CREATE INDEX idx_course_problems_problem ON course_problems(problem_id);
+
+-- 前提条件: problem_id は requires_id に正解するまで開かない（コースごと）
+CREATE TABLE course_prerequisites (
+    course_id   INTEGER NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    problem_id  INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    requires_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    PRIMARY KEY (course_id, problem_id, requires_id),
+    CHECK (problem_id <> requires_id)
+);
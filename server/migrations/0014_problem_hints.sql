@@ -0,0 +1,38 @@
+-- 段階的なヒント（position 順に 1 つずつ開く）。penalty は使ったときに引く点数（0 なら減点なし）
+CREATE TABLE problem_hints (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    body       TEXT    NOT NULL,
+    penalty    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (problem_id, position)
+);
+
+-- ヒントの翻訳（position は problem_hints と対応。無いものは既定ロケールで出す）
+CREATE TABLE problem_hint_translations (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    locale     TEXT    NOT NULL,
+    position   INTEGER NOT NULL,
+    body       TEXT    NOT NULL,
+    PRIMARY KEY (problem_id, locale, position)
+);
+
+-- ヒントを開いた記録（penalty は開いた時点の値を残す）
+CREATE TABLE hint_reveals (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id  INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    penalty     INTEGER NOT NULL,
+    revealed_at TEXT    NOT NULL
+);
+CREATE INDEX idx_hint_reveals_problem ON hint_reveals(problem_id);
+
+-- これまでの 1 問 1 つのヒントは 1 段目に移す
+INSERT INTO problem_hints (problem_id, position, body)
+SELECT id, 0, hint FROM problems WHERE hint IS NOT NULL AND hint <> '';
+
+INSERT INTO problem_hint_translations (problem_id, locale, position, body)
+SELECT problem_id, locale, 0, hint FROM problem_translations
+WHERE hint IS NOT NULL AND hint <> '' AND problem_id IN (SELECT problem_id FROM problem_hints);
+
+ALTER TABLE problems DROP COLUMN hint;
+ALTER TABLE problem_translations DROP COLUMN hint;
@@ -0,0 +1,18 @@
+-- 模範解答以外の別解（イテレータで書く版など）。学習者には正解後かギブアップ後にだけ見せる
+CREATE TABLE problem_answers (
+    problem_id  INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    title       TEXT    NOT NULL,
+    -- 解説（Markdown）
+    explanation TEXT    NOT NULL DEFAULT '',
+    code        TEXT    NOT NULL,
+    PRIMARY KEY (problem_id, position)
+);
+
+-- 「ギブアップして解答を見る」の記録
+CREATE TABLE problem_give_ups (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id  INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    given_up_at TEXT    NOT NULL
+);
+CREATE INDEX idx_problem_give_ups_problem ON problem_give_ups(problem_id);
@@ -0,0 +1,40 @@
+-- 問題の版。内容が変わる保存のたびに 1 つ増える（0 = まだ版を取っていない）
+ALTER TABLE problems ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
+
+-- 各版の内容（管理 API の ProblemInput と同じ形の JSON）
+CREATE TABLE problem_revisions (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    revision   INTEGER NOT NULL,
+    content    TEXT    NOT NULL,
+    created_at TEXT    NOT NULL,
+    PRIMARY KEY (problem_id, revision)
+);
+
+-- どの版で判定したか（この変更より前の提出は NULL = 不明）
+ALTER TABLE submissions ADD COLUMN problem_revision INTEGER;
+
+-- 再判定ジョブ（対象は作成時に rejudge_results に並べておき、判定したものから埋める）
+CREATE TABLE rejudge_jobs (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 対象の選び方（表示用。例: {"problem_id":1,"only_stale":true}）
+    selection   TEXT    NOT NULL,
+    -- 1 なら submissions は更新しない
+    dry_run     INTEGER NOT NULL DEFAULT 0,
+    -- queued / running / done / failed
+    status      TEXT    NOT NULL,
+    error       TEXT,
+    created_at  TEXT    NOT NULL,
+    finished_at TEXT
+);
+
+CREATE TABLE rejudge_results (
+    job_id        INTEGER NOT NULL REFERENCES rejudge_jobs(id) ON DELETE CASCADE,
+    submission_id INTEGER NOT NULL REFERENCES submissions(id) ON DELETE CASCADE,
+    old_verdict   TEXT,
+    old_revision  INTEGER,
+    -- 判定前は NULL。ツールチェーンが無くて飛ばしたものは 'Skipped'
+    new_verdict   TEXT,
+    new_revision  INTEGER,
+    judged_at     TEXT,
+    PRIMARY KEY (job_id, submission_id)
+);
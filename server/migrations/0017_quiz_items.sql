@@ -0,0 +1,27 @@
+-- 問題の種類（code = コードを書いて判定 / quiz = 選択式・短答式）
+ALTER TABLE problems ADD COLUMN kind TEXT NOT NULL DEFAULT 'code';
+
+-- クイズの形式（single = 単一選択 / multiple = 複数選択 / text = 短答）
+CREATE TABLE quiz_items (
+    problem_id     INTEGER PRIMARY KEY REFERENCES problems(id) ON DELETE CASCADE,
+    format         TEXT    NOT NULL,
+    -- text のとき大文字・小文字を区別するか
+    case_sensitive INTEGER NOT NULL DEFAULT 0
+);
+
+-- 選択肢（single / multiple）
+CREATE TABLE quiz_choices (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    body       TEXT    NOT NULL,
+    correct    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (problem_id, position)
+);
+
+-- 正解として受け付ける表記（text）
+CREATE TABLE quiz_accepted_answers (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    answer     TEXT    NOT NULL,
+    PRIMARY KEY (problem_id, position)
+);
@@ -0,0 +1,10 @@
+-- 穴埋め問題（kind = 'blanks'）の穴。テンプレートは problems.starter_code（穴は `___`）
+CREATE TABLE problem_blanks (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    -- 模範の中身（テンプレートに差し込むと模範解答になる）
+    answer     TEXT    NOT NULL,
+    -- 中身の形の制約（正規表現。中身全体が一致すること。NULL なら制約なし）
+    pattern    TEXT,
+    PRIMARY KEY (problem_id, position)
+);
@@ -0,0 +1,17 @@
+-- 並べ替え問題（kind = 'parsons'）
+CREATE TABLE parsons_items (
+    problem_id   INTEGER PRIMARY KEY REFERENCES problems(id) ON DELETE CASCADE,
+    -- 字下げも採点するか（波括弧の深さと合っていること）
+    check_indent INTEGER NOT NULL DEFAULT 1
+);
+
+-- 行（position は正しい順。distractor の行は正解には使わない）
+CREATE TABLE parsons_lines (
+    problem_id INTEGER NOT NULL REFERENCES problems(id) ON DELETE CASCADE,
+    position   INTEGER NOT NULL,
+    code       TEXT    NOT NULL,
+    -- 正解での字下げの段数（1 段 = 空白 4 つ）
+    indent     INTEGER NOT NULL DEFAULT 0,
+    distractor INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (problem_id, position)
+);
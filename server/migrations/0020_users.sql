@@ -0,0 +1,24 @@
+-- 利用者アカウント（username は大文字小文字を区別しない）
+CREATE TABLE users (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    username      TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+    display_name  TEXT    NOT NULL,
+    -- Argon2id の PHC 文字列。NULL ならパスワードではログインできない
+    password_hash TEXT,
+    created_at    TEXT    NOT NULL
+);
+
+-- ログイン中のセッション。id は Cookie に入れたトークンの SHA-256（トークンそのものは残さない）
+CREATE TABLE sessions (
+    id         TEXT    PRIMARY KEY,
+    user_id    INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TEXT    NOT NULL,
+    expires_at TEXT    NOT NULL
+);
+CREATE INDEX idx_sessions_user ON sessions(user_id);
+
+-- 提出・ヒント・ギブアップを利用者に紐づける（これまでの記録は NULL = 誰のものでもない）
+ALTER TABLE submissions ADD COLUMN user_id INTEGER REFERENCES users(id);
+ALTER TABLE hint_reveals ADD COLUMN user_id INTEGER REFERENCES users(id);
+ALTER TABLE problem_give_ups ADD COLUMN user_id INTEGER REFERENCES users(id);
+CREATE INDEX idx_submissions_user ON submissions(user_id, problem_id);
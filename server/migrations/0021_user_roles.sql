@@ -0,0 +1,2 @@
+-- 役割（student: 自分の提出だけ / teacher: 問題の管理と全員の提出 / admin: それに加えて役割の変更）
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'student' CHECK (role IN ('student', 'teacher', 'admin'));
@@ -0,0 +1,20 @@
+-- OpenID Connect でログインした利用者の対応（IdP の iss + sub で 1 人を表す）
+CREATE TABLE user_identities (
+    issuer     TEXT    NOT NULL,
+    subject    TEXT    NOT NULL,
+    user_id    INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TEXT    NOT NULL,
+    PRIMARY KEY (issuer, subject)
+);
+CREATE INDEX idx_user_identities_user ON user_identities(user_id);
+
+-- IdP へ送り出したログイン（コールバックで state から引いて消す）
+CREATE TABLE oidc_logins (
+    state         TEXT PRIMARY KEY,
+    nonce         TEXT NOT NULL,
+    -- PKCE の code_verifier
+    code_verifier TEXT NOT NULL,
+    -- ログイン後に戻るパス
+    return_to     TEXT NOT NULL,
+    created_at    TEXT NOT NULL
+);
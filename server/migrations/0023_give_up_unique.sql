@@ -0,0 +1,5 @@
+-- ギブアップは利用者・問題ごとに 1 回だけ（重複していたら最初の記録を残す）
+DELETE FROM problem_give_ups
+WHERE user_id IS NOT NULL
+  AND id NOT IN (SELECT MIN(id) FROM problem_give_ups WHERE user_id IS NOT NULL GROUP BY user_id, problem_id);
+CREATE UNIQUE INDEX idx_problem_give_ups_user ON problem_give_ups(user_id, problem_id);
@@ -0,0 +1,565 @@
+use actix_web::{
+    cookie::{time::Duration as CookieDuration, Cookie, SameSite},
+    dev::Payload,
+    get, post, put, web, FromRequest, HttpRequest, HttpResponse, Responder,
+};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration, Utc};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, SqlitePool};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+
+use crate::admin::{field_error, validation_failed, FieldError};
+use crate::auth::{unauthorized, AdminAuth, Role, TeacherAuth};
+use crate::judge::Verdict;
+use crate::AppState;
+
+/* ==================== 利用者アカウント ==================== */
+//
+// パスワードは Argon2id でハッシュして users.password_hash に持つ。
+// ログインするとランダムなトークンを HttpOnly の Cookie で渡し、DB にはその SHA-256 だけを sessions に残す。
+// Cookie は SameSite=Lax なので、他サイトからの POST にはセッションが付かない（CSRF 対策）。
+// HTTPS の裏で動かすときは COOKIE_SECURE=1 で Secure 属性を付ける。
+// 学校の IdP でだけログインさせるなら PASSWORD_LOGIN=0 でパスワードでの登録・ログインを止める（oidc.rs）。
+
+pub const SESSION_COOKIE: &str = "session";
+/// セッションの有効期間
+const SESSION_DAYS: i64 = 30;
+
+pub const USERNAME_MAX: usize = 32;
+pub const DISPLAY_NAME_MAX: usize = 64;
+const PASSWORD_MIN: usize = 8;
+const PASSWORD_MAX: usize = 128;
+
+/// ログイン中の利用者。ハンドラの引数に置くとログインを要求する（`Option<CurrentUser>` なら任意）
+#[derive(FromRow, Serialize, Clone, Debug)]
+pub struct CurrentUser {
+    pub id: i64,
+    pub username: String,
+    pub display_name: String,
+    // ★ 追加
+    pub role: Role,
+}
+
+/* ==================== パスワード ==================== */
+
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("password hashing failed: {e}"))?;
+    Ok(hash.to_string())
+}
+
+/// hash が None（利用者がいない・パスワード無し）でも同じだけ時間をかけてから false を返す
+/// （応答時間から username の有無が分からないように）
+pub fn verify_password(hash: Option<&str>, password: &str) -> bool {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    let dummy = DUMMY.get_or_init(|| hash_password("not a real password").unwrap_or_default());
+    let matched = PasswordHash::new(hash.unwrap_or(dummy))
+        .is_ok_and(|h| Argon2::default().verify_password(password.as_bytes(), &h).is_ok());
+    matched && hash.is_some()
+}
+
+/// ハッシュは重いので actix のワーカーを止めないよう別スレッドで
+async fn hash_blocking(password: String) -> anyhow::Result<String> {
+    web::block(move || hash_password(&password)).await?
+}
+
+async fn verify_blocking(hash: Option<String>, password: String) -> bool {
+    web::block(move || verify_password(hash.as_deref(), &password)).await.unwrap_or(false)
+}
+
+/* ==================== 検証 ==================== */
+
+pub fn validate_username(username: &str) -> Vec<FieldError> {
+    let ok_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    if username.len() < 3 || username.len() > USERNAME_MAX {
+        vec![field_error("username", format!("must be 3 to {USERNAME_MAX} characters"))]
+    } else if !username.chars().all(ok_char) {
+        vec![field_error("username", "use only letters, digits, '_', '-' and '.'")]
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn validate_password(field: &str, password: &str) -> Vec<FieldError> {
+    let n = password.chars().count();
+    if n < PASSWORD_MIN {
+        vec![field_error(field, format!("must be at least {PASSWORD_MIN} characters"))]
+    } else if n > PASSWORD_MAX {
+        vec![field_error(field, format!("must be at most {PASSWORD_MAX} characters"))]
+    } else {
+        Vec::new()
+    }
+}
+
+fn validate_display_name(display_name: &str) -> Vec<FieldError> {
+    if display_name.chars().count() > DISPLAY_NAME_MAX {
+        vec![field_error("display_name", format!("must be at most {DISPLAY_NAME_MAX} characters"))]
+    } else {
+        Vec::new()
+    }
+}
+
+/* ==================== 利用者・セッション ==================== */
+
+/// 利用者を作る。username が使われていれば None
+pub async fn create_user(
+    pool: &SqlitePool,
+    username: &str,
+    display_name: &str,
+    // ★ 変更: IdP でログインする利用者はパスワードを持たない（None）
+    password_hash: Option<&str>,
+    role: Role,
+) -> sqlx::Result<Option<CurrentUser>> {
+    let display_name = if display_name.trim().is_empty() { username } else { display_name.trim() };
+    let inserted = sqlx::query(
+        "INSERT INTO users (username, display_name, password_hash, role, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(username)
+    .bind(display_name)
+    .bind(password_hash)
+    .bind(role)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await;
+    match inserted {
+        Ok(r) => Ok(Some(CurrentUser {
+            id: r.last_insert_rowid(),
+            username: username.to_string(),
+            display_name: display_name.to_string(),
+            role,
+        })),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// change_role の結果
+pub enum RoleChange {
+    Changed,
+    NotFound,
+    /// 最後の admin を降格しようとした（変えていない）
+    LastAdmin,
+}
+
+/// 役割を変える（最後の admin は降格しない）。確かめるのと変えるのを 1 文で行う
+pub async fn change_role(pool: &SqlitePool, user_id: i64, role: Role) -> sqlx::Result<RoleChange> {
+    let r = sqlx::query(
+        r#"
+        UPDATE users SET role = ?1
+        WHERE id = ?2
+          AND NOT (role = 'admin' AND ?1 <> 'admin' AND (SELECT COUNT(*) FROM users WHERE role = 'admin') <= 1)
+        "#,
+    )
+    .bind(role)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    if r.rows_affected() > 0 {
+        return Ok(RoleChange::Changed);
+    }
+    let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(if exists.is_some() { RoleChange::LastAdmin } else { RoleChange::NotFound })
+}
+
+/// 役割を変える。利用者がいなければ false（最後の admin の確認はしない。CLI 用）
+pub async fn set_role(pool: &SqlitePool, user_id: i64, role: Role) -> sqlx::Result<bool> {
+    let r = sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+        .bind(role)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// パスワードを置き換える。利用者がいなければ false
+pub async fn set_password(pool: &SqlitePool, user_id: i64, password_hash: &str) -> sqlx::Result<bool> {
+    let r = sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// username から（id, パスワードのハッシュ）
+pub async fn find_credentials(pool: &SqlitePool, username: &str) -> sqlx::Result<Option<(i64, Option<String>)>> {
+    sqlx::query_as("SELECT id, password_hash FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+}
+
+/// 利用者のセッションを消す（keep のセッションだけは残す）
+pub async fn end_sessions(pool: &SqlitePool, user_id: i64, keep: Option<&str>) -> sqlx::Result<()> {
+    sqlx::query("DELETE FROM sessions WHERE user_id = ? AND id IS NOT ?")
+        .bind(user_id)
+        .bind(keep.map(session_id))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 推測できないランダムな文字列（セッションのトークン・OIDC の state / nonce）
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex(&bytes)
+}
+
+pub async fn fetch_user(pool: &SqlitePool, id: i64) -> sqlx::Result<CurrentUser> {
+    sqlx::query_as("SELECT id, username, display_name, role FROM users WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+}
+
+/// sessions.id（トークンの SHA-256）
+fn session_id(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// セッションを作ってトークンを返す（ついでに期限切れのセッションを消す）
+pub async fn start_session(pool: &SqlitePool, user_id: i64) -> sqlx::Result<String> {
+    let token = random_token();
+    let now = Utc::now();
+    sqlx::query("DELETE FROM sessions WHERE expires_at <= ?")
+        .bind(now.to_rfc3339())
+        .execute(pool)
+        .await?;
+    sqlx::query("INSERT INTO sessions (id, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)")
+        .bind(session_id(&token))
+        .bind(user_id)
+        .bind(now.to_rfc3339())
+        .bind((now + Duration::days(SESSION_DAYS)).to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(token)
+}
+
+/// トークンの利用者（無い・期限切れなら None）
+async fn session_user(pool: &SqlitePool, token: &str) -> sqlx::Result<Option<CurrentUser>> {
+    sqlx::query_as(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.role
+        FROM sessions s JOIN users u ON u.id = s.user_id
+        WHERE s.id = ? AND s.expires_at > ?
+        "#,
+    )
+    .bind(session_id(token))
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await
+}
+
+impl FromRequest for CurrentUser {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let state = req.app_data::<web::Data<AppState>>().cloned();
+        let token = req.cookie(SESSION_COOKIE).map(|c| c.value().to_string());
+        Box::pin(async move {
+            let (Some(state), Some(token)) = (state, token) else {
+                return Err(unauthorized("login required"));
+            };
+            match session_user(&state.pool, &token).await {
+                Ok(Some(user)) => Ok(user),
+                Ok(None) => Err(unauthorized("session expired; please log in again")),
+                Err(e) => Err(actix_web::error::ErrorInternalServerError(format!("db error: {e}"))),
+            }
+        })
+    }
+}
+
+pub fn session_cookie(token: String, secure: bool) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(secure)
+        .max_age(CookieDuration::days(SESSION_DAYS))
+        .finish()
+}
+
+/// ログインした状態の応答（Cookie 付き）
+async fn logged_in(state: &AppState, user: CurrentUser, mut resp: actix_web::HttpResponseBuilder) -> HttpResponse {
+    match start_session(&state.pool, user.id).await {
+        Ok(token) => resp.cookie(session_cookie(token, state.secure_cookies)).json(user),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/* ==================== ハンドラ ==================== */
+
+/// PASSWORD_LOGIN=0 のとき（IdP でだけログインさせる）
+fn password_login_disabled() -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "error": "password_login_disabled",
+        "message": "password login is disabled; log in with single sign-on",
+    }))
+}
+
+/// ログイン画面に出す方法
+#[get("/api/account/methods")]
+async fn methods(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(json!({ "password": state.password_login, "oidc": state.oidc.is_some() }))
+}
+
+#[derive(Deserialize)]
+struct RegisterReq {
+    username: String,
+    password: String,
+    #[serde(default)]
+    display_name: String,
+}
+
+/// 登録してそのままログインする
+#[post("/api/account/register")]
+async fn register(input: web::Json<RegisterReq>, state: web::Data<AppState>) -> impl Responder {
+    if !state.password_login {
+        return password_login_disabled();
+    }
+    let RegisterReq { username, password, display_name } = input.into_inner();
+    let username = username.trim().to_string();
+    let mut errors = validate_username(&username);
+    errors.extend(validate_password("password", &password));
+    errors.extend(validate_display_name(&display_name));
+    if !errors.is_empty() {
+        return validation_failed(errors);
+    }
+    let hash = match hash_blocking(password).await {
+        Ok(h) => h,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    // 自分で登録した利用者は student（teacher 以上にするのは admin）
+    match create_user(&state.pool, &username, &display_name, Some(&hash), Role::Student).await {
+        Ok(Some(user)) => logged_in(&state, user, HttpResponse::Created()).await,
+        Ok(None) => HttpResponse::Conflict()
+            .json(json!({ "error": "username_taken", "message": "this username is already taken" })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[derive(Deserialize)]
+struct LoginReq {
+    username: String,
+    password: String,
+}
+
+#[post("/api/account/login")]
+async fn login(input: web::Json<LoginReq>, state: web::Data<AppState>) -> impl Responder {
+    if !state.password_login {
+        return password_login_disabled();
+    }
+    let LoginReq { username, password } = input.into_inner();
+    let found = match find_credentials(&state.pool, username.trim()).await {
+        Ok(f) => f,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    let (id, hash) = found.map_or((None, None), |(id, hash)| (Some(id), hash));
+    let verified = verify_blocking(hash, password).await;
+    let Some(id) = id.filter(|_| verified) else {
+        return HttpResponse::Unauthorized()
+            .json(json!({ "error": "invalid_credentials", "message": "wrong username or password" }));
+    };
+    match fetch_user(&state.pool, id).await {
+        Ok(user) => logged_in(&state, user, HttpResponse::Ok()).await,
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/// セッションを消して Cookie も消す（ログインしていなくても 204）
+#[post("/api/account/logout")]
+async fn logout(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    if let Some(c) = req.cookie(SESSION_COOKIE) {
+        if let Err(e) = sqlx::query("DELETE FROM sessions WHERE id = ?")
+            .bind(session_id(c.value()))
+            .execute(&state.pool)
+            .await
+        {
+            return HttpResponse::InternalServerError().body(format!("db error: {e}"));
+        }
+    }
+    let mut removal = session_cookie(String::new(), state.secure_cookies);
+    removal.make_removal();
+    HttpResponse::NoContent().cookie(removal).finish()
+}
+
+#[get("/api/account")]
+async fn me(user: CurrentUser) -> impl Responder {
+    HttpResponse::Ok().json(user)
+}
+
+#[derive(Deserialize)]
+struct PasswordReq {
+    current_password: String,
+    new_password: String,
+}
+
+/// パスワードを変える。ほかの端末のセッションはログアウトさせる（この端末は続けて使える）
+#[post("/api/account/password")]
+async fn change_password(
+    req: HttpRequest,
+    user: CurrentUser,
+    input: web::Json<PasswordReq>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let PasswordReq { current_password, new_password } = input.into_inner();
+    let hash = match find_credentials(&state.pool, &user.username).await {
+        Ok(found) => found.and_then(|(_, h)| h),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    let mut errors = Vec::new();
+    if !verify_blocking(hash, current_password).await {
+        errors.push(field_error("current_password", "does not match"));
+    }
+    errors.extend(validate_password("new_password", &new_password));
+    if !errors.is_empty() {
+        return validation_failed(errors);
+    }
+
+    let hash = match hash_blocking(new_password).await {
+        Ok(h) => h,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let current = req.cookie(SESSION_COOKIE);
+    let updated = async {
+        set_password(&state.pool, user.id, &hash).await?;
+        end_sessions(&state.pool, user.id, current.as_ref().map(|c| c.value())).await
+    };
+    match updated.await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/* ==================== 利用者の管理 ==================== */
+
+#[derive(FromRow, Serialize)]
+pub struct UserSummary {
+    pub id: i64,
+    pub username: String,
+    pub display_name: String,
+    pub role: Role,
+    pub created_at: String,
+    pub submissions: i64,
+    /// Accepted の提出がある問題の数
+    pub solved: i64,
+}
+
+pub async fn list_users(pool: &SqlitePool) -> sqlx::Result<Vec<UserSummary>> {
+    sqlx::query_as(
+        r#"
+        SELECT u.id, u.username, u.display_name, u.role, u.created_at,
+          (SELECT COUNT(*) FROM submissions WHERE user_id = u.id) AS submissions,
+          (SELECT COUNT(DISTINCT problem_id) FROM submissions WHERE user_id = u.id AND verdict = ?) AS solved
+        FROM users u
+        ORDER BY u.id
+        "#,
+    )
+    .bind(Verdict::Accepted.as_str())
+    .fetch_all(pool)
+    .await
+}
+
+#[get("/api/admin/users")]
+async fn get_users(_: TeacherAuth, state: web::Data<AppState>) -> impl Responder {
+    match list_users(&state.pool).await {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[derive(Deserialize)]
+struct RoleReq {
+    role: String,
+}
+
+/// 役割を変える（最後の admin は降格できない）
+#[put("/api/admin/users/{id}/role")]
+async fn update_role(
+    _: AdminAuth,
+    path: web::Path<i64>,
+    input: web::Json<RoleReq>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let Some(role) = Role::parse(&input.role) else {
+        return validation_failed(vec![field_error("role", "must be student, teacher or admin")]);
+    };
+    // ★ 変更: 最後の admin の確認は OIDC のログインと共通（change_role）
+    match change_role(&state.pool, id, role).await {
+        Ok(RoleChange::Changed) => HttpResponse::Ok().json(json!({ "id": id, "role": role })),
+        Ok(RoleChange::NotFound) => HttpResponse::NotFound().finish(),
+        Ok(RoleChange::LastAdmin) => HttpResponse::Conflict()
+            .json(json!({ "error": "last_admin", "message": "cannot demote the last admin" })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(methods)
+        .service(register)
+        .service(login)
+        .service(logout)
+        .service(me)
+        .service(change_password)
+        .service(get_users)
+        .service(update_role);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    /// メモリ上の DB（接続ごとに別の DB になるので 1 本だけ）
+    async fn pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::MIGRATOR.run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn user(pool: &SqlitePool, username: &str, role: Role) -> i64 {
+        create_user(pool, username, username, None, role).await.unwrap().unwrap().id
+    }
+
+    async fn role(pool: &SqlitePool, id: i64) -> Role {
+        fetch_user(pool, id).await.unwrap().role
+    }
+
+    #[tokio::test]
+    async fn change_role_keeps_the_last_admin() {
+        let pool = pool().await;
+        let a = user(&pool, "alice", Role::Admin).await;
+        let b = user(&pool, "bob", Role::Admin).await;
+        let c = user(&pool, "carol", Role::Student).await;
+
+        assert!(matches!(change_role(&pool, a, Role::Teacher).await.unwrap(), RoleChange::Changed));
+        assert!(matches!(change_role(&pool, b, Role::Student).await.unwrap(), RoleChange::LastAdmin));
+        assert_eq!(role(&pool, b).await, Role::Admin);
+        // admin のままにするのは構わない
+        assert!(matches!(change_role(&pool, b, Role::Admin).await.unwrap(), RoleChange::Changed));
+        // 別の admin ができれば降格できる
+        assert!(matches!(change_role(&pool, c, Role::Admin).await.unwrap(), RoleChange::Changed));
+        assert!(matches!(change_role(&pool, b, Role::Student).await.unwrap(), RoleChange::Changed));
+        assert_eq!(role(&pool, b).await, Role::Student);
+        assert!(matches!(change_role(&pool, 999, Role::Admin).await.unwrap(), RoleChange::NotFound));
+    }
+}
@@ -0,0 +1,675 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+use crate::answer::{fetch_answers, Answer};
+use crate::auth::TeacherAuth;
+use crate::blanks::{self, fetch_blanks, Blank};
+use crate::hint::{fetch_hints, Hint};
+use crate::locale::{DEFAULT_LOCALE, SUPPORTED_LOCALES};
+use crate::parsons::{self, fetch_parsons, Parsons};
+use crate::problem::{
+    fetch_problem, fetch_test_cases, fetch_translations, Problem, TestCase, Translation, CATEGORIES, DIFFICULTIES,
+    KINDS, KIND_BLANKS, KIND_CODE, KIND_PARSONS, KIND_QUIZ,
+};
+use crate::quiz::{self, fetch_quiz, Quiz};
+use crate::revision;
+use crate::runner::{self, CompileOptions, SUPPORTED_EDITIONS, SUPPORTED_OPT_LEVELS};
+use crate::search;
+use crate::selfcheck;
+use crate::toolchain::ToolchainRegistry;
+use crate::AppState;
+
+/* ==================== 入力 ==================== */
+
+/// 作成・更新の本文（更新は全置換。test_cases も丸ごと差し替える）
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ProblemInput {
+    pub slug: String,
+    /// code（既定）/ quiz / blanks / parsons
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    pub title: String,
+    pub description: String,
+    /// 段階的なヒント（前から順に 1 つずつ開く）
+    #[serde(default)]
+    pub hints: Vec<Hint>,
+    /// クイズ・並べ替えでは空でよい。穴埋めでは `___` を穴とするテンプレート
+    #[serde(default)]
+    pub starter_code: String,
+    #[serde(default)]
+    pub expected_stdout: String,
+    pub fixed_top: Option<String>,
+    pub fixed_bottom: Option<String>,
+    pub editable_start_marker: Option<String>,
+    pub editable_end_marker: Option<String>,
+    pub memory_limit_kb: Option<i64>,
+    pub cpu_time_limit_ms: Option<i64>,
+    pub output_limit_bytes: Option<i64>,
+    #[serde(default = "default_edition")]
+    pub edition: String,
+    #[serde(default = "default_opt_level")]
+    pub opt_level: String,
+    #[serde(default)]
+    pub debug_assertions: bool,
+    pub toolchain: Option<String>,
+    /// 模範解答（自己検査で全ケース Accepted になるべきコード）
+    #[serde(default)]
+    pub solution_code: Option<String>,
+    /// 別解（正解後に模範解答と並べて見せる。これも Accepted になるべき）
+    #[serde(default)]
+    pub answers: Vec<Answer>,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub test_cases: Vec<TestCaseInput>,
+    /// kind = "quiz" のときの選択肢・正解
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiz: Option<Quiz>,
+    /// kind = "blanks" のときの穴（starter_code の `___` と同じ数・同じ順）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blanks: Vec<Blank>,
+    /// kind = "parsons" のときの行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsons: Option<Parsons>,
+    /// 既定ロケール（ja）以外の翻訳。キーはロケール（"en" など）
+    #[serde(default)]
+    pub translations: BTreeMap<String, TranslationInput>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TranslationInput {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// hints と同じ順（足りない段は既定ロケールの本文を出す）
+    #[serde(default)]
+    pub hints: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TestCaseInput {
+    #[serde(default)]
+    pub stdin: String,
+    pub expected_stdout: String,
+}
+
+impl ProblemInput {
+    /// DB の内容を入力形式に戻す（パッケージの書き出しと差分判定用）。
+    /// 種類ごとの中身（quiz / blanks / parsons）は空なので呼び出し側で埋める
+    pub fn from_db(
+        p: &Problem,
+        cases: &[TestCase],
+        translations: &[Translation],
+        hints: &[Hint],
+        answers: &[Answer],
+    ) -> Self {
+        ProblemInput {
+            slug: p.slug.clone(),
+            kind: p.kind.clone(),
+            title: p.title.clone(),
+            description: p.description.clone(),
+            hints: hints.to_vec(),
+            starter_code: p.starter_code.clone(),
+            expected_stdout: p.expected_stdout.clone(),
+            fixed_top: p.fixed_top.clone(),
+            fixed_bottom: p.fixed_bottom.clone(),
+            editable_start_marker: p.editable_start_marker.clone(),
+            editable_end_marker: p.editable_end_marker.clone(),
+            memory_limit_kb: p.memory_limit_kb,
+            cpu_time_limit_ms: p.cpu_time_limit_ms,
+            output_limit_bytes: p.output_limit_bytes,
+            edition: p.edition.clone(),
+            opt_level: p.opt_level.clone(),
+            debug_assertions: p.debug_assertions,
+            toolchain: p.toolchain.clone(),
+            solution_code: p.solution_code.clone(),
+            answers: answers.to_vec(),
+            difficulty: p.difficulty.clone(),
+            category: p.category.clone(),
+            tags: p.tags.0.clone(),
+            test_cases: cases
+                .iter()
+                .map(|c| TestCaseInput { stdin: c.stdin.clone(), expected_stdout: c.expected_stdout.clone() })
+                .collect(),
+            quiz: None,
+            blanks: Vec::new(),
+            parsons: None,
+            translations: translations
+                .iter()
+                .map(|t| {
+                    let input = TranslationInput {
+                        title: t.title.clone(),
+                        description: t.description.clone(),
+                        hints: t.hints.clone(),
+                    };
+                    (t.locale.clone(), input)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn default_kind() -> String {
+    KIND_CODE.to_string()
+}
+
+fn default_edition() -> String {
+    CompileOptions::default().edition
+}
+
+fn default_opt_level() -> String {
+    CompileOptions::default().opt_level
+}
+
+/* ==================== 検証 ==================== */
+
+#[derive(Serialize, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+pub fn field_error(field: &str, message: impl Into<String>) -> FieldError {
+    FieldError { field: field.to_string(), message: message.into() }
+}
+
+/// 422 の本文
+pub fn validation_failed(errors: Vec<FieldError>) -> HttpResponse {
+    HttpResponse::UnprocessableEntity().json(json!({ "error": "validation_failed", "fields": errors }))
+}
+
+/// 保存前の検証。`id` は更新時の自分自身（slug 重複の判定から除く）
+pub async fn validate_problem(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    input: &ProblemInput,
+    id: Option<i64>,
+) -> anyhow::Result<Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    // slug: URL に載せるので英小文字・数字・ハイフンだけ
+    if input.slug.is_empty() {
+        errors.push(field_error("slug", "required"));
+    } else if !input.slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        errors.push(field_error("slug", "use only a-z, 0-9 and '-'"));
+    } else {
+        let dup: Option<(i64,)> = sqlx::query_as("SELECT id FROM problems WHERE slug = ? AND id IS NOT ?")
+            .bind(&input.slug)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        if let Some((other,)) = dup {
+            errors.push(field_error("slug", format!("already used by problem {other}")));
+        }
+    }
+
+    if input.title.trim().is_empty() {
+        errors.push(field_error("title", "required"));
+    }
+
+    // ★ 追加: 種類。初期コードはクイズ・並べ替え以外で必須
+    if !KINDS.contains(&input.kind.as_str()) {
+        errors.push(field_error("kind", format!("one of {}", KINDS.join(", "))));
+    }
+    match (&input.quiz, input.kind == KIND_QUIZ) {
+        (Some(q), true) => errors.extend(quiz::validate(q)),
+        (None, true) => errors.push(field_error("quiz", "required for quiz problems")),
+        (Some(_), false) => errors.push(field_error("quiz", "only for quiz problems")),
+        (None, false) => {}
+    }
+    match (&input.parsons, input.kind == KIND_PARSONS) {
+        (Some(p), true) => errors.extend(parsons::validate(p)),
+        (None, true) => errors.push(field_error("parsons", "required for parsons problems")),
+        (Some(_), false) => errors.push(field_error("parsons", "only for parsons problems")),
+        (None, false) => {}
+    }
+    if ![KIND_QUIZ, KIND_PARSONS].contains(&input.kind.as_str()) && input.starter_code.trim().is_empty() {
+        errors.push(field_error("starter_code", "required"));
+    }
+    // ★ 追加: 穴埋めは starter_code の穴と blanks が揃っていること
+    match (input.kind == KIND_BLANKS, input.blanks.is_empty()) {
+        (true, _) if !input.starter_code.trim().is_empty() => {
+            errors.extend(blanks::validate(&input.starter_code, &input.blanks));
+        }
+        (false, false) => errors.push(field_error("blanks", "only for blanks problems")),
+        _ => {}
+    }
+
+    for (field, v) in [
+        ("memory_limit_kb", input.memory_limit_kb),
+        ("cpu_time_limit_ms", input.cpu_time_limit_ms),
+        ("output_limit_bytes", input.output_limit_bytes),
+    ] {
+        if v.is_some_and(|n| n <= 0) {
+            errors.push(field_error(field, "must be positive"));
+        }
+    }
+
+    // 固定領域はエディタ側と同じく行単位（末尾空白・改行コードの差は無視）で探す
+    for (field, block) in [("fixed_top", &input.fixed_top), ("fixed_bottom", &input.fixed_bottom)] {
+        if let Some(b) = block.as_deref().filter(|b| !b.trim().is_empty()) {
+            if !contains_block(&input.starter_code, b) {
+                errors.push(field_error(field, "does not occur in starter_code"));
+            }
+        }
+    }
+
+    if input.difficulty.as_deref().is_some_and(|d| !DIFFICULTIES.contains(&d)) {
+        errors.push(field_error("difficulty", format!("one of {}", DIFFICULTIES.join(", "))));
+    }
+    if input.category.as_deref().is_some_and(|c| !CATEGORIES.contains(&c)) {
+        errors.push(field_error("category", format!("one of {}", CATEGORIES.join(", "))));
+    }
+    // タグは一覧の絞り込み（カンマ区切り）に使うので slug と同じ文字だけ
+    for (i, t) in input.tags.iter().enumerate() {
+        if t.is_empty() || !t.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            errors.push(field_error(&format!("tags[{i}]"), "use only a-z, 0-9 and '-'"));
+        }
+    }
+
+    for (i, h) in input.hints.iter().enumerate() {
+        if h.body.trim().is_empty() {
+            errors.push(field_error(&format!("hints[{i}].body"), "required"));
+        }
+        if h.penalty < 0 {
+            errors.push(field_error(&format!("hints[{i}].penalty"), "must not be negative"));
+        }
+    }
+
+    for (i, a) in input.answers.iter().enumerate() {
+        if a.title.trim().is_empty() {
+            errors.push(field_error(&format!("answers[{i}].title"), "required"));
+        }
+        if a.code.trim().is_empty() {
+            errors.push(field_error(&format!("answers[{i}].code"), "required"));
+        }
+    }
+
+    // 翻訳: 既定ロケールは problems 本体に書くので不可
+    for (l, t) in &input.translations {
+        if l == DEFAULT_LOCALE || !SUPPORTED_LOCALES.contains(&l.as_str()) {
+            let others: Vec<_> = SUPPORTED_LOCALES.iter().filter(|x| **x != DEFAULT_LOCALE).copied().collect();
+            let message = format!("locale must be one of {}", others.join(", "));
+            errors.push(field_error(&format!("translations.{l}"), message));
+        } else if t.title.trim().is_empty() {
+            errors.push(field_error(&format!("translations.{l}.title"), "required"));
+        }
+        if t.hints.len() > input.hints.len() {
+            errors.push(field_error(&format!("translations.{l}.hints"), "more hints than the original"));
+        }
+    }
+
+    let mut compile_ok = true;
+    if !SUPPORTED_EDITIONS.contains(&input.edition.as_str()) {
+        errors.push(field_error("edition", format!("one of {}", SUPPORTED_EDITIONS.join(", "))));
+        compile_ok = false;
+    }
+    if !SUPPORTED_OPT_LEVELS.contains(&input.opt_level.as_str()) {
+        errors.push(field_error("opt_level", format!("one of {}", SUPPORTED_OPT_LEVELS.join(", "))));
+        compile_ok = false;
+    }
+    let toolchain = toolchains.get(input.toolchain.as_deref());
+    if toolchain.is_none() {
+        errors.push(field_error("toolchain", "not installed on this server"));
+    }
+
+    // 初期コードがそのままコンパイルできること（★ 変更: 穴埋め・並べ替えは模範の中身から組み立てたもの）
+    let compile_target = match input.kind.as_str() {
+        KIND_CODE if !input.starter_code.trim().is_empty() => Some(("starter_code", input.starter_code.clone())),
+        KIND_BLANKS if !errors.iter().any(|e| e.field.starts_with("blanks") || e.field == "starter_code") => {
+            blanks::reference_code(&input.starter_code, &input.blanks).map(|c| ("blanks", c))
+        }
+        KIND_PARSONS if !errors.iter().any(|e| e.field.starts_with("parsons")) => {
+            input.parsons.as_ref().map(|p| ("parsons", parsons::assemble(&p.reference())))
+        }
+        _ => None,
+    };
+    if let (true, Some(tc), Some((field, code))) = (compile_ok, toolchain, compile_target) {
+        let opts = CompileOptions {
+            edition: input.edition.clone(),
+            opt_level: input.opt_level.clone(),
+            debug_assertions: input.debug_assertions,
+        };
+        if let Err(stderr) = runner::compile(&code, &opts, tc).await? {
+            errors.push(field_error(field, format!("does not compile:\n{stderr}")));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// `needle` の行が `haystack` に連続して現れるか
+pub fn contains_block(haystack: &str, needle: &str) -> bool {
+    let norm = |s: &str| -> Vec<String> {
+        s.replace("\r\n", "\n")
+            .split('\n')
+            .map(|l| l.replace('\t', "  ").trim_end().to_string())
+            .collect()
+    };
+    let hay = norm(haystack);
+    let mut sub = norm(needle);
+    while sub.last().is_some_and(|l| l.is_empty()) {
+        sub.pop();
+    }
+    !sub.is_empty() && hay.windows(sub.len()).any(|w| w == sub.as_slice())
+}
+
+/* ==================== 保存 ==================== */
+
+/// `id` が None なら作成、Some なら更新。test_cases は全置換
+pub async fn save_problem(pool: &SqlitePool, input: &ProblemInput, id: Option<i64>) -> sqlx::Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let id = match id {
+        None => {
+            sqlx::query(
+                r#"
+                INSERT INTO problems
+                  (slug, kind, title, description, starter_code, expected_stdout,
+                   fixed_top, fixed_bottom, editable_start_marker, editable_end_marker,
+                   memory_limit_kb, cpu_time_limit_ms, output_limit_bytes,
+                   edition, opt_level, debug_assertions, toolchain, solution_code,
+                   difficulty, category, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&input.slug)
+            .bind(&input.kind)
+            .bind(&input.title)
+            .bind(&input.description)
+            .bind(&input.starter_code)
+            .bind(&input.expected_stdout)
+            .bind(&input.fixed_top)
+            .bind(&input.fixed_bottom)
+            .bind(&input.editable_start_marker)
+            .bind(&input.editable_end_marker)
+            .bind(input.memory_limit_kb)
+            .bind(input.cpu_time_limit_ms)
+            .bind(input.output_limit_bytes)
+            .bind(&input.edition)
+            .bind(&input.opt_level)
+            .bind(input.debug_assertions)
+            .bind(&input.toolchain)
+            .bind(&input.solution_code)
+            .bind(&input.difficulty)
+            .bind(&input.category)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid()
+        }
+        Some(id) => {
+            sqlx::query(
+                r#"
+                UPDATE problems SET
+                  slug = ?, kind = ?, title = ?, description = ?, starter_code = ?, expected_stdout = ?,
+                  fixed_top = ?, fixed_bottom = ?, editable_start_marker = ?, editable_end_marker = ?,
+                  memory_limit_kb = ?, cpu_time_limit_ms = ?, output_limit_bytes = ?,
+                  edition = ?, opt_level = ?, debug_assertions = ?, toolchain = ?, solution_code = ?,
+                  difficulty = ?, category = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(&input.slug)
+            .bind(&input.kind)
+            .bind(&input.title)
+            .bind(&input.description)
+            .bind(&input.starter_code)
+            .bind(&input.expected_stdout)
+            .bind(&input.fixed_top)
+            .bind(&input.fixed_bottom)
+            .bind(&input.editable_start_marker)
+            .bind(&input.editable_end_marker)
+            .bind(input.memory_limit_kb)
+            .bind(input.cpu_time_limit_ms)
+            .bind(input.output_limit_bytes)
+            .bind(&input.edition)
+            .bind(&input.opt_level)
+            .bind(input.debug_assertions)
+            .bind(&input.toolchain)
+            .bind(&input.solution_code)
+            .bind(&input.difficulty)
+            .bind(&input.category)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            id
+        }
+    };
+
+    sqlx::query("DELETE FROM test_cases WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (i, tc) in input.test_cases.iter().enumerate() {
+        sqlx::query("INSERT INTO test_cases (problem_id, position, stdin, expected_stdout) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(i as i64)
+            .bind(&tc.stdin)
+            .bind(&tc.expected_stdout)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM problem_tags WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for tag in &input.tags {
+        sqlx::query("INSERT OR IGNORE INTO problem_tags (problem_id, tag) VALUES (?, ?)")
+            .bind(id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM problem_translations WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (locale, t) in &input.translations {
+        sqlx::query("INSERT INTO problem_translations (problem_id, locale, title, description) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(locale)
+            .bind(&t.title)
+            .bind(&t.description)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // ★ 追加: 段階的なヒント（開いた記録 hint_reveals は残す）
+    sqlx::query("DELETE FROM problem_hints WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (i, h) in input.hints.iter().enumerate() {
+        sqlx::query("INSERT INTO problem_hints (problem_id, position, body, penalty) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(i as i64)
+            .bind(&h.body)
+            .bind(h.penalty)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("DELETE FROM problem_hint_translations WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (locale, t) in &input.translations {
+        for (i, body) in t.hints.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO problem_hint_translations (problem_id, locale, position, body) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(locale)
+            .bind(i as i64)
+            .bind(body)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    // ★ 追加: 別解
+    sqlx::query("DELETE FROM problem_answers WHERE problem_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (i, a) in input.answers.iter().enumerate() {
+        sqlx::query("INSERT INTO problem_answers (problem_id, position, title, explanation, code) VALUES (?, ?, ?, ?, ?)")
+            .bind(id)
+            .bind(i as i64)
+            .bind(&a.title)
+            .bind(&a.explanation)
+            .bind(&a.code)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // ★ 追加: クイズの選択肢・正解
+    quiz::save_quiz(&mut tx, id, input.quiz.as_ref()).await?;
+    // ★ 追加: 穴埋めの穴
+    blanks::save_blanks(&mut tx, id, &input.blanks).await?;
+    // ★ 追加: 並べ替えの行
+    parsons::save_parsons(&mut tx, id, input.parsons.as_ref()).await?;
+
+    // ★ 追加: 内容が変わっていれば新しい版を積む
+    revision::record(&mut tx, id, input).await?;
+    search::reindex(&mut tx, Some(id)).await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/* ==================== ハンドラ ==================== */
+
+#[post("/api/admin/problems")]
+async fn create_problem(
+    _: TeacherAuth,
+    input: web::Json<ProblemInput>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match validate_problem(&state.pool, &state.toolchains, &input, None).await {
+        Ok(errors) if !errors.is_empty() => return validation_failed(errors),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("validation error: {e}")),
+    }
+    match save_problem(&state.pool, &input, None).await {
+        Ok(id) => HttpResponse::Created().json(json!({ "id": id })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[get("/api/admin/problems/{id}")]
+async fn get_problem_admin(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    match problem_detail(&state.pool, path.into_inner()).await {
+        Ok(v) => HttpResponse::Ok().json(v),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/// 編集画面用: 問題本体と、学習者向け API では返さないもの一式
+async fn problem_detail(pool: &SqlitePool, id: i64) -> sqlx::Result<serde_json::Value> {
+    let problem = fetch_problem(pool, id).await?;
+    Ok(json!({
+        "solution_code": problem.solution_code,
+        "test_cases": fetch_test_cases(pool, id).await?,
+        "translations": fetch_translations(pool, Some(id)).await?,
+        "hints": fetch_hints(pool, id).await?,
+        "answers": fetch_answers(pool, id).await?,
+        "quiz": fetch_quiz(pool, id).await?,
+        "blanks": fetch_blanks(pool, id).await?,
+        "parsons": fetch_parsons(pool, id).await?,
+        "problem": problem.with_rendered_description(),
+    }))
+}
+
+#[put("/api/admin/problems/{id}")]
+async fn update_problem(
+    _: TeacherAuth,
+    path: web::Path<i64>,
+    input: web::Json<ProblemInput>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match fetch_problem(&state.pool, id).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+    match validate_problem(&state.pool, &state.toolchains, &input, Some(id)).await {
+        Ok(errors) if !errors.is_empty() => return validation_failed(errors),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("validation error: {e}")),
+    }
+    match save_problem(&state.pool, &input, Some(id)).await {
+        Ok(id) => HttpResponse::Ok().json(json!({ "id": id })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[delete("/api/admin/problems/{id}")]
+async fn delete_problem(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+
+    // 提出履歴がある問題は消さない（submissions から参照されている）
+    let used: Result<(i64,), _> = sqlx::query_as("SELECT COUNT(*) FROM submissions WHERE problem_id = ?")
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await;
+    match used {
+        Ok((0,)) => {}
+        Ok((n,)) => {
+            return HttpResponse::Conflict()
+                .json(json!({ "error": "conflict", "message": format!("problem has {n} submissions") }))
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+
+    // test_cases は ON DELETE CASCADE
+    match sqlx::query("DELETE FROM problems WHERE id = ?").bind(id).execute(&state.pool).await {
+        Ok(r) if r.rows_affected() == 0 => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/* ==================== 自己検査 ==================== */
+
+#[post("/api/admin/problems/{id}/check")]
+async fn check_problem(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    let problem = match fetch_problem(&state.pool, path.into_inner()).await {
+        Ok(p) => p,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    match selfcheck::check_problem(&state.pool, &state.toolchains, &problem).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().body(format!("check error: {e}")),
+    }
+}
+
+#[post("/api/admin/check")]
+async fn check_all(_: TeacherAuth, state: web::Data<AppState>) -> impl Responder {
+    match selfcheck::check_all(&state.pool, &state.toolchains, &[]).await {
+        Ok(reports) => HttpResponse::Ok().json(reports),
+        Err(e) => HttpResponse::InternalServerError().body(format!("check error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_problem)
+        .service(get_problem_admin)
+        .service(update_problem)
+        .service(delete_problem)
+        .service(check_problem)
+        .service(check_all);
+}
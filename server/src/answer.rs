@@ -0,0 +1,204 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+
+use crate::account::CurrentUser;
+use crate::blanks::{self, fetch_blanks};
+use crate::course;
+use crate::judge::Verdict;
+use crate::markdown;
+use crate::parsons::{self, fetch_parsons};
+use crate::problem::{KIND_BLANKS, KIND_PARSONS};
+use crate::quiz::{fetch_quiz, Quiz};
+use crate::AppState;
+
+/* ==================== 模範解答の公開 ==================== */
+//
+// 模範解答（problems.solution_code）と別解（problem_answers）は、
+// ★ 変更: ログインした利用者自身に Accepted の提出があるか、自分でギブアップしたあとにだけ返す。
+
+/// 別解 1 つ（explanation は Markdown）
+#[derive(FromRow, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Answer {
+    pub title: String,
+    #[serde(default)]
+    pub explanation: String,
+    pub code: String,
+}
+
+pub async fn fetch_answers(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Vec<Answer>> {
+    sqlx::query_as("SELECT title, explanation, code FROM problem_answers WHERE problem_id = ? ORDER BY position")
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// 解答が見られるようになった理由
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockedBy {
+    Accepted,
+    GaveUp,
+}
+
+async fn unlocked_by(pool: &SqlitePool, problem_id: i64, user_id: i64) -> sqlx::Result<Option<UnlockedBy>> {
+    let (accepted, gave_up): (bool, bool) = sqlx::query_as(
+        r#"
+        SELECT
+          EXISTS (SELECT 1 FROM submissions WHERE problem_id = ?1 AND user_id = ?3 AND verdict = ?2),
+          EXISTS (SELECT 1 FROM problem_give_ups WHERE problem_id = ?1 AND user_id = ?3)
+        "#,
+    )
+    .bind(problem_id)
+    .bind(Verdict::Accepted.as_str())
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(match (accepted, gave_up) {
+        (true, _) => Some(UnlockedBy::Accepted),
+        (false, true) => Some(UnlockedBy::GaveUp),
+        _ => None,
+    })
+}
+
+#[derive(Serialize)]
+pub struct AnswerView {
+    pub title: String,
+    /// explanation をサニタイズ済み HTML にしたもの
+    pub explanation_html: String,
+    pub code: String,
+}
+
+/// 比較表示用: 自分のコードと解答
+#[derive(Serialize)]
+pub struct AnswerSheet {
+    pub unlocked_by: UnlockedBy,
+    /// 自分の最後の Accepted の提出（無ければ最後の提出）
+    pub own_code: Option<String>,
+    /// 模範解答（problems.solution_code。穴埋め・並べ替えで無ければ模範の中身から組み立てたもの）
+    pub reference: Option<String>,
+    pub answers: Vec<AnswerView>,
+    /// クイズなら正解付きの選択肢・受け付ける表記
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiz: Option<Quiz>,
+}
+
+/// 見られる状態なら解答一式。問題が無ければ Err(RowNotFound)、まだ見られなければ Ok(None)
+pub async fn answer_sheet(pool: &SqlitePool, problem_id: i64, user_id: i64) -> sqlx::Result<Option<AnswerSheet>> {
+    let (kind, template, reference): (String, String, Option<String>) =
+        sqlx::query_as("SELECT kind, starter_code, solution_code FROM problems WHERE id = ?")
+            .bind(problem_id)
+            .fetch_one(pool)
+            .await?;
+    let Some(unlocked_by) = unlocked_by(pool, problem_id, user_id).await? else {
+        return Ok(None);
+    };
+    let own: Option<(String,)> = sqlx::query_as(
+        r#"
+        SELECT code FROM submissions
+        WHERE problem_id = ? AND user_id = ? AND code IS NOT NULL
+        ORDER BY verdict = ? DESC, id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(problem_id)
+    .bind(user_id)
+    .bind(Verdict::Accepted.as_str())
+    .fetch_optional(pool)
+    .await?;
+    let answers = fetch_answers(pool, problem_id)
+        .await?
+        .into_iter()
+        .map(|a| AnswerView { explanation_html: markdown::render(&a.explanation), title: a.title, code: a.code })
+        .collect();
+
+    let mut own_code = own.map(|(c,)| c);
+    let mut reference = reference.filter(|c| !c.trim().is_empty());
+    // ★ 追加: 穴埋めは中身をテンプレートに差し込んだコードで比べる（模範解答が無ければ模範の中身から作る）
+    if kind == KIND_BLANKS {
+        own_code = own_code.and_then(|c| blanks::parse_stored(&c)).and_then(|v| blanks::fill(&template, &v));
+        if reference.is_none() {
+            reference = blanks::reference_code(&template, &fetch_blanks(pool, problem_id).await?);
+        }
+    }
+    // ★ 追加: 並べ替えも並べた行をつなげたコードで比べる
+    if kind == KIND_PARSONS {
+        own_code = own_code.and_then(|c| parsons::parse_stored(&c)).map(|p| parsons::assemble(&p));
+        if reference.is_none() {
+            reference = fetch_parsons(pool, problem_id).await?.map(|p| parsons::assemble(&p.reference()));
+        }
+    }
+
+    Ok(Some(AnswerSheet {
+        unlocked_by,
+        own_code,
+        reference,
+        answers,
+        quiz: fetch_quiz(pool, problem_id).await?,
+    }))
+}
+
+/* ==================== ハンドラ ==================== */
+
+fn locked() -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "error": "locked",
+        "message": "answers are available after an accepted submission or giving up",
+    }))
+}
+
+#[get("/api/problems/{id}/answers")]
+async fn get_answers(user: CurrentUser, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+    if let Err(resp) = course::ensure_unlocked(&state.pool, user.id, id).await {
+        return resp;
+    }
+    match answer_sheet(&state.pool, id, user.id).await {
+        Ok(Some(sheet)) => HttpResponse::Ok().json(sheet),
+        Ok(None) => locked(),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("[/api/problems/{id}/answers] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+/// ギブアップを記録して解答一式を返す（正解済み・ギブアップ済みなら記録しない）
+#[post("/api/problems/{id}/give-up")]
+async fn give_up(user: CurrentUser, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+    // ロック中の問題でギブアップして解答を見ることはできない
+    if let Err(resp) = course::ensure_unlocked(&state.pool, user.id, id).await {
+        return resp;
+    }
+    let recorded = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO problem_give_ups (user_id, problem_id, given_up_at)
+        SELECT ?1, id, ?2 FROM problems
+        WHERE id = ?3
+          AND NOT EXISTS (SELECT 1 FROM submissions WHERE problem_id = problems.id AND user_id = ?1 AND verdict = ?4)
+        "#,
+    )
+    .bind(user.id)
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .bind(Verdict::Accepted.as_str())
+    .execute(&state.pool)
+    .await;
+    if let Err(e) = recorded {
+        return HttpResponse::InternalServerError().body(format!("db error: {e}"));
+    }
+    match answer_sheet(&state.pool, id, user.id).await {
+        Ok(Some(sheet)) => HttpResponse::Ok().json(sheet),
+        Ok(None) => locked(),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_answers).service(give_up);
+}
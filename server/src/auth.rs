@@ -0,0 +1,164 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::InternalError,
+    middleware::Next,
+    web, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::account::CurrentUser;
+use crate::AppState;
+
+/* ==================== 役割と認可 ==================== */
+//
+// 利用者は student / teacher / admin のどれか（users.role）。上の役割は下の役割のことが全部できる。
+//   student: 問題を解く。見られる提出は自分のものだけ
+//   teacher: 問題・コースの管理、全員の提出・集計（/api/admin/ 以下）
+//   admin:   teacher に加えて利用者の役割の変更
+// 管理 API はセッション Cookie のほか、環境変数 ADMIN_TOKEN と同じ値を `Authorization: Bearer <token>` で
+// 送っても使える（CI・スクリプト用。admin として扱う）。ADMIN_TOKEN が未設定ならトークンでは入れない。
+//
+// ハンドラでは引数に TeacherAuth / AdminAuth を置く。加えて /api/admin/ 以下は guard_admin_api が
+// teacher 以上に限るので、引数を付け忘れたハンドラも学習者には開かない。
+
+#[derive(sqlx::Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Student,
+    Teacher,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Student => "student",
+            Role::Teacher => "teacher",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Role> {
+        match s {
+            "student" => Some(Role::Student),
+            "teacher" => Some(Role::Teacher),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// 管理 API の呼び出し元
+#[derive(Clone, Debug)]
+pub enum Caller {
+    /// ADMIN_TOKEN
+    Token,
+    User(CurrentUser),
+}
+
+impl Caller {
+    pub fn role(&self) -> Role {
+        match self {
+            Caller::Token => Role::Admin,
+            Caller::User(u) => u.role,
+        }
+    }
+}
+
+/// Bearer があればトークンで、無ければセッションで呼び出し元を決める（1 リクエストで 1 回だけ引く）
+async fn caller(req: &HttpRequest) -> Result<Caller, actix_web::Error> {
+    if let Some(c) = req.extensions().get::<Caller>() {
+        return Ok(c.clone());
+    }
+    let bearer = req
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let caller = match bearer {
+        Some(given) => {
+            let expected = req.app_data::<web::Data<AppState>>().and_then(|s| s.admin_token.clone());
+            match expected {
+                Some(t) if constant_time_eq(given.as_bytes(), t.as_bytes()) => Caller::Token,
+                Some(_) => return Err(unauthorized("invalid admin token")),
+                None => return Err(unauthorized("admin token is disabled (ADMIN_TOKEN is not set)")),
+            }
+        }
+        None => Caller::User(CurrentUser::extract(req).await?),
+    };
+    req.extensions_mut().insert(caller.clone());
+    Ok(caller)
+}
+
+/// role 以上でなければ 403
+async fn require(req: &HttpRequest, role: Role) -> Result<Caller, actix_web::Error> {
+    let caller = caller(req).await?;
+    if caller.role() < role {
+        return Err(forbidden(role));
+    }
+    Ok(caller)
+}
+
+/// ハンドラの引数に置くと teacher 以上（または ADMIN_TOKEN）を要求する
+pub struct TeacherAuth;
+
+/// ハンドラの引数に置くと admin（または ADMIN_TOKEN）を要求する
+pub struct AdminAuth;
+
+impl FromRequest for TeacherAuth {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { require(&req, Role::Teacher).await.map(|_| TeacherAuth) })
+    }
+}
+
+impl FromRequest for AdminAuth {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { require(&req, Role::Admin).await.map(|_| AdminAuth) })
+    }
+}
+
+/// /api/admin/ 以下は teacher 以上に限る（App 全体に wrap する）
+pub async fn guard_admin_api(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if req.path().starts_with("/api/admin/") {
+        require(req.request(), Role::Teacher).await?;
+    }
+    next.call(req).await
+}
+
+/// 401 の本文（利用者のログインでも使う）
+pub fn unauthorized(message: &str) -> actix_web::Error {
+    let resp = HttpResponse::Unauthorized().json(json!({ "error": "unauthorized", "message": message }));
+    InternalError::from_response(message.to_string(), resp).into()
+}
+
+/// 403 の本文（ログインはしているが役割が足りない）
+pub fn forbidden(required: Role) -> actix_web::Error {
+    let message = format!("{} role required", required.as_str());
+    let resp = HttpResponse::Forbidden().json(json!({
+        "error": "forbidden",
+        "message": message,
+        "required_role": required,
+    }));
+    InternalError::from_response(message, resp).into()
+}
+
+/// 比較にかかる時間から一致長が漏れないように
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
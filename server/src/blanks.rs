@@ -0,0 +1,262 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::admin::{field_error, FieldError};
+use crate::judge::{judge_code, JudgeResult};
+use crate::problem::{Problem, TestCase};
+use crate::toolchain::Toolchain;
+
+/* ==================== 穴埋め ==================== */
+//
+// kind = "blanks" の問題。starter_code をテンプレートとして、その中の `___`
+// （アンダースコア 3 つで、前後が識別子の文字でないもの）を穴とみなす。
+// 学習者は穴の中身だけを /api/run に送り、サーバがテンプレートに差し込んでコード問題と同じく判定する。
+// 穴ごとに模範の中身（answer）と、任意で中身の形を縛る正規表現（pattern）を problem_blanks に持つ。
+// 提出は submissions.code に中身の JSON（{"blanks": [...]}）を残し、再判定では最新のテンプレートに差し込み直す。
+
+pub const MARKER: &str = "___";
+
+/// 穴 1 つ分（管理 API の入力・problem.toml の [[blanks]] と同じ形）
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Blank {
+    /// 模範の中身
+    pub answer: String,
+    /// 中身全体がこれに一致しなければ、コンパイルせずに WrongAnswer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+/// 学習者向け（模範の中身・制約は含めない）
+#[derive(Serialize)]
+pub struct BlanksView {
+    /// テンプレートを穴で区切ったもの（穴の数 + 1 個）
+    pub parts: Vec<String>,
+}
+
+/// テンプレートを穴で区切る（穴が n 個なら n + 1 個）
+pub fn split_template(template: &str) -> Vec<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut parts = Vec::new();
+    let (mut start, mut from) = (0, 0);
+    while let Some(i) = template[from..].find(MARKER).map(|i| from + i) {
+        let end = i + MARKER.len();
+        from = end;
+        // `____` や `x___` は穴ではない
+        let before = template[..i].chars().next_back();
+        let after = template[end..].chars().next();
+        if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            continue;
+        }
+        parts.push(&template[start..i]);
+        start = end;
+    }
+    parts.push(&template[start..]);
+    parts
+}
+
+pub fn view(template: &str) -> BlanksView {
+    BlanksView { parts: split_template(template).into_iter().map(str::to_string).collect() }
+}
+
+/// 穴に中身を差し込む（数が合わなければ None）
+pub fn fill(template: &str, values: &[String]) -> Option<String> {
+    let parts = split_template(template);
+    if parts.len() != values.len() + 1 {
+        return None;
+    }
+    let mut code = parts[0].to_string();
+    for (v, p) in values.iter().zip(&parts[1..]) {
+        code.push_str(v);
+        code.push_str(p);
+    }
+    Some(code)
+}
+
+/// 模範の中身を差し込んだコード
+pub fn reference_code(template: &str, blanks: &[Blank]) -> Option<String> {
+    let answers: Vec<String> = blanks.iter().map(|b| b.answer.trim().to_string()).collect();
+    fill(template, &answers)
+}
+
+/// pattern は中身全体に当てる
+fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{pattern})$"))
+}
+
+/* ==================== 判定 ==================== */
+
+/// submissions.code に残す形（判定と同じく前後の空白は落とす）
+pub fn stored(values: &[String]) -> String {
+    let values: Vec<&str> = values.iter().map(|v| v.trim()).collect();
+    json!({ "blanks": values }).to_string()
+}
+
+/// submissions.code から中身を読む（読めなければ None）
+pub fn parse_stored(code: &str) -> Option<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Stored {
+        blanks: Vec<String>,
+    }
+    serde_json::from_str::<Stored>(code).ok().map(|s| s.blanks)
+}
+
+/// 中身の数・形を確かめる。だめなら学習者に見せる理由
+fn check(blanks: &[Blank], values: &[String]) -> Result<(), String> {
+    if values.len() != blanks.len() {
+        return Err(format!("Expected {} blanks, got {}", blanks.len(), values.len()));
+    }
+    for (i, (b, v)) in blanks.iter().zip(values).enumerate() {
+        let n = i + 1;
+        if v.is_empty() {
+            return Err(format!("Blank {n} is empty"));
+        }
+        if v.contains('\n') {
+            return Err(format!("Blank {n} must fit on one line"));
+        }
+        if let Some(p) = &b.pattern {
+            if !compile_pattern(p).is_ok_and(|re| re.is_match(v)) {
+                return Err(format!("Blank {n} does not have the expected form"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 中身を確かめてからテンプレートに差し込んで判定する（形が違えばコンパイルせずに WrongAnswer）
+pub async fn judge_blanks(
+    problem: &Problem,
+    cases: &[TestCase],
+    blanks: &[Blank],
+    values: &[String],
+    toolchain: &Toolchain,
+) -> anyhow::Result<JudgeResult> {
+    let values: Vec<String> = values.iter().map(|v| v.trim().to_string()).collect();
+    let code = check(blanks, &values).and_then(|()| {
+        fill(&problem.starter_code, &values).ok_or_else(|| "The template does not match the blanks".to_string())
+    });
+    match code {
+        Ok(code) => judge_code(problem, cases, &code, toolchain).await,
+        Err(message) => Ok(JudgeResult::rejected(message)),
+    }
+}
+
+/* ==================== 検証・保存 ==================== */
+
+/// テンプレートの穴と定義が揃っているか（テンプレートは starter_code）
+pub fn validate(template: &str, blanks: &[Blank]) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let count = split_template(template).len() - 1;
+    if count == 0 {
+        errors.push(field_error("starter_code", format!("no blanks ({MARKER}) in the template")));
+    } else if count != blanks.len() {
+        errors.push(field_error("blanks", format!("the template has {count} blanks but {} are defined", blanks.len())));
+    }
+    for (i, b) in blanks.iter().enumerate() {
+        if b.answer.trim().is_empty() {
+            errors.push(field_error(&format!("blanks[{i}].answer"), "required"));
+        } else if b.answer.contains('\n') {
+            errors.push(field_error(&format!("blanks[{i}].answer"), "must fit on one line"));
+        }
+        match b.pattern.as_deref().map(compile_pattern) {
+            Some(Err(e)) => errors.push(field_error(&format!("blanks[{i}].pattern"), format!("invalid regex: {e}"))),
+            Some(Ok(re)) if !re.is_match(b.answer.trim()) => {
+                errors.push(field_error(&format!("blanks[{i}].answer"), "does not match the pattern"));
+            }
+            _ => {}
+        }
+    }
+    errors
+}
+
+pub async fn fetch_blanks(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Vec<Blank>> {
+    let rows: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT answer, pattern FROM problem_blanks WHERE problem_id = ? ORDER BY position")
+            .bind(problem_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().map(|(answer, pattern)| Blank { answer, pattern }).collect())
+}
+
+/// 全置換。save_problem のトランザクション内で呼ぶ
+pub async fn save_blanks(conn: &mut SqliteConnection, problem_id: i64, blanks: &[Blank]) -> sqlx::Result<()> {
+    sqlx::query("DELETE FROM problem_blanks WHERE problem_id = ?")
+        .bind(problem_id)
+        .execute(&mut *conn)
+        .await?;
+    for (i, b) in blanks.iter().enumerate() {
+        sqlx::query("INSERT INTO problem_blanks (problem_id, position, answer, pattern) VALUES (?, ?, ?, ?)")
+            .bind(problem_id)
+            .bind(i as i64)
+            .bind(&b.answer)
+            .bind(&b.pattern)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_on_blanks_only() {
+        assert_eq!(split_template("let x: ___ = ___;"), ["let x: ", " = ", ";"]);
+        assert_eq!(split_template("no blanks"), ["no blanks"]);
+        assert_eq!(split_template("___"), ["", ""]);
+        // 識別子の一部や 4 つ以上のアンダースコアは穴ではない
+        assert_eq!(split_template("let ____ = x___ + ___y + _x___;"), ["let ____ = x___ + ___y + _x___;"]);
+        // 全角文字に挟まれていても位置はずれない
+        assert_eq!(split_template("「___」"), ["「", "」"]);
+    }
+
+    #[test]
+    fn blanks_next_to_lifetimes() {
+        // 'a の直後の空白や & の直後は穴、'a に続けて書いたものは識別子の一部
+        assert_eq!(split_template("fn f<'a>(x: &___ str) -> &'a ___"), ["fn f<'a>(x: &", " str) -> &'a ", ""]);
+        assert_eq!(split_template("&'___ str"), ["&'", " str"]);
+        assert_eq!(split_template("&'a___ str"), ["&'a___ str"]);
+        assert_eq!(
+            fill("fn longest<'a>(x: &'a str, y: &___ str) -> &'a str", &strings(&["'a"])).as_deref(),
+            Some("fn longest<'a>(x: &'a str, y: &'a str) -> &'a str")
+        );
+    }
+
+    #[test]
+    fn fill_needs_one_value_per_blank() {
+        let template = "let v: ___ = vec![___];";
+        assert_eq!(fill(template, &strings(&["Vec<i32>", "1, 2"])).as_deref(), Some("let v: Vec<i32> = vec![1, 2];"));
+        assert_eq!(fill(template, &strings(&["Vec<i32>"])), None);
+        assert_eq!(fill(template, &strings(&["a", "b", "c"])), None);
+        assert_eq!(fill("no blanks", &[]).as_deref(), Some("no blanks"));
+    }
+
+    #[test]
+    fn stored_values_round_trip() {
+        let code = stored(&strings(&["  'a ", "Vec<&str>"]));
+        assert_eq!(parse_stored(&code), Some(strings(&["'a", "Vec<&str>"])));
+        // 穴埋めにする前のコードや壊れた JSON は読めない
+        assert_eq!(parse_stored("fn main() {}"), None);
+        assert_eq!(parse_stored(r#"{"blanks": [1]}"#), None);
+        assert_eq!(parse_stored(r#"{"code": "x"}"#), None);
+    }
+
+    #[test]
+    fn check_rejects_wrong_shapes() {
+        let blanks = [
+            Blank { answer: "'a".into(), pattern: Some("'a".into()) },
+            Blank { answer: "Vec<&str>".into(), pattern: None },
+        ];
+        assert_eq!(check(&blanks, &strings(&["'a", "Vec<_>"])), Ok(()));
+        assert!(check(&blanks, &strings(&["'a"])).is_err());
+        assert_eq!(check(&blanks, &strings(&["'b", "x"])), Err("Blank 1 does not have the expected form".into()));
+        assert_eq!(check(&blanks, &strings(&["'a", ""])), Err("Blank 2 is empty".into()));
+        assert_eq!(check(&blanks, &strings(&["'a", "a\nb"])), Err("Blank 2 must fit on one line".into()));
+    }
+}
@@ -0,0 +1,278 @@
+use clap::{Parser, Subcommand};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+use crate::account;
+use crate::admin::FieldError;
+use crate::auth::Role;
+use crate::judge::judge_code;
+use crate::problem::{fetch_problem, judge_cases, resolve_slug, Problem};
+use crate::rejudge::{self, Selection};
+use crate::toolchain::ToolchainRegistry;
+
+/* ==================== 管理用サブコマンド ==================== */
+//
+// Web を経由せずコンテナ内で使う（`docker compose exec app /app/server <cmd>`）。
+// 引数なし / `serve` なら従来どおりサーバを起動する。
+
+#[derive(Parser)]
+#[command(name = "server", about = "Rust 学習システム（引数なしで Web サーバを起動）")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Web サーバを起動する
+    Serve,
+    /// DB を作成してマイグレーションを適用する（--problems で問題パッケージも取り込む）
+    InitDb {
+        #[arg(long)]
+        problems: Option<PathBuf>,
+        /// コース定義のディレクトリ（問題の取り込みのあとに読む）
+        #[arg(long)]
+        courses: Option<PathBuf>,
+    },
+    /// マイグレーションを適用する
+    Migrate {
+        #[command(subcommand)]
+        action: Option<MigrateAction>,
+    },
+    /// 問題パッケージのディレクトリを DB に同期する
+    Import {
+        dir: PathBuf,
+        /// パッケージに無い問題を消す（提出のある問題は残す）
+        #[arg(long)]
+        prune: bool,
+        /// 差分の表示だけ
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// DB の問題をパッケージとして書き出す
+    Export { dir: PathBuf },
+    /// コース定義（<dir>/*.toml）を DB に同期する
+    ImportCourses { dir: PathBuf },
+    /// 模範解答・初期コードを判定に通して問題を検査する
+    Check { slugs: Vec<String> },
+    /// ローカルのファイルを問題に対して判定する（提出としては保存しない）
+    Judge {
+        /// slug または id
+        problem: String,
+        file: PathBuf,
+        /// 問題の設定より優先するツールチェーン
+        #[arg(long)]
+        toolchain: Option<String>,
+    },
+    /// 保存済みの提出を問題の最新版で判定し直す（再判定ジョブとして記録する）
+    Rejudge {
+        /// slug または id（省略時は全問題）
+        #[arg(long)]
+        problem: Option<String>,
+        /// 提出 id（複数指定可）
+        #[arg(long = "submission")]
+        submissions: Vec<i64>,
+        /// 最新版より前の版で判定された提出だけ
+        #[arg(long)]
+        stale: bool,
+        /// 結果の表示だけ（DB は更新しない）
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 全文検索の索引を作り直す（DB を直接編集したあとなど）
+    Reindex,
+    /// 利用者を作る（パスワードは標準入力の 1 行目から読む）
+    CreateUser {
+        username: String,
+        /// 表示名（省略時は username）
+        #[arg(long)]
+        display_name: Option<String>,
+        /// student / teacher / admin
+        #[arg(long, default_value = "student")]
+        role: String,
+    },
+    /// 利用者の役割を変える（student / teacher / admin）
+    SetRole { username: String, role: String },
+    /// 利用者のパスワードを置き換えてログアウトさせる（パスワードは標準入力の 1 行目から読む）
+    SetPassword { username: String },
+    /// DB のスナップショットを取る（稼働中でも可）
+    Backup {
+        /// 出力先（省略時は DB と同じディレクトリに backup-<日時>.db）
+        dest: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateAction {
+    /// 適用状況の表示だけ
+    Status,
+}
+
+/// slug（旧 slug も可）でも id でも問題を引けるように
+pub async fn find_problem(pool: &SqlitePool, key: &str) -> anyhow::Result<Problem> {
+    if let Some((id, _)) = resolve_slug(pool, key).await? {
+        return Ok(fetch_problem(pool, id).await?);
+    }
+    let Ok(id) = key.parse::<i64>() else {
+        anyhow::bail!("no such problem: {key}");
+    };
+    match fetch_problem(pool, id).await {
+        Err(sqlx::Error::RowNotFound) => anyhow::bail!("no such problem: {key}"),
+        r => Ok(r?),
+    }
+}
+
+/* ==================== judge ==================== */
+
+pub async fn judge_file(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    key: &str,
+    file: &Path,
+    toolchain: Option<&str>,
+) -> anyhow::Result<()> {
+    let problem = find_problem(pool, key).await?;
+    let code = std::fs::read_to_string(file).map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+    let name = toolchain.or(problem.toolchain.as_deref());
+    let Some(tc) = toolchains.get(name) else {
+        anyhow::bail!("toolchain not installed: {}", name.unwrap_or_default());
+    };
+
+    let cases = judge_cases(pool, &problem).await?;
+    let result = judge_code(&problem, &cases, &code, tc).await?;
+
+    println!("problem:   {} ({})", problem.slug, problem.id);
+    println!("toolchain: {}", tc.version);
+    for c in &result.cases {
+        let usage = c
+            .usage
+            .map(|u| format!("  {} ms, {} KiB", u.cpu_time_ms(), u.max_rss_kb))
+            .unwrap_or_default();
+        println!("  case {:<3} {}{usage}", c.position, c.verdict.as_str());
+    }
+    println!("verdict:   {}", result.verdict.as_str());
+    if !result.output.is_empty() {
+        println!("---\n{}", result.output.trim_end());
+    }
+    Ok(())
+}
+
+/* ==================== rejudge ==================== */
+
+/// 再判定ジョブを作ってその場で流し、判定が変わった提出を表示する
+pub async fn rejudge(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    key: Option<&str>,
+    submission_ids: Vec<i64>,
+    only_stale: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let problem_id = match key {
+        Some(k) => Some(find_problem(pool, k).await?.id),
+        None => None,
+    };
+    let selection = Selection { problem_id, submission_ids, only_stale };
+    let id = rejudge::create_job(pool, &selection, dry_run).await?;
+    rejudge::run_job(pool, toolchains, id).await?;
+
+    for c in rejudge::job_changes(pool, id).await? {
+        let old = c.old_verdict.as_deref().unwrap_or("-");
+        let rev = |r: Option<i64>| r.map_or("?".to_string(), |r| r.to_string());
+        println!(
+            "#{:<5} {}: {old} (r{}) -> {} (r{})",
+            c.submission_id,
+            c.slug,
+            rev(c.old_revision),
+            c.new_verdict,
+            rev(c.new_revision)
+        );
+    }
+    let job = rejudge::fetch_job(pool, id).await?;
+    println!(
+        "job {id}: {} submissions rejudged, {} verdicts changed{}",
+        job.judged,
+        job.changed,
+        if dry_run { " (dry run)" } else { "" }
+    );
+    Ok(())
+}
+
+/* ==================== users ==================== */
+
+/// パスワードはコマンドライン引数だとシェルの履歴に残るので標準入力から
+fn read_password() -> anyhow::Result<String> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    check(account::validate_password("password", &password))?;
+    Ok(password)
+}
+
+fn check(errors: Vec<FieldError>) -> anyhow::Result<()> {
+    match errors.first() {
+        Some(e) => anyhow::bail!("{}: {}", e.field, e.message),
+        None => Ok(()),
+    }
+}
+
+fn parse_role(role: &str) -> anyhow::Result<Role> {
+    Role::parse(role).ok_or_else(|| anyhow::anyhow!("unknown role: {role} (student / teacher / admin)"))
+}
+
+pub async fn create_user(
+    pool: &SqlitePool,
+    username: &str,
+    display_name: Option<&str>,
+    role: &str,
+) -> anyhow::Result<()> {
+    check(account::validate_username(username))?;
+    let role = parse_role(role)?;
+    let hash = account::hash_password(&read_password()?)?;
+    let Some(user) = account::create_user(pool, username, display_name.unwrap_or_default(), Some(&hash), role).await?
+    else {
+        anyhow::bail!("username already taken: {username}");
+    };
+    println!("user {} created ({}, {})", user.username, user.id, user.role.as_str());
+    Ok(())
+}
+
+pub async fn set_role(pool: &SqlitePool, username: &str, role: &str) -> anyhow::Result<()> {
+    let role = parse_role(role)?;
+    let Some((id, _)) = account::find_credentials(pool, username).await? else {
+        anyhow::bail!("no such user: {username}");
+    };
+    account::set_role(pool, id, role).await?;
+    println!("{username} is now {}", role.as_str());
+    Ok(())
+}
+
+pub async fn set_password(pool: &SqlitePool, username: &str) -> anyhow::Result<()> {
+    let Some((id, _)) = account::find_credentials(pool, username).await? else {
+        anyhow::bail!("no such user: {username}");
+    };
+    let hash = account::hash_password(&read_password()?)?;
+    account::set_password(pool, id, &hash).await?;
+    account::end_sessions(pool, id, None).await?;
+    println!("password of {username} changed");
+    Ok(())
+}
+
+/* ==================== backup ==================== */
+
+/// VACUUM INTO で一貫したコピーを作る（WAL の内容も含まれる）
+pub async fn backup(pool: &SqlitePool, db_path: &str, dest: Option<PathBuf>) -> anyhow::Result<()> {
+    let dest = dest.unwrap_or_else(|| {
+        let dir = Path::new(db_path).parent().unwrap_or(Path::new("."));
+        dir.join(format!("backup-{}.db", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+    });
+    if dest.exists() {
+        anyhow::bail!("{} already exists", dest.display());
+    }
+    sqlx::query("VACUUM INTO ?")
+        .bind(dest.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+    println!("backup written to {}", dest.display());
+    Ok(())
+}
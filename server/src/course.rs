@@ -0,0 +1,800 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::account::CurrentUser;
+use crate::admin::{field_error, validation_failed, FieldError};
+use crate::auth::TeacherAuth;
+use crate::judge::Verdict;
+use crate::locale::{self, LangQuery};
+use crate::problem::{fetch_translations, pick_translation, resolve_slug};
+use crate::AppState;
+
+/* ==================== コース ==================== */
+//
+// コース = 順序付きの単元、単元 = 順序付きの問題。
+// 前提条件（コース内の問題同士の辺）の問題にすべて正解するまで、その問題は locked。
+// sequential な単元は「単元内の 1 つ前の問題」を前提条件に加える。
+//
+// 正解済み = ログインした利用者自身の Accepted の提出がある（ログインしていなければ何も解いていない扱い）。
+
+/// 作成・更新の本文（全置換）。問題は slug で指定する
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CourseInput {
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// 一覧での並び順（小さいほど先）
+    #[serde(default)]
+    pub position: i64,
+    #[serde(default)]
+    pub units: Vec<UnitInput>,
+    /// 問題の slug → 先に正解しておく問題の slug
+    #[serde(default)]
+    pub prerequisites: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UnitInput {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub sequential: bool,
+    #[serde(default)]
+    pub problems: Vec<String>,
+}
+
+/// 学習者から見た問題の状態
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemState {
+    Locked,
+    Unlocked,
+    Solved,
+}
+
+/* ==================== 検証 ==================== */
+
+/// input 中の問題 slug（旧 slug も可）→ 問題 id。見つからない slug は入らない
+async fn resolve_problems(pool: &SqlitePool, input: &CourseInput) -> sqlx::Result<HashMap<String, i64>> {
+    let mut ids = HashMap::new();
+    let slugs = input.units.iter().flat_map(|u| &u.problems);
+    for slug in slugs.chain(input.prerequisites.iter().flat_map(|(k, v)| std::iter::once(k).chain(v))) {
+        if ids.contains_key(slug) {
+            continue;
+        }
+        if let Some((id, _)) = resolve_slug(pool, slug).await? {
+            ids.insert(slug.clone(), id);
+        }
+    }
+    Ok(ids)
+}
+
+/// 保存前の検証。`id` は更新時の自分自身（slug 重複の判定から除く）
+pub async fn validate_course(
+    pool: &SqlitePool,
+    input: &CourseInput,
+    id: Option<i64>,
+) -> sqlx::Result<Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if input.slug.is_empty() {
+        errors.push(field_error("slug", "required"));
+    } else if !input.slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        errors.push(field_error("slug", "use only a-z, 0-9 and '-'"));
+    } else {
+        let dup: Option<(i64,)> = sqlx::query_as("SELECT id FROM courses WHERE slug = ? AND id IS NOT ?")
+            .bind(&input.slug)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+        if let Some((other,)) = dup {
+            errors.push(field_error("slug", format!("already used by course {other}")));
+        }
+    }
+    if input.title.trim().is_empty() {
+        errors.push(field_error("title", "required"));
+    }
+
+    // 問題: 存在すること、コース内で 1 回だけ
+    let ids = resolve_problems(pool, input).await?;
+    let mut seen = HashSet::new();
+    for (i, u) in input.units.iter().enumerate() {
+        if u.title.trim().is_empty() {
+            errors.push(field_error(&format!("units[{i}].title"), "required"));
+        }
+        for (j, slug) in u.problems.iter().enumerate() {
+            let field = format!("units[{i}].problems[{j}]");
+            match ids.get(slug) {
+                None => errors.push(field_error(&field, format!("no such problem: {slug}"))),
+                Some(pid) if !seen.insert(*pid) => {
+                    errors.push(field_error(&field, format!("{slug} appears more than once")))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    // 前提条件: コース内の問題同士、自分自身は不可
+    let mut explicit = Vec::new();
+    for (slug, requires) in &input.prerequisites {
+        let field = format!("prerequisites.{slug}");
+        let Some(pid) = ids.get(slug).filter(|p| seen.contains(p)) else {
+            errors.push(field_error(&field, format!("{slug} is not in this course")));
+            continue;
+        };
+        for r in requires {
+            match ids.get(r).filter(|p| seen.contains(p)) {
+                None => errors.push(field_error(&field, format!("{r} is not in this course"))),
+                Some(rid) if rid == pid => errors.push(field_error(&field, "a problem cannot require itself")),
+                Some(rid) => explicit.push((*pid, *rid)),
+            }
+        }
+    }
+    // 循環があると輪の中の問題が永遠に開かない
+    if errors.is_empty() {
+        let units: Vec<(bool, Vec<i64>)> =
+            input.units.iter().map(|u| (u.sequential, u.problems.iter().map(|s| ids[s]).collect())).collect();
+        if has_cycle(&edges(&units, &explicit)) {
+            errors.push(field_error("prerequisites", "prerequisites form a cycle"));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// (問題, 前提の問題) の組。sequential な単元は 1 つ前の問題を前提にする
+fn edges(units: &[(bool, Vec<i64>)], explicit: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut out = explicit.to_vec();
+    for (sequential, problems) in units {
+        if *sequential {
+            out.extend(problems.windows(2).map(|w| (w[1], w[0])));
+        }
+    }
+    out
+}
+
+/// 前提をたどって自分に戻れるか（前提の無い問題から順に外していき、残ったら循環）
+fn has_cycle(edges: &[(i64, i64)]) -> bool {
+    let mut remaining: Vec<(i64, i64)> = edges.to_vec();
+    loop {
+        let before = remaining.len();
+        let blocked: HashSet<i64> = remaining.iter().map(|(p, _)| *p).collect();
+        remaining.retain(|(_, r)| blocked.contains(r));
+        if remaining.is_empty() {
+            return false;
+        }
+        if remaining.len() == before {
+            return true;
+        }
+    }
+}
+
+/* ==================== 保存 ==================== */
+
+/// `id` が None なら作成、Some なら更新。単元・前提条件は全置換
+pub async fn save_course(pool: &SqlitePool, input: &CourseInput, id: Option<i64>) -> sqlx::Result<i64> {
+    let ids = resolve_problems(pool, input).await?;
+    let mut tx = pool.begin().await?;
+
+    let id = match id {
+        None => sqlx::query(
+            "INSERT INTO courses (slug, title, description, position, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&input.slug)
+        .bind(&input.title)
+        .bind(&input.description)
+        .bind(input.position)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid(),
+        Some(id) => {
+            sqlx::query("UPDATE courses SET slug = ?, title = ?, description = ?, position = ? WHERE id = ?")
+                .bind(&input.slug)
+                .bind(&input.title)
+                .bind(&input.description)
+                .bind(input.position)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            id
+        }
+    };
+
+    // course_problems は ON DELETE CASCADE
+    sqlx::query("DELETE FROM course_units WHERE course_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (i, u) in input.units.iter().enumerate() {
+        let unit_id = sqlx::query(
+            "INSERT INTO course_units (course_id, position, title, description, sequential) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(i as i64)
+        .bind(&u.title)
+        .bind(&u.description)
+        .bind(u.sequential)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        for (j, slug) in u.problems.iter().enumerate() {
+            let Some(pid) = ids.get(slug) else { continue };
+            sqlx::query("INSERT INTO course_problems (unit_id, problem_id, position) VALUES (?, ?, ?)")
+                .bind(unit_id)
+                .bind(pid)
+                .bind(j as i64)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    sqlx::query("DELETE FROM course_prerequisites WHERE course_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (slug, requires) in &input.prerequisites {
+        let Some(pid) = ids.get(slug) else { continue };
+        for rid in requires.iter().filter_map(|r| ids.get(r)) {
+            sqlx::query(
+                "INSERT OR IGNORE INTO course_prerequisites (course_id, problem_id, requires_id) VALUES (?, ?, ?)",
+            )
+            .bind(id)
+            .bind(pid)
+            .bind(rid)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/* ==================== 読み出し ==================== */
+
+#[derive(FromRow)]
+struct CourseRow {
+    id: i64,
+    slug: String,
+    title: String,
+    description: String,
+    position: i64,
+}
+
+#[derive(FromRow)]
+struct UnitRow {
+    id: i64,
+    title: String,
+    description: String,
+    sequential: bool,
+}
+
+#[derive(FromRow)]
+struct CourseProblemRow {
+    unit_id: i64,
+    id: i64,
+    slug: String,
+    title: String,
+    difficulty: Option<String>,
+}
+
+struct Loaded {
+    course: CourseRow,
+    units: Vec<UnitRow>,
+    problems: Vec<CourseProblemRow>,
+    /// (問題, 前提の問題)。sequential の分は含まない
+    prerequisites: Vec<(i64, i64)>,
+}
+
+async fn load(pool: &SqlitePool, course: CourseRow) -> sqlx::Result<Loaded> {
+    let units = sqlx::query_as::<_, UnitRow>(
+        "SELECT id, title, description, sequential FROM course_units WHERE course_id = ? ORDER BY position",
+    )
+    .bind(course.id)
+    .fetch_all(pool)
+    .await?;
+    let problems = sqlx::query_as::<_, CourseProblemRow>(
+        r#"
+        SELECT cp.unit_id, p.id, p.slug, p.title, p.difficulty
+        FROM course_problems cp
+          JOIN course_units u ON u.id = cp.unit_id
+          JOIN problems p ON p.id = cp.problem_id
+        WHERE u.course_id = ?
+        ORDER BY u.position, cp.position
+        "#,
+    )
+    .bind(course.id)
+    .fetch_all(pool)
+    .await?;
+    let prerequisites = sqlx::query_as("SELECT problem_id, requires_id FROM course_prerequisites WHERE course_id = ?")
+        .bind(course.id)
+        .fetch_all(pool)
+        .await?;
+    Ok(Loaded { course, units, problems, prerequisites })
+}
+
+impl Loaded {
+    fn unit_problems(&self, unit_id: i64) -> impl Iterator<Item = &CourseProblemRow> {
+        self.problems.iter().filter(move |p| p.unit_id == unit_id)
+    }
+
+    fn edges(&self) -> Vec<(i64, i64)> {
+        let units: Vec<(bool, Vec<i64>)> =
+            self.units.iter().map(|u| (u.sequential, self.unit_problems(u.id).map(|p| p.id).collect())).collect();
+        edges(&units, &self.prerequisites)
+    }
+
+    fn slug_of(&self, id: i64) -> Option<&str> {
+        self.problems.iter().find(|p| p.id == id).map(|p| p.slug.as_str())
+    }
+}
+
+/// 管理 API / import 用に、DB の内容を CourseInput の形で
+pub async fn load_input(pool: &SqlitePool, id: i64) -> sqlx::Result<CourseInput> {
+    let course = sqlx::query_as::<_, CourseRow>(
+        "SELECT id, slug, title, description, position FROM courses WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    let loaded = load(pool, course).await?;
+
+    let mut prerequisites: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (p, r) in &loaded.prerequisites {
+        if let (Some(p), Some(r)) = (loaded.slug_of(*p), loaded.slug_of(*r)) {
+            prerequisites.entry(p.to_string()).or_default().push(r.to_string());
+        }
+    }
+    prerequisites.values_mut().for_each(|v| v.sort());
+
+    Ok(CourseInput {
+        slug: loaded.course.slug.clone(),
+        title: loaded.course.title.clone(),
+        description: loaded.course.description.clone(),
+        position: loaded.course.position,
+        units: loaded
+            .units
+            .iter()
+            .map(|u| UnitInput {
+                title: u.title.clone(),
+                description: u.description.clone(),
+                sequential: u.sequential,
+                problems: loaded.unit_problems(u.id).map(|p| p.slug.clone()).collect(),
+            })
+            .collect(),
+        prerequisites,
+    })
+}
+
+/// 利用者が正解済みの問題 id（冒頭のコメント参照）
+async fn solved_problems(pool: &SqlitePool, user_id: Option<i64>) -> sqlx::Result<HashSet<i64>> {
+    let Some(user_id) = user_id else {
+        return Ok(HashSet::new());
+    };
+    let rows: Vec<(i64,)> = sqlx::query_as(
+        "SELECT DISTINCT problem_id FROM submissions WHERE problem_id IS NOT NULL AND user_id = ? AND verdict = ?",
+    )
+    .bind(user_id)
+    .bind(Verdict::Accepted.as_str())
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/* ==================== ロック ==================== */
+//
+// 提出・ヒント・解答の API もコースマップと同じ判定でロックを守る。
+// 問題は複数のコースに入りうるので、どれか 1 つのコースで開いていれば開いているとみなす。
+// どのコースにも入っていない問題と、正解済みの問題は常に開いている。
+
+/// locked なら、まだ正解していない前提の問題（slug）。開いていれば None
+pub async fn missing_prerequisites(
+    pool: &SqlitePool,
+    user_id: i64,
+    problem_id: i64,
+) -> sqlx::Result<Option<Vec<String>>> {
+    let courses = sqlx::query_as::<_, CourseRow>(
+        r#"
+        SELECT DISTINCT c.id, c.slug, c.title, c.description, c.position
+        FROM courses c
+          JOIN course_units u ON u.course_id = c.id
+          JOIN course_problems cp ON cp.unit_id = u.id
+        WHERE cp.problem_id = ?
+        "#,
+    )
+    .bind(problem_id)
+    .fetch_all(pool)
+    .await?;
+    if courses.is_empty() {
+        return Ok(None);
+    }
+    let solved = solved_problems(pool, Some(user_id)).await?;
+    if solved.contains(&problem_id) {
+        return Ok(None);
+    }
+
+    let mut missing = Vec::new();
+    for course in courses {
+        let loaded = load(pool, course).await?;
+        let requires: Vec<i64> = loaded
+            .edges()
+            .into_iter()
+            .filter(|(p, r)| *p == problem_id && !solved.contains(r))
+            .map(|(_, r)| r)
+            .collect();
+        if requires.is_empty() {
+            return Ok(None);
+        }
+        missing.extend(requires.into_iter().filter_map(|r| loaded.slug_of(r).map(str::to_string)));
+    }
+    missing.sort();
+    missing.dedup();
+    Ok(Some(missing))
+}
+
+/// locked な問題への提出・ヒント・解答は 403。ハンドラの先頭で `if let Err(resp) = ... { return resp; }`
+pub async fn ensure_unlocked(pool: &SqlitePool, user_id: i64, problem_id: i64) -> Result<(), HttpResponse> {
+    match missing_prerequisites(pool, user_id, problem_id).await {
+        Ok(None) => Ok(()),
+        Ok(Some(requires)) => Err(HttpResponse::Forbidden().json(json!({
+            "error": "problem_locked",
+            "message": "solve the prerequisite problems first",
+            "requires": requires,
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().body(format!("db error: {e}"))),
+    }
+}
+
+/* ==================== 学習者向けの表示 ==================== */
+
+#[derive(Serialize)]
+pub struct CourseSummary {
+    pub id: i64,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub total: usize,
+    pub solved: usize,
+}
+
+#[derive(Serialize)]
+pub struct CourseMap {
+    pub id: i64,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub units: Vec<UnitMap>,
+    pub total: usize,
+    pub solved: usize,
+}
+
+#[derive(Serialize)]
+pub struct UnitMap {
+    pub title: String,
+    pub description: String,
+    pub sequential: bool,
+    pub problems: Vec<CourseProblem>,
+}
+
+#[derive(Serialize)]
+pub struct CourseProblem {
+    pub id: i64,
+    pub slug: String,
+    pub title: String,
+    pub locale: String,
+    pub difficulty: Option<String>,
+    pub state: ProblemState,
+    /// 前提の問題（slug）。locked の理由の表示用
+    pub requires: Vec<String>,
+}
+
+pub async fn list_courses(pool: &SqlitePool, user_id: Option<i64>) -> sqlx::Result<Vec<CourseSummary>> {
+    let courses = sqlx::query_as::<_, CourseRow>(
+        "SELECT id, slug, title, description, position FROM courses ORDER BY position, id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let members: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT u.course_id, cp.problem_id FROM course_problems cp JOIN course_units u ON u.id = cp.unit_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    let solved = solved_problems(pool, user_id).await?;
+
+    Ok(courses
+        .into_iter()
+        .map(|c| {
+            let problems: Vec<i64> = members.iter().filter(|(cid, _)| *cid == c.id).map(|(_, p)| *p).collect();
+            CourseSummary {
+                total: problems.len(),
+                solved: problems.iter().filter(|p| solved.contains(p)).count(),
+                id: c.id,
+                slug: c.slug,
+                title: c.title,
+                description: c.description,
+            }
+        })
+        .collect())
+}
+
+/// slug のコースを、利用者の状態（locked / unlocked / solved）付きで。無ければ None
+pub async fn course_map(
+    pool: &SqlitePool,
+    slug: &str,
+    user_id: Option<i64>,
+    chain: &[&str],
+) -> sqlx::Result<Option<CourseMap>> {
+    let course = sqlx::query_as::<_, CourseRow>(
+        "SELECT id, slug, title, description, position FROM courses WHERE slug = ?",
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+    let Some(course) = course else {
+        return Ok(None);
+    };
+    let loaded = load(pool, course).await?;
+    let edges = loaded.edges();
+    let solved = solved_problems(pool, user_id).await?;
+    let translations = fetch_translations(pool, None).await?;
+
+    let state = |id: i64| {
+        if solved.contains(&id) {
+            ProblemState::Solved
+        } else if edges.iter().filter(|(p, _)| *p == id).all(|(_, r)| solved.contains(r)) {
+            ProblemState::Unlocked
+        } else {
+            ProblemState::Locked
+        }
+    };
+
+    let units: Vec<UnitMap> = loaded
+        .units
+        .iter()
+        .map(|u| UnitMap {
+            title: u.title.clone(),
+            description: u.description.clone(),
+            sequential: u.sequential,
+            problems: loaded
+                .unit_problems(u.id)
+                .map(|p| {
+                    let t = pick_translation(&translations, p.id, chain);
+                    CourseProblem {
+                        id: p.id,
+                        slug: p.slug.clone(),
+                        title: t.map_or_else(|| p.title.clone(), |t| t.title.clone()),
+                        locale: t.map_or(locale::DEFAULT_LOCALE, |t| t.locale.as_str()).to_string(),
+                        difficulty: p.difficulty.clone(),
+                        state: state(p.id),
+                        requires: edges
+                            .iter()
+                            .filter(|(q, _)| *q == p.id)
+                            .filter_map(|(_, r)| loaded.slug_of(*r).map(str::to_string))
+                            .collect(),
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    let all = units.iter().flat_map(|u| &u.problems);
+    let (total, solved) = (all.clone().count(), all.filter(|p| p.state == ProblemState::Solved).count());
+    Ok(Some(CourseMap {
+        id: loaded.course.id,
+        slug: loaded.course.slug,
+        title: loaded.course.title,
+        description: loaded.course.description,
+        units,
+        total,
+        solved,
+    }))
+}
+
+/* ==================== コース定義ファイル ==================== */
+//
+//   courses/<slug>.toml   … CourseInput と同じ形（slug を省略するとファイル名）
+//
+//   title = "Rust 入門"
+//   [[units]]
+//   title = "所有権"
+//   sequential = true
+//   problems = ["hello-world", "move-semantics"]
+//   [prerequisites]
+//   borrow-checker = ["move-semantics"]
+
+/// dir/*.toml を slug 単位で DB に同期する。検証エラーがあれば Err
+pub async fn import(pool: &SqlitePool, dir: &Path) -> anyhow::Result<()> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", dir.display()))?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "toml"))
+        .collect();
+    files.sort();
+
+    let mut failed = 0;
+    let mut unchanged = 0;
+    for file in &files {
+        let text = std::fs::read_to_string(file).map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+        let mut table: toml::Table = toml::from_str(&text).map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        table.entry("slug").or_insert_with(|| stem.into());
+        let input: CourseInput = table.try_into().map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+
+        let current: Option<(i64,)> = sqlx::query_as("SELECT id FROM courses WHERE slug = ?")
+            .bind(&input.slug)
+            .fetch_optional(pool)
+            .await?;
+        let id = current.map(|(id,)| id);
+        let label = match id {
+            Some(id) if load_input(pool, id).await? == input => {
+                unchanged += 1;
+                continue;
+            }
+            Some(_) => "changed",
+            None => "added",
+        };
+
+        let errors = validate_course(pool, &input, id).await?;
+        if !errors.is_empty() {
+            failed += 1;
+            println!("invalid   {}", input.slug);
+            for e in errors {
+                println!("            {}: {}", e.field, e.message);
+            }
+            continue;
+        }
+        save_course(pool, &input, id).await?;
+        println!("{label:<9} {}", input.slug);
+    }
+
+    println!("{unchanged} unchanged");
+    if failed > 0 {
+        anyhow::bail!("{failed} course(s) failed validation");
+    }
+    Ok(())
+}
+
+/* ==================== ハンドラ ==================== */
+
+#[get("/api/courses")]
+async fn get_courses(user: Option<CurrentUser>, state: web::Data<AppState>) -> impl Responder {
+    match list_courses(&state.pool, user.map(|u| u.id)).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            eprintln!("[/api/courses] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+#[get("/api/courses/{slug}")]
+async fn get_course(
+    req: HttpRequest,
+    user: Option<CurrentUser>,
+    path: web::Path<String>,
+    query: web::Query<LangQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let chain = locale::negotiate(&req, &query);
+    match course_map(&state.pool, &path, user.map(|u| u.id), &chain).await {
+        Ok(Some(map)) => HttpResponse::Ok().json(map),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("[/api/courses/{path}] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+#[post("/api/admin/courses")]
+async fn create_course(_: TeacherAuth, input: web::Json<CourseInput>, state: web::Data<AppState>) -> impl Responder {
+    match validate_course(&state.pool, &input, None).await {
+        Ok(errors) if !errors.is_empty() => return validation_failed(errors),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+    match save_course(&state.pool, &input, None).await {
+        Ok(id) => HttpResponse::Created().json(json!({ "id": id })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[get("/api/admin/courses/{id}")]
+async fn get_course_admin(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    match load_input(&state.pool, path.into_inner()).await {
+        Ok(input) => HttpResponse::Ok().json(input),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[put("/api/admin/courses/{id}")]
+async fn update_course(
+    _: TeacherAuth,
+    path: web::Path<i64>,
+    input: web::Json<CourseInput>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let exists: Result<Option<(i64,)>, _> = sqlx::query_as("SELECT id FROM courses WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await;
+    match exists {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+    match validate_course(&state.pool, &input, Some(id)).await {
+        Ok(errors) if !errors.is_empty() => return validation_failed(errors),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+    match save_course(&state.pool, &input, Some(id)).await {
+        Ok(id) => HttpResponse::Ok().json(json!({ "id": id })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[delete("/api/admin/courses/{id}")]
+async fn delete_course(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    // 単元・前提条件は ON DELETE CASCADE（問題そのものは消さない）
+    match sqlx::query("DELETE FROM courses WHERE id = ?").bind(path.into_inner()).execute(&state.pool).await {
+        Ok(r) if r.rows_affected() == 0 => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_courses)
+        .service(get_course)
+        .service(create_course)
+        .service(get_course_admin)
+        .service(update_course)
+        .service(delete_course);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acyclic_prerequisites() {
+        assert!(!has_cycle(&[]));
+        assert!(!has_cycle(&[(2, 1), (3, 2), (3, 1)]));
+        // ひし形: 4 は 2 と 3、どちらも 1 を前提にする
+        assert!(!has_cycle(&[(2, 1), (3, 1), (4, 2), (4, 3)]));
+        // 別々の鎖
+        assert!(!has_cycle(&[(2, 1), (20, 10)]));
+    }
+
+    #[test]
+    fn cyclic_prerequisites() {
+        assert!(has_cycle(&[(1, 1)]));
+        assert!(has_cycle(&[(1, 2), (2, 1)]));
+        assert!(has_cycle(&[(2, 1), (3, 2), (1, 3)]));
+        // 循環の外に枝があっても見つける
+        assert!(has_cycle(&[(5, 4), (2, 1), (3, 2), (2, 3)]));
+    }
+
+    #[test]
+    fn sequential_units_chain_their_problems() {
+        let units = [(true, vec![1, 2, 3]), (false, vec![4, 5])];
+        let mut e = edges(&units, &[(4, 3)]);
+        e.sort();
+        assert_eq!(e, [(2, 1), (3, 2), (4, 3)]);
+        assert!(!has_cycle(&e));
+        // 明示した前提が sequential の順と逆向きなら循環
+        assert!(has_cycle(&edges(&units, &[(1, 3)])));
+    }
+}
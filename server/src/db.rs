@@ -0,0 +1,84 @@
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::{FromRow, SqlitePool};
+use std::path::Path;
+
+use crate::revision;
+
+/* ==================== DB 接続＆マイグレーション ==================== */
+
+/// server/migrations/*.sql をバイナリに埋め込む
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// DB_PATH（既定は /app/data/data.db）
+pub fn db_path() -> String {
+    std::env::var("DB_PATH").unwrap_or_else(|_| "/app/data/data.db".into())
+}
+
+/// 無ければ親ディレクトリごと作って開く（スキーマは migrate で入れる）
+pub async fn connect(db_path: &str) -> anyhow::Result<SqlitePool> {
+    if let Some(dir) = Path::new(db_path).parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+
+    let opts = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        // 接続ごとに効く（PRAGMA を 1 回流すだけだとプールの 1 本にしか効かない）
+        .foreign_keys(true);
+
+    Ok(SqlitePool::connect_with(opts).await?)
+}
+
+/// 未適用のマイグレーションを順に流す（版の無い問題には版 1 を作る）
+pub async fn migrate(pool: &SqlitePool) -> anyhow::Result<()> {
+    MIGRATOR.run(pool).await?;
+    revision::backfill(pool).await
+}
+
+#[derive(FromRow)]
+struct AppliedMigration {
+    version: i64,
+    success: bool,
+    checksum: Vec<u8>,
+    installed_on: String,
+}
+
+/// 埋め込み済みマイグレーションごとの適用状況を表示する（DB は変更しない）
+pub async fn print_status(pool: &SqlitePool) -> anyhow::Result<()> {
+    let has_table: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'")
+            .fetch_optional(pool)
+            .await?;
+    let applied: Vec<AppliedMigration> = if has_table.is_some() {
+        sqlx::query_as(
+            "SELECT version, success, checksum, CAST(installed_on AS TEXT) AS installed_on \
+             FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    for m in MIGRATOR.iter() {
+        let row = applied.iter().find(|a| a.version == m.version);
+        let state = match row {
+            None => "pending".to_string(),
+            Some(a) if !a.success => "FAILED".to_string(),
+            Some(a) if a.checksum != *m.checksum => format!("applied {} (CHECKSUM MISMATCH)", a.installed_on),
+            Some(a) => format!("applied {}", a.installed_on),
+        };
+        println!("{:04}  {:<32}  {state}", m.version, m.description);
+    }
+
+    // バイナリが知らない（新しいバイナリで適用された）もの
+    for a in applied.iter().filter(|a| MIGRATOR.iter().all(|m| m.version != a.version)) {
+        println!("{:04}  {:<32}  applied {} (unknown to this binary)", a.version, "?", a.installed_on);
+    }
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+
+use crate::account::CurrentUser;
+use crate::auth::TeacherAuth;
+use crate::course;
+use crate::locale::{self, LangQuery};
+use crate::problem::{fetch_translations, pick_translation};
+use crate::AppState;
+
+/* ==================== 段階的なヒント ==================== */
+//
+// 問題ごとに position 順のヒントを持ち、学習者は 1 つずつ開く（開いた時刻と減点を hint_reveals に残す）。
+// ★ 変更: 開いた記録は利用者ごと（ログインしていなければ何も開いていない状態を見せる）。
+
+/// ヒント 1 段（penalty は開いたときに引く点数）
+#[derive(FromRow, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub body: String,
+    #[serde(default)]
+    pub penalty: i64,
+}
+
+pub async fn fetch_hints(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Vec<Hint>> {
+    sqlx::query_as("SELECT body, penalty FROM problem_hints WHERE problem_id = ? ORDER BY position")
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// 開いたヒント（本文は chain のロケール）
+#[derive(Serialize)]
+pub struct RevealedHint {
+    pub position: i64,
+    pub body: String,
+    pub penalty: i64,
+    pub revealed_at: String,
+}
+
+#[derive(Serialize)]
+pub struct HintStatus {
+    pub total: usize,
+    pub revealed: Vec<RevealedHint>,
+    /// 次のヒントを開いたときの減点（もう無ければ None）
+    pub next_penalty: Option<i64>,
+    /// 開いたヒントの減点の合計
+    pub penalty: i64,
+}
+
+#[derive(FromRow)]
+struct Reveal {
+    position: i64,
+    penalty: i64,
+    revealed_at: String,
+}
+
+/// 利用者が開いた分と次の 1 段の情報。問題が無ければ None
+pub async fn status(
+    pool: &SqlitePool,
+    problem_id: i64,
+    user_id: Option<i64>,
+    chain: &[&str],
+) -> sqlx::Result<Option<HintStatus>> {
+    let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM problems WHERE id = ?")
+        .bind(problem_id)
+        .fetch_optional(pool)
+        .await?;
+    if exists.is_none() {
+        return Ok(None);
+    }
+    let hints = fetch_hints(pool, problem_id).await?;
+    let reveals = sqlx::query_as::<_, Reveal>(
+        r#"
+        SELECT position, penalty, MIN(revealed_at) AS revealed_at
+        FROM hint_reveals WHERE problem_id = ? AND user_id = ?
+        GROUP BY position ORDER BY position
+        "#,
+    )
+    .bind(problem_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    // 翻訳が無い段は既定ロケールの本文
+    let translations = fetch_translations(pool, Some(problem_id)).await?;
+    let tr = pick_translation(&translations, problem_id, chain);
+    let body = |i: usize| {
+        tr.and_then(|t| t.hints.get(i))
+            .or(hints.get(i).map(|h| &h.body))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let revealed: Vec<RevealedHint> = reveals
+        .into_iter()
+        .filter(|r| (r.position as usize) < hints.len())
+        .map(|r| RevealedHint {
+            body: body(r.position as usize),
+            position: r.position,
+            penalty: r.penalty,
+            revealed_at: r.revealed_at,
+        })
+        .collect();
+    Ok(Some(HintStatus {
+        total: hints.len(),
+        next_penalty: hints.get(revealed.len()).map(|h| h.penalty),
+        penalty: revealed.iter().map(|r| r.penalty).sum(),
+        revealed,
+    }))
+}
+
+/// 利用者が次の 1 段を開く。開けたら true（もう無い / 問題が無いなら false）
+pub async fn reveal_next(pool: &SqlitePool, problem_id: i64, user_id: i64) -> sqlx::Result<bool> {
+    // 1 文で「まだ開いていない最初の段」を挿入する（連打しても同じ段を二重に記録しない）
+    let r = sqlx::query(
+        r#"
+        INSERT INTO hint_reveals (user_id, problem_id, position, penalty, revealed_at)
+        SELECT ?1, problem_id, position, penalty, ?2
+        FROM problem_hints
+        WHERE problem_id = ?3
+          AND position = (
+            SELECT COALESCE(MAX(position) + 1, 0) FROM hint_reveals WHERE problem_id = ?3 AND user_id = ?1
+          )
+        "#,
+    )
+    .bind(user_id)
+    .bind(Utc::now().to_rfc3339())
+    .bind(problem_id)
+    .execute(pool)
+    .await?;
+    Ok(r.rows_affected() > 0)
+}
+
+/* ==================== 集計（講師向け） ==================== */
+
+#[derive(FromRow, Serialize)]
+pub struct HintUsage {
+    pub id: i64,
+    pub slug: String,
+    pub title: String,
+    /// ヒントの段数
+    pub hints: i64,
+    /// 開いた段数（全利用者の合計）
+    pub revealed: i64,
+    /// ヒントを開いた利用者の数
+    pub users: i64,
+    /// 減点の合計
+    pub penalty: i64,
+    pub last_revealed_at: Option<String>,
+    /// ★ 追加: 利用者ごとの内訳（減点の多い順）
+    #[sqlx(skip)]
+    pub by_user: Vec<UserHintUsage>,
+}
+
+/// 1 人の利用者が 1 つの問題で開いたヒント
+#[derive(FromRow, Serialize)]
+pub struct UserHintUsage {
+    #[serde(skip)]
+    pub problem_id: i64,
+    /// ログイン導入前の記録は None
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub revealed: i64,
+    pub penalty: i64,
+    pub last_revealed_at: Option<String>,
+}
+
+/// ヒントのある問題ごとの使用状況（利用者ごとの内訳つき）
+pub async fn usage_report(pool: &SqlitePool) -> sqlx::Result<Vec<HintUsage>> {
+    let mut rows: Vec<HintUsage> = sqlx::query_as(
+        r#"
+        SELECT p.id, p.slug, p.title,
+          (SELECT COUNT(*) FROM problem_hints WHERE problem_id = p.id) AS hints,
+          (SELECT COUNT(*) FROM hint_reveals WHERE problem_id = p.id) AS revealed,
+          (SELECT COUNT(DISTINCT user_id) FROM hint_reveals WHERE problem_id = p.id) AS users,
+          (SELECT COALESCE(SUM(penalty), 0) FROM hint_reveals WHERE problem_id = p.id) AS penalty,
+          (SELECT MAX(revealed_at) FROM hint_reveals WHERE problem_id = p.id) AS last_revealed_at
+        FROM problems p
+        WHERE EXISTS (SELECT 1 FROM problem_hints WHERE problem_id = p.id)
+        ORDER BY p.id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let users: Vec<UserHintUsage> = sqlx::query_as(
+        r#"
+        SELECT r.problem_id, r.user_id, u.username, u.display_name,
+          COUNT(*) AS revealed, COALESCE(SUM(r.penalty), 0) AS penalty, MAX(r.revealed_at) AS last_revealed_at
+        FROM hint_reveals r
+        LEFT JOIN users u ON u.id = r.user_id
+        GROUP BY r.problem_id, r.user_id
+        ORDER BY r.problem_id, penalty DESC, revealed DESC, u.username
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    for u in users {
+        if let Some(row) = rows.iter_mut().find(|r| r.id == u.problem_id) {
+            row.by_user.push(u);
+        }
+    }
+    Ok(rows)
+}
+
+/* ==================== ハンドラ ==================== */
+
+#[get("/api/problems/{id}/hints")]
+async fn get_hints(
+    req: HttpRequest,
+    user: Option<CurrentUser>,
+    path: web::Path<i64>,
+    query: web::Query<LangQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let chain = locale::negotiate(&req, &query);
+    match status(&state.pool, id, user.map(|u| u.id), &chain).await {
+        Ok(Some(s)) => HttpResponse::Ok().json(s),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("[/api/problems/{id}/hints] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+/// 次のヒントを開いて、開いた後の状態を返す（もう無ければ 409）
+#[post("/api/problems/{id}/hints/next")]
+async fn reveal_hint(
+    req: HttpRequest,
+    user: CurrentUser,
+    path: web::Path<i64>,
+    query: web::Query<LangQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let chain = locale::negotiate(&req, &query);
+    if let Err(resp) = course::ensure_unlocked(&state.pool, user.id, id).await {
+        return resp;
+    }
+    let revealed = match reveal_next(&state.pool, id, user.id).await {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    match status(&state.pool, id, Some(user.id), &chain).await {
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Ok(Some(_)) if !revealed => HttpResponse::Conflict()
+            .json(json!({ "error": "no_more_hints", "message": "all hints are already revealed" })),
+        Ok(Some(s)) => HttpResponse::Ok().json(s),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[get("/api/admin/reports/hints")]
+async fn hint_report(_: TeacherAuth, state: web::Data<AppState>) -> impl Responder {
+    match usage_report(&state.pool).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_hints).service(reveal_hint).service(hint_report);
+}
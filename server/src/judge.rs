@@ -0,0 +1,188 @@
+use serde::Serialize;
+
+use crate::panic_report::{self, PanicReport};
+use crate::problem::{Problem, TestCase};
+use crate::runner::{self, ResourceUsage, RunOutcome};
+use crate::toolchain::Toolchain;
+
+/* ==================== 判定 ==================== */
+
+/// 判定結果（submissions.verdict にもこの名前で保存）
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Accepted,
+    WrongAnswer,
+    CompileError,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    OutputLimitExceeded,
+    RuntimeError,
+}
+
+impl Verdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Accepted => "Accepted",
+            Verdict::WrongAnswer => "WrongAnswer",
+            Verdict::CompileError => "CompileError",
+            Verdict::TimeLimitExceeded => "TimeLimitExceeded",
+            Verdict::MemoryLimitExceeded => "MemoryLimitExceeded",
+            Verdict::OutputLimitExceeded => "OutputLimitExceeded",
+            Verdict::RuntimeError => "RuntimeError",
+        }
+    }
+}
+
+/// テストケース 1 件分の結果（入出力は返さない）
+#[derive(Serialize, Clone)]
+pub struct CaseResult {
+    pub position: i64,
+    pub verdict: Verdict,
+    pub usage: Option<ResourceUsage>,
+}
+
+pub struct JudgeResult {
+    pub verdict: Verdict,
+    /// 最後に実行したケース（最初に落ちたケース / 全部通れば最後のケース）の実行結果
+    pub last: RunOutcome,
+    /// 実行したケースだけ（最初の不正解で打ち切る）
+    pub cases: Vec<CaseResult>,
+    /// 全ケースのうち各項目の最大値
+    pub usage: Option<ResourceUsage>,
+    pub panic: Option<PanicReport>,
+    /// 画面表示用の最終メッセージ
+    pub output: String,
+}
+
+impl JudgeResult {
+    /// 実行する前に解答の形で落とした（穴埋めの形・並べ替えの字下げなど）。`message` は画面にそのまま出す
+    pub fn rejected(message: String) -> Self {
+        JudgeResult {
+            verdict: Verdict::WrongAnswer,
+            last: RunOutcome::failed_compile(String::new()),
+            cases: Vec::new(),
+            usage: None,
+            panic: None,
+            output: message,
+        }
+    }
+}
+
+/// 1 回コンパイルし、テストケースを順に流す
+pub async fn judge_code(
+    problem: &Problem,
+    cases: &[TestCase],
+    code: &str,
+    toolchain: &Toolchain,
+) -> anyhow::Result<JudgeResult> {
+    let program = match runner::compile(code, &problem.compile_options(), toolchain).await? {
+        Ok(p) => p,
+        Err(stderr) => {
+            let last = RunOutcome::failed_compile(stderr);
+            return Ok(JudgeResult {
+                verdict: Verdict::CompileError,
+                output: last.stderr.clone(),
+                last,
+                cases: Vec::new(),
+                usage: None,
+                panic: None,
+            });
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut usage: Option<ResourceUsage> = None;
+    let mut last = None;
+    let mut verdict = Verdict::Accepted;
+    for case in cases {
+        let o = program.run(&case.stdin, problem.run_limits()).await?;
+        verdict = judge_case(problem, &case.expected_stdout, &o);
+        if let Some(u) = o.usage {
+            usage = Some(match usage {
+                None => u,
+                Some(m) => ResourceUsage {
+                    max_rss_kb: m.max_rss_kb.max(u.max_rss_kb),
+                    user_time_ms: m.user_time_ms.max(u.user_time_ms),
+                    sys_time_ms: m.sys_time_ms.max(u.sys_time_ms),
+                    wall_time_ms: m.wall_time_ms.max(u.wall_time_ms),
+                },
+            });
+        }
+        results.push(CaseResult { position: case.position, verdict, usage: o.usage });
+        last = Some(o);
+        if verdict != Verdict::Accepted {
+            break;
+        }
+    }
+    let last = last.unwrap_or_else(|| RunOutcome::failed_compile(String::new()));
+
+    let panic = if verdict == Verdict::RuntimeError { PanicReport::parse(&last.stderr) } else { None };
+    let output = display_output(verdict, &last, panic.as_ref());
+
+    Ok(JudgeResult { verdict, last, cases: results, usage, panic, output })
+}
+
+/// 実行結果と問題のリソース上限から判定を決める
+fn judge_case(problem: &Problem, expected: &str, o: &RunOutcome) -> Verdict {
+    if !o.compiled {
+        return Verdict::CompileError;
+    }
+    // 上限超過で kill しているので、タイムアウトより先に見る
+    if o.output_limited {
+        return Verdict::OutputLimitExceeded;
+    }
+    if o.timed_out || o.cpu_limited {
+        return Verdict::TimeLimitExceeded;
+    }
+    // ★ 追加: RLIMIT_DATA で確保に失敗した abort は実行時エラーではなくメモリ超過
+    if o.memory_limited {
+        return Verdict::MemoryLimitExceeded;
+    }
+    if o.signal.is_some() || o.exit_code.is_some_and(|c| c != 0) {
+        return Verdict::RuntimeError;
+    }
+    if let Some(u) = &o.usage {
+        if problem.cpu_time_limit_ms.is_some_and(|lim| u.cpu_time_ms() > lim) {
+            return Verdict::TimeLimitExceeded;
+        }
+        if problem.memory_limit_kb.is_some_and(|lim| u.max_rss_kb > lim) {
+            return Verdict::MemoryLimitExceeded;
+        }
+    }
+    if o.stdout.trim_end() == expected.trim_end() {
+        Verdict::Accepted
+    } else {
+        Verdict::WrongAnswer
+    }
+}
+
+fn display_output(verdict: Verdict, o: &RunOutcome, panic: Option<&PanicReport>) -> String {
+    let RunOutcome { stdout, stderr, usage, exit_code, signal, timed_out, .. } = o;
+    match verdict {
+        Verdict::CompileError => stderr.clone(),
+        Verdict::TimeLimitExceeded if *timed_out => "Time limit exceeded".to_string(),
+        Verdict::TimeLimitExceeded => format!(
+            "CPU time limit exceeded ({} ms)",
+            usage.map(|u| u.cpu_time_ms()).unwrap_or_default()
+        ),
+        // 確保に失敗した時点の RSS は上限よりずっと小さいので、数字は出さない
+        Verdict::MemoryLimitExceeded if o.memory_limited => "Memory limit exceeded (allocation failed)".to_string(),
+        Verdict::MemoryLimitExceeded => format!(
+            "Memory limit exceeded ({} KiB)",
+            usage.map(|u| u.max_rss_kb).unwrap_or_default()
+        ),
+        // 生の backtrace は std の内部フレームだらけなので、ユーザコードだけに絞ったものを出す
+        Verdict::RuntimeError => {
+            let detail = match (panic, signal) {
+                (Some(p), _) => p.render(),
+                (None, Some(sig)) => format!(
+                    "{stderr}Process terminated by signal {sig} ({})\n",
+                    panic_report::signal_name(*sig).unwrap_or("unknown")
+                ),
+                (None, None) => format!("{stderr}Process exited with code {}\n", exit_code.unwrap_or_default()),
+            };
+            format!("{stdout}{detail}")
+        }
+        _ => if stderr.is_empty() { stdout.clone() } else { format!("{}{}", stdout, stderr) },
+    }
+}
@@ -0,0 +1,143 @@
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/* ==================== ロケール ==================== */
+//
+// 優先順: ?lang= → Accept-Language（q 値順）→ 既定（ja）。
+// 問題本文・UI 文言とも、選んだロケールに無いものは既定ロケールにフォールバックする。
+
+pub const DEFAULT_LOCALE: &str = "ja";
+pub const SUPPORTED_LOCALES: &[&str] = &["ja", "en"];
+
+/// `?lang=en` を受けるハンドラ用
+#[derive(Deserialize)]
+pub struct LangQuery {
+    pub lang: Option<String>,
+}
+
+/// 使う順に並べたロケール（末尾は必ず既定ロケール）
+pub fn negotiate(req: &HttpRequest, query: &LangQuery) -> Vec<&'static str> {
+    let mut wanted: Vec<(String, f32)> = Vec::new();
+    if let Some(l) = &query.lang {
+        wanted.push((l.clone(), 2.0));
+    }
+    let header = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    // 例: "en-US,en;q=0.9,ja;q=0.8"
+    for part in header.split(',') {
+        let mut it = part.trim().split(';');
+        let tag = it.next().unwrap_or_default().trim();
+        let q = it
+            .find_map(|p| p.trim().strip_prefix("q="))
+            .and_then(|q| q.parse().ok())
+            .unwrap_or(1.0);
+        if !tag.is_empty() && tag != "*" {
+            wanted.push((tag.to_string(), q));
+        }
+    }
+    // 同じ q なら出現順（sort_by は安定ソート）
+    wanted.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut chain = Vec::new();
+    for (tag, _) in wanted {
+        // en-US → en
+        let primary = tag.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+        if let Some(l) = SUPPORTED_LOCALES.iter().find(|l| **l == primary) {
+            if !chain.contains(l) {
+                chain.push(*l);
+            }
+        }
+    }
+    if !chain.contains(&DEFAULT_LOCALE) {
+        chain.push(DEFAULT_LOCALE);
+    }
+    chain
+}
+
+/* ==================== UI 文言 ==================== */
+
+fn table(locale: &str) -> &'static str {
+    match locale {
+        "en" => include_str!("../locales/en.json"),
+        _ => include_str!("../locales/ja.json"),
+    }
+}
+
+/// chain の後ろ（既定ロケール）から順に上書きして、欠けたキーを埋める
+pub fn ui_strings(chain: &[&str]) -> Map<String, Value> {
+    let mut out = Map::new();
+    for locale in chain.iter().rev() {
+        if let Ok(Value::Object(m)) = serde_json::from_str::<Value>(table(locale)) {
+            out.extend(m);
+        }
+    }
+    out
+}
+
+#[get("/api/i18n")]
+async fn get_ui_strings(req: HttpRequest, query: web::Query<LangQuery>) -> impl Responder {
+    let chain = negotiate(&req, &query);
+    HttpResponse::Ok().json(json!({
+        "locale": chain[0],
+        "supported": SUPPORTED_LOCALES,
+        "strings": ui_strings(&chain),
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_ui_strings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn chain(accept: Option<&str>, lang: Option<&str>) -> Vec<&'static str> {
+        let mut req = TestRequest::default();
+        if let Some(a) = accept {
+            req = req.insert_header(("Accept-Language", a));
+        }
+        negotiate(&req.to_http_request(), &LangQuery { lang: lang.map(String::from) })
+    }
+
+    #[test]
+    fn falls_back_to_the_default_locale() {
+        assert_eq!(chain(None, None), ["ja"]);
+        assert_eq!(chain(Some(""), None), ["ja"]);
+        assert_eq!(chain(Some("*"), None), ["ja"]);
+        assert_eq!(chain(Some("fr-FR,de;q=0.5"), None), ["ja"]);
+    }
+
+    #[test]
+    fn orders_accept_language_by_q() {
+        assert_eq!(chain(Some("en-US,en;q=0.9,ja;q=0.8"), None), ["en", "ja"]);
+        assert_eq!(chain(Some("ja;q=0.3, en;q=0.7"), None), ["en", "ja"]);
+        // 同じ q なら書かれた順、地域や大文字小文字は見ない
+        assert_eq!(chain(Some("EN_gb, ja"), None), ["en", "ja"]);
+        assert_eq!(chain(Some("fr, en;q=0.1"), None), ["en", "ja"]);
+        // 壊れた q は 1 として扱う
+        assert_eq!(chain(Some("ja, en;q=x"), None), ["ja", "en"]);
+    }
+
+    #[test]
+    fn query_wins_over_the_header() {
+        assert_eq!(chain(Some("ja"), Some("en")), ["en", "ja"]);
+        assert_eq!(chain(Some("en"), Some("ja")), ["ja", "en"]);
+        // 知らない ?lang= は無視してヘッダを使う
+        assert_eq!(chain(Some("en"), Some("xx")), ["en", "ja"]);
+    }
+
+    #[test]
+    fn ui_strings_fill_missing_keys_from_the_default() {
+        let en = ui_strings(&["en", "ja"]);
+        let ja = ui_strings(&["ja"]);
+        assert_eq!(en.len(), ja.len());
+        assert_eq!(en["account.login"], "Log in");
+        assert_eq!(ja["account.login"], "ログイン");
+    }
+}
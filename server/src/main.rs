@@ -1,17 +1,50 @@
-use actix_files::Files;
+use actix_files::{Files, NamedFile};
 use actix_web::{
-    get, post, web, App, HttpResponse, HttpServer, Responder,
-    middleware::{Logger, DefaultHeaders},
+    get, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method, StatusCode},
+    middleware::{self, Logger, DefaultHeaders},
 };
 use serde::{Deserialize, Serialize};
-use std::{
-    path::{Path, PathBuf},
-    time::{Duration, SystemTime, UNIX_EPOCH},
-};
-use tokio::{fs, process::Command, time::timeout};
-use sqlx::{SqlitePool, FromRow};
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use std::sync::Arc;
+use sqlx::SqlitePool;
 use chrono::Utc;
+use clap::Parser;
+
+mod account;
+mod admin;
+mod answer;
+mod auth;
+mod blanks;
+mod cli;
+mod course;
+mod db;
+mod hint;
+mod judge;
+mod locale;
+mod markdown;
+mod oidc;
+mod package;
+mod panic_report;
+mod parsons;
+mod problem;
+mod quiz;
+mod rejudge;
+mod revision;
+mod runner;
+mod search;
+mod selfcheck;
+mod submission;
+mod toolchain;
+use account::CurrentUser;
+use cli::{Cli, Command, MigrateAction};
+use judge::{judge_code, CaseResult, Verdict};
+use locale::LangQuery;
+use panic_report::PanicReport;
+use problem::{fetch_problem, fetch_translations, judge_cases, ProblemFilter};
+use runner::{run_user_code, CompileOptions, ResourceUsage, RunLimits};
+use search::SearchQuery;
+use toolchain::ToolchainRegistry;
 
 /* ==================== CSP（Monaco のための最小セット） ==================== */
 const CSP: &str = concat!(
@@ -29,40 +62,39 @@ const CSP: &str = concat!(
 #[derive(Clone)]
 struct AppState {
     pool: SqlitePool,
+    // 起動時に検出した rustc の一覧（読み取り専用）
+    toolchains: Arc<ToolchainRegistry>,
+    // 管理 API 用トークン（ADMIN_TOKEN。未設定なら管理 API は無効）
+    admin_token: Option<String>,
+    // ★ 追加: セッション Cookie に Secure を付ける（COOKIE_SECURE=1。HTTPS の裏で動かすとき）
+    secure_cookies: bool,
+    // ★ 追加: 学校の IdP でのログイン（OIDC_ISSUER。未設定なら None）
+    oidc: Option<Arc<oidc::Oidc>>,
+    // ★ 追加: パスワードでの登録・ログインを受け付ける（PASSWORD_LOGIN=0 で止める）
+    password_login: bool,
 }
 
 /* ==================== データモデル ==================== */
 
-#[derive(FromRow, Serialize)]
-struct Problem {
-    id: i64,
-    slug: String,
-    title: String,
-    description: String,
-    starter_code: String,
-    expected_stdout: String,
-    fixed_top: Option<String>,
-    fixed_bottom: Option<String>,
-    // ★ 追加: 編集範囲マーカー（NULL可）
-    editable_start_marker: Option<String>,
-    editable_end_marker:   Option<String>,
-    created_at: String,
-}
-
-// 最小構成の submissions
-#[derive(FromRow)]
-struct Submission {
-    id: i64,
-    problem_id: i64,
-    code: String,
-    output: String,
-    created_at: String,
-}
-
 #[derive(Deserialize)]
 struct RunReq {
     problem_id: i64,
+    // ★ 変更: 穴埋め問題では送らない
+    #[serde(default)]
     code: String,
+    // ★ 追加: 穴埋め問題の穴の中身（テンプレートの前から順）
+    #[serde(default)]
+    blanks: Vec<String>,
+    // ★ 追加: 並べ替え問題で選んだ行（上から順）
+    #[serde(default)]
+    lines: Vec<parsons::Pick>,
+}
+
+/// ★ 追加: 問題の種類ごとの解答（判定の前に問題の定義と突き合わせたもの）
+enum Submitted {
+    Code,
+    Blanks(Vec<blanks::Blank>),
+    Parsons(parsons::Parsons, Vec<parsons::Placed>),
 }
 
 #[derive(Serialize)]
@@ -73,56 +105,82 @@ struct RunResp {
     stderr: String,
     passed: bool,
     output: String,
+    verdict: Verdict,
+    // 実行まで到達したときだけ（ピーク RSS / CPU 時間など）
+    usage: Option<ResourceUsage>,
+    // 終了コード / 終了シグナル（どちらか一方）
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    signal_name: Option<&'static str>,
+    // パニックした場合のメッセージ・位置・ユーザコードのバックトレース
+    panic: Option<PanicReport>,
+    // 使った toolchain の名前と `rustc -V`
+    toolchain: String,
+    toolchain_version: String,
+    // 実行したテストケースごとの判定（最初の不正解で打ち切り）
+    cases: Vec<CaseResult>,
 }
 
-/* ==================== コンパイル＆実行 ==================== */
-
-async fn run_user_code(code: &str) -> anyhow::Result<(bool, bool, String, String)> {
-    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
-    let work_dir = PathBuf::from(format!("/tmp/run-{}", nanos));
-    fs::create_dir_all(&work_dir).await?;
-
-    let src = work_dir.join("main.rs");
-    fs::write(&src, code).await?;
-
-    let bin = work_dir.join("app-bin");
-    let compile_out = Command::new("rustc")
-        .arg(&src)
-        .arg("-O")
-        .arg("-o")
-        .arg(&bin)
-        .output()
-        .await?;
-
-    if !compile_out.status.success() {
-        let stderr = String::from_utf8_lossy(&compile_out.stderr).to_string();
-        return Ok((false, false, String::new(), stderr));
-    }
-
-    let run_fut = Command::new(&bin).output();
-    let out = match timeout(Duration::from_secs(2), run_fut).await {
-        Ok(Ok(o)) => o,
-        Ok(Err(e)) => return Ok((true, false, String::new(), format!("exec error: {e}"))),
-        Err(_) => return Ok((true, true, String::new(), String::new())),
-    };
+// 問題に紐づかない自由実行（判定・保存なし）
+#[derive(Deserialize)]
+struct PlaygroundReq {
+    code: String,
+    toolchain: Option<String>,
+    edition: Option<String>,
+    #[serde(default)]
+    stdin: String,
+}
 
-    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-    Ok((true, false, stdout, stderr))
+#[derive(Serialize)]
+struct PlaygroundResp {
+    compiled: bool,
+    timed_out: bool,
+    output_limited: bool,
+    stdout: String,
+    stderr: String,
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    usage: Option<ResourceUsage>,
+    toolchain: String,
+    toolchain_version: String,
 }
 
 /* ==================== ヘルパ：提出保存 ==================== */
 
-async fn save_submission(pool: &SqlitePool, problem_id: i64, code: &str, output: &str) {
+struct NewSubmission<'a> {
+    // ★ 追加: 提出した利用者
+    user_id: i64,
+    problem_id: i64,
+    // ★ 追加: 判定に使った問題の版
+    problem_revision: i64,
+    code: &'a str,
+    output: &'a str,
+    verdict: Verdict,
+    usage: Option<ResourceUsage>,
+    // クイズは None
+    toolchain_version: Option<&'a str>,
+}
+
+async fn save_submission(pool: &SqlitePool, sub: NewSubmission<'_>) {
+    let usage = sub.usage;
     if let Err(e) = sqlx::query(
         r#"
-        INSERT INTO submissions (problem_id, code, output, created_at)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO submissions
+          (user_id, problem_id, problem_revision, code, output, verdict, max_rss_kb, user_time_ms, sys_time_ms,
+           toolchain_version, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
-    .bind(problem_id)
-    .bind(code)
-    .bind(output)
+    .bind(sub.user_id)
+    .bind(sub.problem_id)
+    .bind(sub.problem_revision)
+    .bind(sub.code)
+    .bind(sub.output)
+    .bind(sub.verdict.as_str())
+    .bind(usage.map(|u| u.max_rss_kb))
+    .bind(usage.map(|u| u.user_time_ms))
+    .bind(usage.map(|u| u.sys_time_ms))
+    .bind(sub.toolchain_version)
     .bind(Utc::now().to_rfc3339())
     .execute(pool)
     .await
@@ -133,24 +191,17 @@ async fn save_submission(pool: &SqlitePool, problem_id: i64, code: &str, output:
 
 /* ==================== ハンドラ ==================== */
 
+// ★ 変更: 本文・コードを含まない一覧（絞り込み・ページング付き）
 #[get("/api/problems")]
-async fn list_problems(state: web::Data<AppState>) -> impl Responder {
-    let rows = sqlx::query_as::<_, Problem>(
-        r#"
-        SELECT
-          id, slug, title, description, starter_code, expected_stdout,
-          fixed_top, fixed_bottom,
-          editable_start_marker, editable_end_marker,
-          created_at
-        FROM problems
-        ORDER BY id
-        "#,
-    )
-    .fetch_all(&state.pool)
-    .await;
-
-    match rows {
-        Ok(v) => HttpResponse::Ok().json(v),
+async fn list_problems(
+    req: HttpRequest,
+    query: web::Query<LangQuery>,
+    filter: web::Query<ProblemFilter>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let chain = locale::negotiate(&req, &query);
+    match problem::list_summaries(&state.pool, &filter, &chain).await {
+        Ok(page) => HttpResponse::Ok().json(page),
         Err(e) => {
             eprintln!("[/api/problems] sqlx error: {e}");
             HttpResponse::InternalServerError().body(format!("db error: {e}"))
@@ -158,26 +209,98 @@ async fn list_problems(state: web::Data<AppState>) -> impl Responder {
     }
 }
 
+// ★ 追加: 全文検索（/api/problems/{id} より先に登録する）
+#[get("/api/problems/search")]
+async fn search_problems(
+    req: HttpRequest,
+    query: web::Query<LangQuery>,
+    sq: web::Query<SearchQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let chain = locale::negotiate(&req, &query);
+    let limit = sq.limit.unwrap_or(20);
+    match search::search(&state.pool, &sq.q, limit, &chain).await {
+        Ok(items) => HttpResponse::Ok().json(serde_json::json!({ "query": sq.q, "items": items })),
+        Err(e) => {
+            eprintln!("[/api/problems/search] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+// 絞り込み UI 用（/api/problems/{id} より先に登録する）
+#[get("/api/problems/facets")]
+async fn problem_facets(state: web::Data<AppState>) -> impl Responder {
+    match problem::facets(&state.pool).await {
+        Ok(v) => HttpResponse::Ok().json(v),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
 #[get("/api/problems/{id}")]
-async fn get_problem(path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
-    let id = path.into_inner();
-    let row = sqlx::query_as::<_, Problem>(
-        r#"
-        SELECT
-          id, slug, title, description, starter_code, expected_stdout,
-          fixed_top, fixed_bottom,
-          editable_start_marker, editable_end_marker,
-          created_at
-        FROM problems
-        WHERE id = ?
-        "#,
-    )
-    .bind(id)
-    .fetch_one(&state.pool)
-    .await;
+async fn get_problem(
+    req: HttpRequest,
+    path: web::Path<i64>,
+    query: web::Query<LangQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let chain = locale::negotiate(&req, &query);
+    problem_response(&state.pool, path.into_inner(), &chain).await
+}
 
-    match row {
-        Ok(p) => HttpResponse::Ok().json(p),
+// ★ 追加: slug で引く。旧 slug なら現在の slug へ 301
+#[get("/api/problems/by-slug/{slug}")]
+async fn get_problem_by_slug(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<LangQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let slug = path.into_inner();
+    match problem::resolve_slug(&state.pool, &slug).await {
+        Ok(Some((_, current))) if current != slug => {
+            redirect(&format!("/api/problems/by-slug/{current}"), req.query_string())
+        }
+        Ok(Some((id, _))) => {
+            let chain = locale::negotiate(&req, &query);
+            problem_response(&state.pool, id, &chain).await
+        }
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            eprintln!("[/api/problems/by-slug/{slug}] sqlx error: {e}");
+            HttpResponse::InternalServerError().body(format!("db error: {e}"))
+        }
+    }
+}
+
+/// 翻訳を当てて description_html を付けた問題
+async fn problem_response(pool: &SqlitePool, id: i64, chain: &[&str]) -> HttpResponse {
+    let found = match fetch_problem(pool, id).await {
+        Ok(p) => fetch_translations(pool, Some(id)).await.map(|tr| p.localize(&tr, chain)),
+        Err(e) => Err(e),
+    };
+    // ★ 追加: クイズなら選択肢（正解は含めない）
+    let found = match found {
+        Ok(mut p) => quiz::fetch_quiz(pool, id).await.map(|q| {
+            p.quiz = q.map(|q| q.view());
+            // ★ 追加: 穴埋めならテンプレートを穴で区切ったもの
+            if p.kind == problem::KIND_BLANKS {
+                p.blanks = Some(blanks::view(&p.starter_code));
+            }
+            p
+        }),
+        Err(e) => Err(e),
+    };
+    // ★ 追加: 並べ替えなら混ぜた行
+    let found = match found {
+        Ok(mut p) => parsons::fetch_parsons(pool, id).await.map(|l| {
+            p.parsons = l.map(|l| l.view(id));
+            p
+        }),
+        Err(e) => Err(e),
+    };
+    match found {
+        Ok(p) => HttpResponse::Ok().json(p.with_rendered_description()),
         Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
         Err(e) => {
             eprintln!("[/api/problems/{id}] sqlx error: {e}");
@@ -186,24 +309,67 @@ async fn get_problem(path: web::Path<i64>, state: web::Data<AppState>) -> impl R
     }
 }
 
-#[post("/api/run")]
-async fn run(req: web::Json<RunReq>, state: web::Data<AppState>) -> impl Responder {
-    let p = sqlx::query_as::<_, Problem>(
-        r#"
-        SELECT
-          id, slug, title, description, starter_code, expected_stdout,
-          fixed_top, fixed_bottom,
-          editable_start_marker, editable_end_marker,
-          created_at
-        FROM problems
-        WHERE id = ?
-        "#,
-    )
-    .bind(req.problem_id)
-    .fetch_one(&state.pool)
-    .await;
+/// クエリ文字列（?lang= など）を引き継いで恒久リダイレクト
+fn redirect(path: &str, query: &str) -> HttpResponse {
+    let location = if query.is_empty() { path.to_string() } else { format!("{path}?{query}") };
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+/* ==================== UI（クライアント側ルーティング） ==================== */
+//
+// /p/{slug} は index.html を返し、表示する問題は script.js が URL から決める。
+// 旧 slug はここでも現在の slug へ 301（ブックマークやノートに貼ったリンクを生かす）。
+
+const UI_DIR: &str = "/app/ui";
+
+async fn index_html() -> actix_web::Result<NamedFile> {
+    Ok(NamedFile::open_async(format!("{UI_DIR}/index.html")).await?)
+}
+
+#[get("/p/{slug}")]
+async fn problem_page(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> actix_web::Result<HttpResponse> {
+    let slug = path.into_inner();
+    let page = index_html().await?;
+    match problem::resolve_slug(&state.pool, &slug).await {
+        Ok(Some((_, current))) if current != slug => Ok(redirect(&format!("/p/{current}"), req.query_string())),
+        Ok(Some(_)) => Ok(page.into_response(&req)),
+        // 無い問題でも UI は出す（script.js が「読み込みに失敗」を表示する）
+        Ok(None) => {
+            let mut res = page.into_response(&req);
+            *res.status_mut() = StatusCode::NOT_FOUND;
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("[/p/{slug}] sqlx error: {e}");
+            Ok(HttpResponse::InternalServerError().body(format!("db error: {e}")))
+        }
+    }
+}
+
+/// 静的ファイルに無いパス: 拡張子の無い GET は index.html（クライアント側のルート）、それ以外は 404
+async fn ui_fallback(req: ServiceRequest) -> actix_web::Result<ServiceResponse> {
+    let (req, _) = req.into_parts();
+    let is_page = req.method() == Method::GET
+        && !req.path().starts_with("/api/")
+        && !req.path().rsplit('/').next().unwrap_or_default().contains('.');
+    let res = if is_page {
+        index_html().await?.into_response(&req)
+    } else {
+        HttpResponse::NotFound().finish()
+    };
+    Ok(ServiceResponse::new(req, res))
+}
 
-    let problem = match p {
+// ★ 変更: 提出はログインした利用者のものとして残す
+#[post("/api/run")]
+async fn run(user: CurrentUser, req: web::Json<RunReq>, state: web::Data<AppState>) -> impl Responder {
+    let problem = match fetch_problem(&state.pool, req.problem_id).await {
         Ok(p) => p,
         Err(sqlx::Error::RowNotFound) => {
             return HttpResponse::BadRequest().body("invalid problem_id");
@@ -212,31 +378,127 @@ async fn run(req: web::Json<RunReq>, state: web::Data<AppState>) -> impl Respond
             return HttpResponse::InternalServerError().body(format!("db error: {e}"));
         }
     };
-
-    let (compiled, timed_out, stdout, stderr) = match run_user_code(&req.code).await {
-        Ok(t) => t,
-        Err(e) => return HttpResponse::InternalServerError().body(format!("runner error: {e}")),
+    // ★ 追加: コースで前提の問題を解いていなければ提出させない
+    if let Err(resp) = course::ensure_unlocked(&state.pool, user.id, problem.id).await {
+        return resp;
+    }
+    // ★ 変更: 穴埋め・並べ替えは問題の定義と突き合わせてから判定する（提出には解答の JSON を残す）
+    let submitted = match problem.kind.as_str() {
+        problem::KIND_CODE => Submitted::Code,
+        problem::KIND_BLANKS => match blanks::fetch_blanks(&state.pool, problem.id).await {
+            Ok(b) => Submitted::Blanks(b),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+        },
+        problem::KIND_PARSONS => match parsons::fetch_parsons(&state.pool, problem.id).await {
+            Ok(Some(p)) => match p.place(problem.id, &req.lines) {
+                Ok(placed) => Submitted::Parsons(p, placed),
+                Err(errors) => return admin::validation_failed(errors),
+            },
+            Ok(None) => return HttpResponse::InternalServerError().body("parsons lines missing"),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+        },
+        kind => return HttpResponse::BadRequest().body(format!("{kind} problems are not answered with /api/run")),
     };
 
-    let passed = if compiled && !timed_out {
-        stdout.trim_end() == problem.expected_stdout.trim_end()
-    } else {
-        false
+    let Some(toolchain) = state.toolchains.get(problem.toolchain.as_deref()) else {
+        let name = problem.toolchain.as_deref().unwrap_or_default();
+        return HttpResponse::InternalServerError().body(format!("toolchain not installed: {name}"));
     };
 
-    // 画面表示用の最終メッセージ
-    let output = if compiled && !timed_out {
-        if stderr.is_empty() { stdout.clone() } else { format!("{}{}", stdout, stderr) }
-    } else if timed_out {
-        "Time limit exceeded".to_string()
-    } else {
-        stderr.clone()
+    let cases = match judge_cases(&state.pool, &problem).await {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    let (result, code) = match &submitted {
+        Submitted::Code => (judge_code(&problem, &cases, &req.code, toolchain).await, req.code.clone()),
+        Submitted::Blanks(defs) => (
+            blanks::judge_blanks(&problem, &cases, defs, &req.blanks, toolchain).await,
+            blanks::stored(&req.blanks),
+        ),
+        Submitted::Parsons(p, placed) => (
+            parsons::judge_parsons(&problem, &cases, p, placed, toolchain).await,
+            parsons::stored(placed),
+        ),
     };
+    let result = match result {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("runner error: {e}")),
+    };
+    let verdict = result.verdict;
 
     // 最小構成の submissions に保存
-    save_submission(&state.pool, problem.id, &req.code, &output).await;
+    save_submission(
+        &state.pool,
+        NewSubmission {
+            user_id: user.id,
+            problem_id: problem.id,
+            problem_revision: problem.revision,
+            code: &code,
+            output: &result.output,
+            verdict,
+            usage: result.usage,
+            toolchain_version: Some(&toolchain.version),
+        },
+    )
+    .await;
+
+    let runner::RunOutcome { compiled, timed_out, stdout, stderr, exit_code, signal, .. } = result.last;
+    HttpResponse::Ok().json(RunResp {
+        compiled,
+        timed_out,
+        stdout,
+        stderr,
+        passed: verdict == Verdict::Accepted,
+        output: result.output,
+        verdict,
+        usage: result.usage,
+        exit_code,
+        signal,
+        signal_name: signal.and_then(panic_report::signal_name),
+        panic: result.panic,
+        toolchain: toolchain.name.clone(),
+        toolchain_version: toolchain.version.clone(),
+        cases: result.cases,
+    })
+}
 
-    HttpResponse::Ok().json(RunResp { compiled, timed_out, stdout, stderr, passed, output })
+// 読み取りだけ（名前とバージョン）なので未ログインでも見られる。問題ページのツールチェイン選択に使う
+#[get("/api/toolchains")]
+async fn list_toolchains(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(state.toolchains.list())
+}
+
+// ★ 変更: 任意のコードを実行するので /api/run と同じくログインを要求する
+#[post("/api/playground")]
+async fn playground(_: CurrentUser, req: web::Json<PlaygroundReq>, state: web::Data<AppState>) -> impl Responder {
+    let Some(toolchain) = state.toolchains.get(req.toolchain.as_deref()) else {
+        return HttpResponse::BadRequest().body("unknown toolchain");
+    };
+    let mut opts = CompileOptions::default();
+    if let Some(ed) = &req.edition {
+        opts.edition = ed.clone();
+    }
+    if let Err(e) = opts.validate() {
+        return HttpResponse::BadRequest().body(e);
+    }
+
+    let o = match run_user_code(&req.code, &opts, toolchain, &req.stdin, RunLimits::default()).await {
+        Ok(o) => o,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("runner error: {e}")),
+    };
+
+    HttpResponse::Ok().json(PlaygroundResp {
+        compiled: o.compiled,
+        timed_out: o.timed_out,
+        output_limited: o.output_limited,
+        stdout: o.stdout,
+        stderr: o.stderr,
+        exit_code: o.exit_code,
+        signal: o.signal,
+        usage: o.usage,
+        toolchain: toolchain.name.clone(),
+        toolchain_version: toolchain.version.clone(),
+    })
 }
 
 /* ==================== 起動 ==================== */
@@ -245,33 +507,153 @@ async fn run(req: web::Json<RunReq>, state: web::Data<AppState>) -> impl Respond
 async fn main() -> anyhow::Result<()> {
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
 
-    // DB_PATH を使って“ファイル名指定”で接続
-    let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "/app/data/data.db".into());
-    if !Path::new(&db_path).exists() {
-        anyhow::bail!("DB not found at {db_path}. Please pre-create it.");
+    // --help などは DB に触る前に処理させる
+    let cli = Cli::parse();
+
+    // DB_PATH を使って“ファイル名指定”で接続（無ければ作る）
+    let db_path = db::db_path();
+    let pool = db::connect(&db_path).await?;
+
+    // サブコマンド（引数なしならサーバ起動）
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {}
+        Command::InitDb { problems, courses } => {
+            db::migrate(&pool).await?;
+            println!("database ready: {db_path}");
+            if let Some(dir) = problems {
+                let toolchains = ToolchainRegistry::detect().await?;
+                let opts = package::ImportOptions { prune: false, dry_run: false };
+                package::import(&pool, &toolchains, &dir, &opts).await?;
+            }
+            // コースは問題を slug で参照するので問題のあと
+            if let Some(dir) = courses {
+                course::import(&pool, &dir).await?;
+            }
+            return Ok(());
+        }
+        Command::Migrate { action: None } => {
+            db::migrate(&pool).await?;
+            return db::print_status(&pool).await;
+        }
+        Command::Migrate { action: Some(MigrateAction::Status) } => return db::print_status(&pool).await,
+        // ★ 追加: 問題パッケージの取り込み / 書き出し
+        Command::Import { dir, prune, dry_run } => {
+            db::migrate(&pool).await?;
+            let toolchains = ToolchainRegistry::detect().await?;
+            let opts = package::ImportOptions { prune, dry_run };
+            return package::import(&pool, &toolchains, &dir, &opts).await;
+        }
+        Command::Export { dir } => {
+            db::migrate(&pool).await?;
+            return package::export(&pool, &dir).await;
+        }
+        // ★ 追加: コース定義（<dir>/*.toml）の取り込み
+        Command::ImportCourses { dir } => {
+            db::migrate(&pool).await?;
+            return course::import(&pool, &dir).await;
+        }
+        // ★ 追加: 模範解答・初期コードを実際に判定へ通す（slug 指定なしなら全問題）
+        Command::Check { slugs } => {
+            db::migrate(&pool).await?;
+            let toolchains = ToolchainRegistry::detect().await?;
+            let slugs: Vec<&str> = slugs.iter().map(String::as_str).collect();
+            let reports = selfcheck::check_all(&pool, &toolchains, &slugs).await?;
+            return selfcheck::print_reports(&reports);
+        }
+        Command::Judge { problem, file, toolchain } => {
+            db::migrate(&pool).await?;
+            let toolchains = ToolchainRegistry::detect().await?;
+            return cli::judge_file(&pool, &toolchains, &problem, &file, toolchain.as_deref()).await;
+        }
+        Command::Rejudge { problem, submissions, stale, dry_run } => {
+            db::migrate(&pool).await?;
+            let toolchains = ToolchainRegistry::detect().await?;
+            return cli::rejudge(&pool, &toolchains, problem.as_deref(), submissions, stale, dry_run).await;
+        }
+        Command::Reindex => {
+            db::migrate(&pool).await?;
+            let mut conn = pool.acquire().await?;
+            search::reindex(&mut conn, None).await?;
+            println!("search index rebuilt");
+            return Ok(());
+        }
+        // ★ 追加: 利用者の作成・パスワードの再設定
+        Command::CreateUser { username, display_name, role } => {
+            db::migrate(&pool).await?;
+            return cli::create_user(&pool, &username, display_name.as_deref(), &role).await;
+        }
+        Command::SetRole { username, role } => {
+            db::migrate(&pool).await?;
+            return cli::set_role(&pool, &username, &role).await;
+        }
+        Command::SetPassword { username } => {
+            db::migrate(&pool).await?;
+            return cli::set_password(&pool, &username).await;
+        }
+        Command::Backup { dest } => return cli::backup(&pool, &db_path, dest).await,
     }
 
-    let opts = SqliteConnectOptions::new()
-        .filename(&db_path)
-        .create_if_missing(false)
-        .journal_mode(SqliteJournalMode::Wal)
-        .synchronous(SqliteSynchronous::Normal);
+    // 起動時にスキーマを最新へ
+    db::migrate(&pool).await?;
 
-    let pool = SqlitePool::connect_with(opts).await?;
+    let toolchains = Arc::new(ToolchainRegistry::detect().await?);
+    for tc in toolchains.list() {
+        println!("[toolchain] {}: {}", tc.name, tc.version);
+    }
+    let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+    let secure_cookies = std::env::var("COOKIE_SECURE").is_ok_and(|v| v == "1" || v == "true");
+    let password_login = !std::env::var("PASSWORD_LOGIN").is_ok_and(|v| v == "0" || v == "false");
+    let oidc = match oidc::OidcConfig::from_env()? {
+        Some(config) => {
+            println!("[oidc] issuer: {}", config.issuer);
+            Some(Arc::new(oidc::Oidc::new(config)?))
+        }
+        None => None,
+    };
 
-    // 外部キー ON（安全策）
-    let _ = sqlx::query("PRAGMA foreign_keys = ON;").execute(&pool).await;
+    // ★ 追加: 前回の停止で途中になった再判定ジョブを再開
+    rejudge::resume(&pool, &toolchains).await?;
 
     HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(AppState { pool: pool.clone() }))
+            .app_data(web::Data::new(AppState {
+                pool: pool.clone(),
+                toolchains: toolchains.clone(),
+                admin_token: admin_token.clone(),
+                secure_cookies,
+                oidc: oidc.clone(),
+                password_login,
+            }))
+            // ★ 追加: /api/admin/ 以下は teacher 以上（ハンドラの引数と二重に確かめる）
+            .wrap(middleware::from_fn(auth::guard_admin_api))
             .wrap(Logger::default())
             .wrap(DefaultHeaders::new().add(("Content-Security-Policy", CSP)))
             .service(web::resource("/favicon.ico").to(|| async { HttpResponse::NoContent().finish() }))
             .service(list_problems)
+            .service(problem_facets)
+            .service(search_problems)
+            .service(get_problem_by_slug)
             .service(get_problem)
             .service(run)
-            .service(Files::new("/", "/app/ui").index_file("index.html"))
+            .service(list_toolchains)
+            .service(playground)
+            .configure(locale::configure)
+            .configure(account::configure)
+            .configure(oidc::configure)
+            .configure(submission::configure)
+            .configure(course::configure)
+            .configure(hint::configure)
+            .configure(answer::configure)
+            .configure(quiz::configure)
+            .configure(admin::configure)
+            .configure(revision::configure)
+            .configure(rejudge::configure)
+            .service(problem_page)
+            .service(
+                Files::new("/", UI_DIR)
+                    .index_file("index.html")
+                    .default_handler(ui_fallback),
+            )
     })
     .bind(("0.0.0.0", 8080))?
     .run()
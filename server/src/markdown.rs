@@ -0,0 +1,297 @@
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashSet;
+
+/* ==================== 問題文の Markdown ==================== */
+//
+// 問題文（problems.description）は Markdown。サーバ側で HTML にして ammonia でサニタイズする。
+// ```rust のコードブロックだけ簡易ハイライト（<span class="hl-*">）を付ける。
+
+/// Markdown → サニタイズ済み HTML
+pub fn render(src: &str) -> String {
+    let opts = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let mut events = Vec::new();
+    let mut rust_block: Option<String> = None;
+    for ev in Parser::new_ext(src, opts) {
+        match ev {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref lang))) if is_rust(lang) => {
+                rust_block = Some(String::new());
+            }
+            Event::Text(ref t) if rust_block.is_some() => {
+                rust_block.as_mut().unwrap().push_str(t);
+            }
+            Event::End(TagEnd::CodeBlock) if rust_block.is_some() => {
+                let code = rust_block.take().unwrap();
+                events.push(Event::Html(CowStr::from(format!(
+                    "<pre><code class=\"language-rust\">{}</code></pre>\n",
+                    highlight_rust(&code)
+                ))));
+            }
+            ev => events.push(ev),
+        }
+    }
+
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    sanitize(&out)
+}
+
+/// ```rust / ```rs / ```rust,ignore など
+fn is_rust(lang: &str) -> bool {
+    matches!(lang.split([',', ' ']).next(), Some("rust" | "rs"))
+}
+
+fn sanitize(html: &str) -> String {
+    let span_classes: HashSet<&str> = HL_CLASSES.iter().copied().collect();
+    let code_classes: HashSet<&str> = ["language-rust"].into_iter().collect();
+    ammonia::Builder::default()
+        .add_allowed_classes("span", span_classes)
+        .add_allowed_classes("code", code_classes)
+        .clean(html)
+        .to_string()
+}
+
+/* ==================== Rust の簡易ハイライト ==================== */
+//
+// 教材のコード例が読みやすくなれば十分なので、字句を大まかに分けるだけ（構文解析はしない）。
+
+const HL_CLASSES: &[&str] = &["hl-kw", "hl-ty", "hl-str", "hl-num", "hl-com", "hl-mac", "hl-life"];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128",
+    "usize", "f32", "f64",
+];
+
+fn highlight_rust(code: &str) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        let start = i;
+
+        let class = if rest.starts_with(&['/', '/']) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            "hl-com"
+        } else if rest.starts_with(&['/', '*']) {
+            i += 2;
+            while i < chars.len() && !chars[i..].starts_with(&['*', '/']) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            "hl-com"
+        } else if let Some(end) = raw_string_end(&chars, i) {
+            i = end;
+            "hl-str"
+        } else if is_raw_ident_start(&chars, i) {
+            // ★ 追加: r#type などの生識別子はキーワードにしない
+            i += 2;
+            while i < chars.len() && is_ident(chars[i]) {
+                i += 1;
+            }
+            ""
+        } else if c == '"' || (c == 'b' && chars.get(i + 1) == Some(&'"')) {
+            i += if c == 'b' { 2 } else { 1 };
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            "hl-str"
+        } else if c == '\'' {
+            // 'a' / '\n' は文字リテラル、'a（後ろに ' が無い）はライフタイム
+            if chars.get(i + 1) == Some(&'\\') {
+                i += 2;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                "hl-str"
+            } else if chars.get(i + 2) == Some(&'\'') {
+                i += 3;
+                "hl-str"
+            } else {
+                i += 1;
+                while i < chars.len() && is_ident(chars[i]) {
+                    i += 1;
+                }
+                "hl-life"
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (is_ident(chars[i]) || (chars[i] == '.' && next_is_digit(&chars, i))) {
+                i += 1;
+            }
+            "hl-num"
+        } else if is_ident(c) {
+            while i < chars.len() && is_ident(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&'!') && chars.get(i + 1) != Some(&'=') {
+                i += 1;
+                "hl-mac"
+            } else if KEYWORDS.contains(&word.as_str()) {
+                "hl-kw"
+            } else if PRIMITIVES.contains(&word.as_str()) || word.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+                "hl-ty"
+            } else {
+                ""
+            }
+        } else {
+            i += 1;
+            ""
+        };
+
+        let text: String = chars[start..i].iter().collect();
+        if class.is_empty() {
+            out.push_str(&escape(&text));
+        } else {
+            out.push_str(&format!("<span class=\"{class}\">{}</span>", escape(&text)));
+        }
+    }
+    out
+}
+
+/// r"..." / r#"..."# / br"..." の終端（生文字列でなければ None）
+fn raw_string_end(chars: &[char], i: usize) -> Option<usize> {
+    let mut j = i;
+    if chars.get(j) == Some(&'b') {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') || (i > 0 && is_ident(chars[i - 1])) {
+        return None;
+    }
+    j += 1;
+    let hashes = chars[j..].iter().take_while(|&&c| c == '#').count();
+    j += hashes;
+    if chars.get(j) != Some(&'"') {
+        return None;
+    }
+    j += 1;
+    while j < chars.len() {
+        if chars[j] == '"' && chars[j + 1..].iter().take(hashes).filter(|&&c| c == '#').count() == hashes {
+            return Some(j + 1 + hashes);
+        }
+        j += 1;
+    }
+    Some(chars.len())
+}
+
+/// r#ident（生識別子）の始まりか
+fn is_raw_ident_start(chars: &[char], i: usize) -> bool {
+    chars[i] == 'r'
+        && chars.get(i + 1) == Some(&'#')
+        && chars.get(i + 2).is_some_and(|&c| is_ident(c))
+        && (i == 0 || !is_ident(chars[i - 1]))
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn next_is_digit(chars: &[char], i: usize) -> bool {
+    chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(class: &str, text: &str) -> String {
+        format!("<span class=\"{class}\">{text}</span>")
+    }
+
+    #[test]
+    fn lifetimes_and_char_literals() {
+        assert_eq!(highlight_rust("'a"), span("hl-life", "'a"));
+        assert_eq!(highlight_rust("'a'"), span("hl-str", "'a'"));
+        assert_eq!(highlight_rust("'\\n'"), span("hl-str", "'\\n'"));
+        assert_eq!(highlight_rust("'\\u{3042}'"), span("hl-str", "'\\u{3042}'"));
+        assert_eq!(highlight_rust("'\"'"), span("hl-str", "'&quot;'"));
+        assert_eq!(
+            highlight_rust("&'a str"),
+            format!("&amp;{} {}", span("hl-life", "'a"), span("hl-ty", "str"))
+        );
+        assert_eq!(highlight_rust("<'a>"), format!("&lt;{}&gt;", span("hl-life", "'a")));
+        assert_eq!(highlight_rust("'static"), span("hl-life", "'static"));
+    }
+
+    #[test]
+    fn raw_strings_and_raw_identifiers() {
+        assert_eq!(highlight_rust("r\"a\\b\""), span("hl-str", "r&quot;a\\b&quot;"));
+        // 終端は同じ数の # が続く " だけ
+        assert_eq!(
+            highlight_rust("r#\"say \"hi\"\"#;"),
+            format!("{};", span("hl-str", "r#&quot;say &quot;hi&quot;&quot;#"))
+        );
+        assert_eq!(highlight_rust("br##\"x\"#y\"##"), span("hl-str", "br##&quot;x&quot;#y&quot;##"));
+        // 生識別子はキーワードではない
+        assert_eq!(highlight_rust("r#type"), "r#type");
+        assert_eq!(
+            highlight_rust("let r#fn = 1;"),
+            format!("{} r#fn = {};", span("hl-kw", "let"), span("hl-num", "1"))
+        );
+        // 識別子の途中の r は生文字列の始まりではない
+        assert_eq!(highlight_rust("for\"x\""), format!("{}{}", span("hl-kw", "for"), span("hl-str", "&quot;x&quot;")));
+    }
+
+    #[test]
+    fn unterminated_tokens_end_at_the_input() {
+        assert_eq!(highlight_rust("\"abc"), span("hl-str", "&quot;abc"));
+        assert_eq!(highlight_rust("\"abc\\"), span("hl-str", "&quot;abc\\"));
+        assert_eq!(highlight_rust("r#\"abc\""), span("hl-str", "r#&quot;abc&quot;"));
+        assert_eq!(highlight_rust("/* abc"), span("hl-com", "/* abc"));
+        assert_eq!(highlight_rust("/*"), span("hl-com", "/*"));
+        assert_eq!(highlight_rust("// abc"), span("hl-com", "// abc"));
+        assert_eq!(highlight_rust("'\\"), span("hl-str", "'\\"));
+        assert_eq!(highlight_rust("'"), span("hl-life", "'"));
+    }
+
+    #[test]
+    fn macros_and_not_equal() {
+        assert_eq!(
+            highlight_rust("println!(\"{}\", x);"),
+            format!("{}({}, x);", span("hl-mac", "println!"), span("hl-str", "&quot;{}&quot;"))
+        );
+        assert_eq!(highlight_rust("a != b"), "a != b");
+        assert_eq!(highlight_rust("a!=b"), "a!=b");
+        assert_eq!(highlight_rust("1..10"), format!("{}..{}", span("hl-num", "1"), span("hl-num", "10")));
+        assert_eq!(highlight_rust("1.5"), span("hl-num", "1.5"));
+    }
+
+    #[test]
+    fn render_strips_scripts_but_keeps_highlighting() {
+        let html = render("Hi <script>alert(1)</script><span class=\"evil\" onclick=\"x()\">there</span>\n\n\
+                           ```rust\nlet s = \"<script>\";\n```\n");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert"));
+        assert!(!html.contains("evil") && !html.contains("onclick"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(html.contains(&span("hl-kw", "let")));
+        // コードの中の <script> は文字として残る（サニタイズで &quot; は " に戻る）
+        assert!(html.contains(&span("hl-str", "\"&lt;script&gt;\"")));
+    }
+
+    #[test]
+    fn only_rust_fences_are_highlighted() {
+        assert!(render("```rust,ignore\nfn f() {}\n```\n").contains(&span("hl-kw", "fn")));
+        assert!(render("```rs\nfn f() {}\n```\n").contains(&span("hl-kw", "fn")));
+        let plain = render("```text\nfn f() {}\n```\n");
+        assert!(!plain.contains("hl-kw"));
+        assert!(plain.contains("fn f() {}"));
+    }
+}
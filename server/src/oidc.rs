@@ -0,0 +1,581 @@
+use actix_web::{
+    cookie::{time::Duration as CookieDuration, Cookie, SameSite},
+    get,
+    http::header,
+    web, HttpRequest, HttpResponse, Responder,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::fmt::Display;
+
+use crate::account::{self, CurrentUser, RoleChange, DISPLAY_NAME_MAX, USERNAME_MAX};
+use crate::auth::Role;
+use crate::AppState;
+
+/* ==================== OpenID Connect（学校の IdP でログイン） ==================== */
+//
+// 認可コードフロー + PKCE（S256）。環境変数 OIDC_ISSUER を設定したときだけ有効。
+//   GET /api/oidc/login?return_to=/path  … state / nonce / code_verifier を oidc_logins に残して IdP へ
+//   GET /api/oidc/callback               … コードをトークンに換え、ID トークンを検証してセッションを作る
+// IdP の設定（エンドポイント・公開鍵）は {issuer}/.well-known/openid-configuration から毎回引く。
+// IdP の利用者は (iss, sub) で user_identities に結び付け、初回ログインで users を作る（パスワードは無し）。
+// OIDC_ROLE_CLAIM を設定すると、ログインのたびにそのクレームから役割を決め直す（IdP 側が正）。
+//
+//   OIDC_ISSUER          IdP の issuer（例: https://idp.example.ac.jp/realms/school）
+//   OIDC_CLIENT_ID       クライアント ID
+//   OIDC_CLIENT_SECRET   クライアントシークレット（公開クライアントなら未設定）
+//   OIDC_REDIRECT_URL    IdP に登録したコールバック（例: https://rust.example.ac.jp/api/oidc/callback）
+//   OIDC_SCOPES          既定 "openid profile email"
+//   OIDC_USERNAME_CLAIM  ユーザー名にするクレーム（既定 preferred_username。無ければ email の @ より前）
+//   OIDC_ROLE_CLAIM      役割を表すクレーム（文字列か文字列の配列。例: roles, groups）
+//   OIDC_ROLE_MAP        クレームの値と役割の対応（例: "teachers=teacher,it-staff=admin"）。
+//                        対応に無い値は student / teacher / admin そのものなら使う。どれにも当たらなければ student。
+//                        クレーム自体が無いときは役割を変えない。最後の admin は降格しない
+//
+// 手元では examples/mock_oidc.rs の模擬 IdP で試せる。
+
+/// IdP へ送り出してからコールバックまでの猶予
+const LOGIN_MINUTES: i64 = 10;
+/// state を入れておく Cookie（コールバックと突き合わせる）
+const STATE_COOKIE: &str = "oidc_state";
+/// ID トークンの exp / iat の時計のずれの許容（秒）
+const LEEWAY_SECS: u64 = 60;
+/// IdP への問い合わせのタイムアウト
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+/// 署名に受け付けるアルゴリズム（HS* は client_secret と鍵を取り違えさせる攻撃があるので受け付けない）
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: String,
+    pub username_claim: String,
+    pub role_claim: Option<String>,
+    pub role_map: Vec<(String, Role)>,
+}
+
+impl OidcConfig {
+    /// OIDC_ISSUER が無ければ None（OIDC は無効）
+    pub fn from_env() -> anyhow::Result<Option<OidcConfig>> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let Some(issuer) = var("OIDC_ISSUER") else {
+            return Ok(None);
+        };
+        let required =
+            |name: &str| var(name).ok_or_else(|| anyhow::anyhow!("{name} is required when OIDC_ISSUER is set"));
+        Ok(Some(OidcConfig {
+            issuer,
+            client_id: required("OIDC_CLIENT_ID")?,
+            client_secret: var("OIDC_CLIENT_SECRET"),
+            redirect_url: required("OIDC_REDIRECT_URL")?,
+            scopes: var("OIDC_SCOPES").unwrap_or_else(|| "openid profile email".to_string()),
+            username_claim: var("OIDC_USERNAME_CLAIM").unwrap_or_else(|| "preferred_username".to_string()),
+            role_claim: var("OIDC_ROLE_CLAIM"),
+            role_map: parse_role_map(&var("OIDC_ROLE_MAP").unwrap_or_default())?,
+        }))
+    }
+
+    /// クレームから役割を決める。OIDC_ROLE_CLAIM が未設定か、ID トークンにそのクレームが無ければ None（役割は変えない）
+    fn role_of(&self, claims: &Map<String, Value>) -> Option<Role> {
+        let claim = self.role_claim.as_ref()?;
+        let values: Vec<&str> = match claims.get(claim)? {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let role = values
+            .into_iter()
+            .filter_map(|v| {
+                self.role_map.iter().find(|(k, _)| k == v).map(|(_, r)| *r).or_else(|| Role::parse(v))
+            })
+            .max()
+            .unwrap_or(Role::Student);
+        Some(role)
+    }
+}
+
+/// "teachers=teacher,it-staff=admin"
+fn parse_role_map(s: &str) -> anyhow::Result<Vec<(String, Role)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|entry| {
+            let (value, role) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("OIDC_ROLE_MAP: expected value=role, got {entry:?}"))?;
+            let role = role.trim();
+            let role = Role::parse(role)
+                .ok_or_else(|| anyhow::anyhow!("OIDC_ROLE_MAP: unknown role {role:?} (student/teacher/admin)"))?;
+            Ok((value.trim().to_string(), role))
+        })
+        .collect()
+}
+
+pub struct Oidc {
+    pub config: OidcConfig,
+    http: reqwest::Client,
+}
+
+impl Oidc {
+    pub fn new(config: OidcConfig) -> anyhow::Result<Oidc> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECS))
+            .build()?;
+        Ok(Oidc { config, http })
+    }
+
+    async fn discover(&self) -> Result<Discovery, Failure> {
+        let url = format!("{}/.well-known/openid-configuration", self.config.issuer.trim_end_matches('/'));
+        let d: Discovery = self.get_json(&url).await?;
+        if d.issuer != self.config.issuer {
+            return Err(Failure::new("idp_unavailable", format!("issuer mismatch: {}", d.issuer)));
+        }
+        Ok(d)
+    }
+
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, Failure> {
+        let resp = self.http.get(url).send().await.map_err(fail("idp_unavailable"))?;
+        let resp = resp.error_for_status().map_err(fail("idp_unavailable"))?;
+        resp.json().await.map_err(fail("idp_unavailable"))
+    }
+
+    /// 認可コードを ID トークンに換える
+    async fn exchange(&self, d: &Discovery, code: &str, code_verifier: &str) -> Result<String, Failure> {
+        let c = &self.config;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", c.redirect_url.as_str()),
+            ("client_id", c.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &c.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let resp = self.http.post(&d.token_endpoint).form(&form).send().await.map_err(fail("idp_unavailable"))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(Failure::new("token_exchange_failed", format!("{status}: {body}")));
+        }
+        let token: TokenResponse = resp.json().await.map_err(fail("token_exchange_failed"))?;
+        token.id_token.ok_or_else(|| Failure::new("token_exchange_failed", "no id_token in the token response"))
+    }
+
+    /// 署名・iss・aud・exp・nonce を確かめてクレームを返す
+    async fn verify(&self, d: &Discovery, id_token: &str, nonce: &str) -> Result<Map<String, Value>, Failure> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(fail("invalid_id_token"))?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(Failure::new("invalid_id_token", format!("algorithm {:?} is not allowed", header.alg)));
+        }
+        let jwks: JwkSet = self.get_json(&d.jwks_uri).await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| Failure::new("invalid_id_token", format!("no matching key for kid {:?}", header.kid)))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(fail("invalid_id_token"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.leeway = LEEWAY_SECS;
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map_err(fail("invalid_id_token"))?
+            .claims;
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(Failure::new("invalid_id_token", "nonce mismatch"));
+        }
+        Ok(claims)
+    }
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// ログインの失敗。code は画面へ戻すとき ?login_error= に付ける
+struct Failure {
+    code: &'static str,
+    detail: String,
+}
+
+impl Failure {
+    fn new(code: &'static str, detail: impl Display) -> Failure {
+        Failure { code, detail: detail.to_string() }
+    }
+}
+
+fn fail<E: Display>(code: &'static str) -> impl Fn(E) -> Failure {
+    move |e| Failure::new(code, e)
+}
+
+fn db_failure(e: sqlx::Error) -> Failure {
+    Failure::new("internal_error", format!("db error: {e}"))
+}
+
+/* ==================== 利用者の対応付け ==================== */
+
+/// (iss, sub) に結び付いた利用者。初めてなら作る。表示名と（設定があれば）役割は IdP に合わせる
+async fn user_for(pool: &SqlitePool, config: &OidcConfig, claims: &Map<String, Value>) -> Result<CurrentUser, Failure> {
+    let claim = |name: &str| claims.get(name).and_then(Value::as_str).map(str::trim).filter(|s| !s.is_empty());
+    let subject = claim("sub").ok_or_else(|| Failure::new("invalid_id_token", "no sub claim"))?;
+    let username = claim(&config.username_claim)
+        .or_else(|| claim("email").and_then(|e| e.split('@').next()))
+        .map(sanitize_username)
+        .unwrap_or_else(|| "user".to_string());
+    let display_name: String = claim("name").unwrap_or(&username).chars().take(DISPLAY_NAME_MAX).collect();
+    let role = config.role_of(claims);
+
+    let linked: Option<(i64,)> = sqlx::query_as("SELECT user_id FROM user_identities WHERE issuer = ? AND subject = ?")
+        .bind(&config.issuer)
+        .bind(subject)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_failure)?;
+    let id = match linked {
+        Some((id,)) => {
+            sqlx::query("UPDATE users SET display_name = ? WHERE id = ?")
+                .bind(&display_name)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(db_failure)?;
+            // ★ 変更: 管理画面と同じく最後の admin は降格しない（IdP の設定ミスで管理者がいなくならないように）
+            if let Some(role) = role {
+                if let RoleChange::LastAdmin = account::change_role(pool, id, role).await.map_err(db_failure)? {
+                    eprintln!("[oidc] user {id} is the last admin; kept the admin role instead of {}", role.as_str());
+                }
+            }
+            id
+        }
+        None => {
+            let user = create_linked_user(pool, &username, &display_name, role.unwrap_or(Role::Student)).await?;
+            let linked =
+                sqlx::query("INSERT INTO user_identities (issuer, subject, user_id, created_at) VALUES (?, ?, ?, ?)")
+                    .bind(&config.issuer)
+                    .bind(subject)
+                    .bind(user.id)
+                    .bind(Utc::now().to_rfc3339())
+                    .execute(pool)
+                    .await;
+            if let Err(e) = linked {
+                // 同じ利用者の同時ログインで先を越された。作った利用者は消してやり直してもらう
+                let _ = sqlx::query("DELETE FROM users WHERE id = ?").bind(user.id).execute(pool).await;
+                return Err(db_failure(e));
+            }
+            user.id
+        }
+    };
+    account::fetch_user(pool, id).await.map_err(db_failure)
+}
+
+/// ユーザー名が使われていれば -2, -3, ... を付ける
+async fn create_linked_user(
+    pool: &SqlitePool,
+    username: &str,
+    display_name: &str,
+    role: Role,
+) -> Result<CurrentUser, Failure> {
+    for n in 1..100 {
+        let candidate = if n == 1 { username.to_string() } else { format!("{username}-{n}") };
+        if let Some(user) = account::create_user(pool, &candidate, display_name, None, role)
+            .await
+            .map_err(db_failure)?
+        {
+            return Ok(user);
+        }
+    }
+    Err(Failure::new("internal_error", format!("no free username for {username}")))
+}
+
+/// validate_username を通る形に（使えない文字は '_'。-NN を付ける余地を残す）
+fn sanitize_username(raw: &str) -> String {
+    let mut s: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .take(USERNAME_MAX - 3)
+        .collect();
+    while s.len() < 3 {
+        s.push('_');
+    }
+    s
+}
+
+/* ==================== ハンドラ ==================== */
+
+fn oidc_disabled() -> HttpResponse {
+    HttpResponse::NotFound().json(json!({ "error": "oidc_disabled", "message": "single sign-on is not configured" }))
+}
+
+fn found(location: &str) -> HttpResponse {
+    HttpResponse::Found().insert_header((header::LOCATION, location)).finish()
+}
+
+/// 同じサイトの中のパスだけ（//evil.example や /\evil.example で外へ飛ばされないように）
+fn safe_return_to(path: Option<&str>) -> String {
+    let ok = |p: &str| p.starts_with('/') && !p.starts_with("//") && !p.starts_with("/\\");
+    match path {
+        Some(p) if ok(p) && !p.contains(char::is_control) => p.to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+fn state_cookie(value: String, secure: bool) -> Cookie<'static> {
+    Cookie::build(STATE_COOKIE, value)
+        .path("/api/oidc")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(secure)
+        .max_age(CookieDuration::minutes(LOGIN_MINUTES))
+        .finish()
+}
+
+/// code_verifier の S256
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+#[derive(Deserialize)]
+struct LoginQuery {
+    return_to: Option<String>,
+}
+
+/// IdP のログイン画面へ
+#[get("/api/oidc/login")]
+async fn login(query: web::Query<LoginQuery>, state: web::Data<AppState>) -> impl Responder {
+    let Some(oidc) = &state.oidc else {
+        return oidc_disabled();
+    };
+    let discovery = match oidc.discover().await {
+        Ok(d) => d,
+        Err(f) => {
+            eprintln!("[oidc] discovery failed: {}", f.detail);
+            return HttpResponse::BadGateway()
+                .json(json!({ "error": "idp_unavailable", "message": "the identity provider is not reachable" }));
+        }
+    };
+
+    let (login_state, nonce, verifier) = (account::random_token(), account::random_token(), account::random_token());
+    let now = Utc::now();
+    let saved = async {
+        sqlx::query("DELETE FROM oidc_logins WHERE created_at <= ?")
+            .bind((now - Duration::minutes(LOGIN_MINUTES)).to_rfc3339())
+            .execute(&state.pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO oidc_logins (state, nonce, code_verifier, return_to, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&login_state)
+        .bind(&nonce)
+        .bind(&verifier)
+        .bind(safe_return_to(query.return_to.as_deref()))
+        .bind(now.to_rfc3339())
+        .execute(&state.pool)
+        .await
+    };
+    if let Err(e) = saved.await {
+        return HttpResponse::InternalServerError().body(format!("db error: {e}"));
+    }
+
+    let c = &oidc.config;
+    let url = reqwest::Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", c.client_id.as_str()),
+            ("redirect_uri", c.redirect_url.as_str()),
+            ("scope", c.scopes.as_str()),
+            ("state", login_state.as_str()),
+            ("nonce", nonce.as_str()),
+            ("code_challenge", code_challenge(&verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    );
+    match url {
+        Ok(url) => HttpResponse::Found()
+            .cookie(state_cookie(login_state, state.secure_cookies))
+            .insert_header((header::LOCATION, url.as_str()))
+            .finish(),
+        Err(e) => {
+            eprintln!("[oidc] bad authorization_endpoint: {e}");
+            HttpResponse::BadGateway()
+                .json(json!({ "error": "idp_unavailable", "message": "the identity provider is misconfigured" }))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// IdP から戻ってきたところ。成功すればセッションを作って return_to へ、失敗すれば /?login_error=<code> へ
+#[get("/api/oidc/callback")]
+async fn callback(req: HttpRequest, query: web::Query<CallbackQuery>, state: web::Data<AppState>) -> impl Responder {
+    let Some(oidc) = &state.oidc else {
+        return oidc_disabled();
+    };
+    let mut clear = state_cookie(String::new(), state.secure_cookies);
+    clear.make_removal();
+    match finish_login(&req, &query, oidc, &state.pool).await {
+        Ok((user, return_to)) => match account::start_session(&state.pool, user.id).await {
+            Ok(token) => {
+                eprintln!("[oidc] login: {} ({})", user.username, user.role.as_str());
+                HttpResponse::Found()
+                    .cookie(account::session_cookie(token, state.secure_cookies))
+                    .cookie(clear)
+                    .insert_header((header::LOCATION, return_to))
+                    .finish()
+            }
+            Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+        },
+        Err(f) => {
+            eprintln!("[oidc] login failed ({}): {}", f.code, f.detail);
+            let mut resp = found(&format!("/?login_error={}", f.code));
+            let _ = resp.add_cookie(&clear);
+            resp
+        }
+    }
+}
+
+async fn finish_login(
+    req: &HttpRequest,
+    query: &CallbackQuery,
+    oidc: &Oidc,
+    pool: &SqlitePool,
+) -> Result<(CurrentUser, String), Failure> {
+    if let Some(error) = &query.error {
+        let detail = format!("{error}: {}", query.error_description.as_deref().unwrap_or_default());
+        return Err(Failure::new(if error == "access_denied" { "access_denied" } else { "idp_error" }, detail));
+    }
+    let (Some(code), Some(login_state)) = (&query.code, &query.state) else {
+        return Err(Failure::new("invalid_callback", "code or state is missing"));
+    };
+    // 別のブラウザで始めたログインの state を持ち込まれないように Cookie と突き合わせる
+    if req.cookie(STATE_COOKIE).map(|c| c.value().to_string()).as_ref() != Some(login_state) {
+        return Err(Failure::new("invalid_callback", "state does not match the cookie"));
+    }
+
+    let row: Option<(String, String, String, String)> =
+        sqlx::query_as("SELECT nonce, code_verifier, return_to, created_at FROM oidc_logins WHERE state = ?")
+            .bind(login_state)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_failure)?;
+    // 一度きり
+    sqlx::query("DELETE FROM oidc_logins WHERE state = ?").bind(login_state).execute(pool).await.map_err(db_failure)?;
+    let Some((nonce, verifier, return_to, created_at)) = row else {
+        return Err(Failure::new("invalid_callback", "unknown or already used state"));
+    };
+    let expired = chrono::DateTime::parse_from_rfc3339(&created_at)
+        .map_or(true, |t| Utc::now() - t.with_timezone(&Utc) > Duration::minutes(LOGIN_MINUTES));
+    if expired {
+        return Err(Failure::new("login_expired", "the login took too long"));
+    }
+
+    let discovery = oidc.discover().await?;
+    let id_token = oidc.exchange(&discovery, code, &verifier).await?;
+    let claims = oidc.verify(&discovery, &id_token, &nonce).await?;
+    let user = user_for(pool, &oidc.config, &claims).await?;
+    Ok((user, return_to))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(login).service(callback);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(role_claim: Option<&str>, role_map: &str) -> OidcConfig {
+        OidcConfig {
+            issuer: "http://localhost:9000".into(),
+            client_id: "rust-learning".into(),
+            client_secret: None,
+            redirect_url: "http://localhost:8080/api/oidc/callback".into(),
+            scopes: "openid".into(),
+            username_claim: "preferred_username".into(),
+            role_claim: role_claim.map(String::from),
+            role_map: parse_role_map(role_map).unwrap(),
+        }
+    }
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match json!({ "sub": "mock-alice", "roles": value }) {
+            Value::Object(m) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn parses_role_maps() {
+        assert_eq!(
+            parse_role_map(" teachers = teacher , it-staff=admin,").unwrap(),
+            [("teachers".to_string(), Role::Teacher), ("it-staff".to_string(), Role::Admin)]
+        );
+        assert!(parse_role_map("").unwrap().is_empty());
+        assert!(parse_role_map("teachers").is_err());
+        assert!(parse_role_map("teachers=owner").is_err());
+    }
+
+    #[test]
+    fn role_claim_as_string_or_array() {
+        let c = config(Some("roles"), "teachers=teacher,it-staff=admin");
+        assert_eq!(c.role_of(&claims(json!("teachers"))), Some(Role::Teacher));
+        // 配列なら一番強い役割
+        assert_eq!(c.role_of(&claims(json!(["students", "it-staff", "teachers"]))), Some(Role::Admin));
+        // 対応に無くても役割の名前そのものなら使う
+        assert_eq!(c.role_of(&claims(json!(["teacher"]))), Some(Role::Teacher));
+    }
+
+    #[test]
+    fn unmapped_role_values_are_students() {
+        let c = config(Some("roles"), "teachers=teacher");
+        assert_eq!(c.role_of(&claims(json!("staff"))), Some(Role::Student));
+        assert_eq!(c.role_of(&claims(json!([]))), Some(Role::Student));
+        assert_eq!(c.role_of(&claims(json!([1, null]))), Some(Role::Student));
+        assert_eq!(c.role_of(&claims(json!(null))), Some(Role::Student));
+    }
+
+    #[test]
+    fn absent_claim_keeps_the_role() {
+        let c = config(Some("groups"), "teachers=teacher");
+        assert_eq!(c.role_of(&claims(json!("teachers"))), None);
+        // OIDC_ROLE_CLAIM が未設定なら何も見ない
+        assert_eq!(config(None, "").role_of(&claims(json!("admin"))), None);
+    }
+}
@@ -0,0 +1,446 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::admin::{save_problem, validate_problem, ProblemInput, TestCaseInput, TranslationInput};
+use crate::answer::{fetch_answers, Answer};
+use crate::blanks::{fetch_blanks, Blank};
+use crate::hint::{fetch_hints, Hint};
+use crate::parsons::{fetch_parsons, Parsons};
+use crate::problem::{fetch_test_cases, fetch_translations, Problem, KIND_CODE, PROBLEM_SELECT};
+use crate::quiz::{fetch_quiz, Quiz};
+use crate::runner::CompileOptions;
+use crate::toolchain::ToolchainRegistry;
+
+/* ==================== 問題パッケージ ==================== */
+//
+// 1 問 = 1 ディレクトリ（git で管理する前提）
+//
+//   problems/<slug>/
+//     problem.toml     … タイトル・固定領域・リソース上限・コンパイル設定
+//     description.md   … 問題文（既定ロケール = ja）
+//     description.en.md … 翻訳の問題文（任意。title / hints は problem.toml の [translations.en]）
+//     starter.rs       … 初期コード（クイズ・並べ替えでは不要。穴埋めでは `___` を穴とするテンプレート）
+//     solution.rs      … 模範解答（任意。`server check` で全ケース通ることを確かめる）
+//     answers/
+//       01.rs          … 別解（任意。タイトル・解説は problem.toml の [[answers]] に同じ順で書く）
+//     tests/
+//       01.in          … 標準入力（任意。無ければ空）
+//       01.out         … 期待出力
+//
+// tests/ が空なら problem.toml の expected_stdout で判定する（従来の 1 ケース問題）。
+// クイズ（kind = "quiz"）は設問を description.md に、選択肢・正解を problem.toml の [quiz] に書く。
+// 穴埋め（kind = "blanks"）は穴ごとの模範の中身・形の制約を problem.toml の [[blanks]] に穴の順で書く。
+// 並べ替え（kind = "parsons"）は行を正しい順で problem.toml の [parsons] / [[parsons.lines]] に書く。
+
+const MANIFEST: &str = "problem.toml";
+const DESCRIPTION: &str = "description.md";
+const STARTER: &str = "starter.rs";
+const SOLUTION: &str = "solution.rs";
+const TESTS_DIR: &str = "tests";
+const ANSWERS_DIR: &str = "answers";
+
+/// problem.toml の中身（本文・コード・テストは別ファイル）
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    /// 省略時はディレクトリ名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    slug: Option<String>,
+    /// 省略時は code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    expected_stdout: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fixed_top: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fixed_bottom: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    editable_start_marker: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    editable_end_marker: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    memory_limit_kb: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cpu_time_limit_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_limit_bytes: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    edition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    opt_level: Option<String>,
+    #[serde(default)]
+    debug_assertions: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    toolchain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    difficulty: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// [quiz] format = "single" / [[quiz.choices]] body = "..." correct = true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quiz: Option<Quiz>,
+    /// [[blanks]] answer = "..." / pattern = '...'（starter.rs の穴と同じ順）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blanks: Vec<Blank>,
+    /// [parsons] check_indent = true / [[parsons.lines]] code = "..." indent = 1 distractor = false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parsons: Option<Parsons>,
+    /// [[hints]] body = "..." / penalty = 10（テーブルの配列なので後ろに置く）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hints: Vec<Hint>,
+    /// answers/NN.rs と同じ順
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    answers: Vec<ManifestAnswer>,
+    /// テーブルなので最後に置く
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    translations: BTreeMap<String, ManifestTranslation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestTranslation {
+    title: String,
+    /// ヒントの本文だけ（減点は元のヒントのもの）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hints: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestAnswer {
+    title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    explanation: String,
+}
+
+/// description.<locale>.md
+fn description_file(locale: &str) -> String {
+    format!("description.{locale}.md")
+}
+
+/// 1 パッケージを読み込む
+pub fn read_package(dir: &Path) -> anyhow::Result<ProblemInput> {
+    let manifest: Manifest = toml::from_str(&read(dir, MANIFEST)?)?;
+    let dir_name = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let defaults = CompileOptions::default();
+    let kind = manifest.kind.unwrap_or_else(|| KIND_CODE.to_string());
+    // クイズは starter.rs が無くてもよい
+    let starter_code =
+        if kind == KIND_CODE || dir.join(STARTER).is_file() { read(dir, STARTER)? } else { String::new() };
+
+    Ok(ProblemInput {
+        slug: manifest.slug.unwrap_or(dir_name),
+        kind,
+        title: manifest.title,
+        description: read(dir, DESCRIPTION)?,
+        hints: manifest.hints,
+        difficulty: manifest.difficulty,
+        category: manifest.category,
+        // DB からは並べ替え済みで返るので、差分判定のため揃えておく
+        tags: {
+            let mut tags = manifest.tags;
+            tags.sort();
+            tags.dedup();
+            tags
+        },
+        starter_code,
+        expected_stdout: manifest.expected_stdout,
+        fixed_top: manifest.fixed_top,
+        fixed_bottom: manifest.fixed_bottom,
+        editable_start_marker: manifest.editable_start_marker,
+        editable_end_marker: manifest.editable_end_marker,
+        memory_limit_kb: manifest.memory_limit_kb,
+        cpu_time_limit_ms: manifest.cpu_time_limit_ms,
+        output_limit_bytes: manifest.output_limit_bytes,
+        edition: manifest.edition.unwrap_or(defaults.edition),
+        opt_level: manifest.opt_level.unwrap_or(defaults.opt_level),
+        debug_assertions: manifest.debug_assertions,
+        toolchain: manifest.toolchain,
+        solution_code: dir.join(SOLUTION).is_file().then(|| read(dir, SOLUTION)).transpose()?,
+        answers: read_answers(&dir.join(ANSWERS_DIR), manifest.answers)?,
+        test_cases: read_tests(&dir.join(TESTS_DIR))?,
+        quiz: manifest.quiz,
+        blanks: manifest.blanks,
+        parsons: manifest.parsons,
+        translations: manifest
+            .translations
+            .into_iter()
+            .map(|(l, t)| {
+                let file = description_file(&l);
+                let description = if dir.join(&file).is_file() { read(dir, &file)? } else { String::new() };
+                Ok((l, TranslationInput { title: t.title, description, hints: t.hints }))
+            })
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+/// tests/NN.out（と任意の NN.in）をファイル名順に
+fn read_tests(dir: &Path) -> anyhow::Result<Vec<TestCaseInput>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut outs: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "out"))
+        .collect();
+    outs.sort();
+
+    outs.into_iter()
+        .map(|out| {
+            let input = out.with_extension("in");
+            Ok(TestCaseInput {
+                stdin: if input.exists() { std::fs::read_to_string(&input)? } else { String::new() },
+                expected_stdout: std::fs::read_to_string(&out)?,
+            })
+        })
+        .collect()
+}
+
+/// answers/*.rs（ファイル名順）に problem.toml の [[answers]] を順に対応させる
+fn read_answers(dir: &Path, meta: Vec<ManifestAnswer>) -> anyhow::Result<Vec<Answer>> {
+    let mut files: Vec<_> = if dir.is_dir() {
+        std::fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|x| x == "rs"))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    files.sort();
+    if files.len() != meta.len() {
+        anyhow::bail!("{} files in {ANSWERS_DIR}/ but {} [[answers]] in {MANIFEST}", files.len(), meta.len());
+    }
+    files
+        .iter()
+        .zip(meta)
+        .map(|(f, m)| {
+            let code = std::fs::read_to_string(f).map_err(|e| anyhow::anyhow!("{}: {e}", f.display()))?;
+            Ok(Answer { title: m.title, explanation: m.explanation, code })
+        })
+        .collect()
+}
+
+fn read(dir: &Path, name: &str) -> anyhow::Result<String> {
+    let path = dir.join(name);
+    std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+}
+
+/// 1 パッケージを書き出す（tests/ は作り直す）
+pub fn write_package(dir: &Path, input: &ProblemInput) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let defaults = CompileOptions::default();
+    let manifest = Manifest {
+        // ディレクトリ名と同じなら省略
+        slug: (dir.file_name().is_none_or(|n| n.to_string_lossy() != input.slug)).then(|| input.slug.clone()),
+        kind: (input.kind != KIND_CODE).then(|| input.kind.clone()),
+        title: input.title.clone(),
+        expected_stdout: input.expected_stdout.clone(),
+        fixed_top: input.fixed_top.clone(),
+        fixed_bottom: input.fixed_bottom.clone(),
+        editable_start_marker: input.editable_start_marker.clone(),
+        editable_end_marker: input.editable_end_marker.clone(),
+        memory_limit_kb: input.memory_limit_kb,
+        cpu_time_limit_ms: input.cpu_time_limit_ms,
+        output_limit_bytes: input.output_limit_bytes,
+        edition: (input.edition != defaults.edition).then(|| input.edition.clone()),
+        opt_level: (input.opt_level != defaults.opt_level).then(|| input.opt_level.clone()),
+        debug_assertions: input.debug_assertions,
+        toolchain: input.toolchain.clone(),
+        hints: input.hints.clone(),
+        answers: input
+            .answers
+            .iter()
+            .map(|a| ManifestAnswer { title: a.title.clone(), explanation: a.explanation.clone() })
+            .collect(),
+        difficulty: input.difficulty.clone(),
+        category: input.category.clone(),
+        tags: input.tags.clone(),
+        quiz: input.quiz.clone(),
+        blanks: input.blanks.clone(),
+        parsons: input.parsons.clone(),
+        translations: input
+            .translations
+            .iter()
+            .map(|(l, t)| (l.clone(), ManifestTranslation { title: t.title.clone(), hints: t.hints.clone() }))
+            .collect(),
+    };
+    std::fs::write(dir.join(MANIFEST), toml::to_string(&manifest)?)?;
+    std::fs::write(dir.join(DESCRIPTION), &input.description)?;
+    // 翻訳の問題文（無くなったロケールのファイルは消す）
+    for entry in std::fs::read_dir(dir)?.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let locale = name.strip_prefix("description.").and_then(|n| n.strip_suffix(".md"));
+        if locale.is_some_and(|l| !input.translations.contains_key(l)) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    for (l, t) in &input.translations {
+        if !t.description.is_empty() {
+            std::fs::write(dir.join(description_file(l)), &t.description)?;
+        }
+    }
+    if input.kind == KIND_CODE || !input.starter_code.is_empty() {
+        std::fs::write(dir.join(STARTER), &input.starter_code)?;
+    } else if dir.join(STARTER).exists() {
+        std::fs::remove_file(dir.join(STARTER))?;
+    }
+    match &input.solution_code {
+        Some(code) => std::fs::write(dir.join(SOLUTION), code)?,
+        None if dir.join(SOLUTION).exists() => std::fs::remove_file(dir.join(SOLUTION))?,
+        None => {}
+    }
+
+    let answers = dir.join(ANSWERS_DIR);
+    if answers.exists() {
+        std::fs::remove_dir_all(&answers)?;
+    }
+    if !input.answers.is_empty() {
+        std::fs::create_dir_all(&answers)?;
+        for (i, a) in input.answers.iter().enumerate() {
+            std::fs::write(answers.join(format!("{:02}.rs", i + 1)), &a.code)?;
+        }
+    }
+
+    let tests = dir.join(TESTS_DIR);
+    if tests.exists() {
+        std::fs::remove_dir_all(&tests)?;
+    }
+    if !input.test_cases.is_empty() {
+        std::fs::create_dir_all(&tests)?;
+        for (i, tc) in input.test_cases.iter().enumerate() {
+            let stem = format!("{:02}", i + 1);
+            if !tc.stdin.is_empty() {
+                std::fs::write(tests.join(format!("{stem}.in")), &tc.stdin)?;
+            }
+            std::fs::write(tests.join(format!("{stem}.out")), &tc.expected_stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// root 直下の problem.toml を持つディレクトリを全部読む（ディレクトリ名順）
+pub fn read_all(root: &Path) -> anyhow::Result<Vec<ProblemInput>> {
+    let mut dirs: Vec<_> = std::fs::read_dir(root)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", root.display()))?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.join(MANIFEST).is_file())
+        .collect();
+    dirs.sort();
+    dirs.iter()
+        .map(|d| read_package(d).map_err(|e| anyhow::anyhow!("{}: {e}", d.display())))
+        .collect()
+}
+
+/// DB の全問題を ProblemInput として（slug 順）
+pub async fn load_all(pool: &SqlitePool) -> anyhow::Result<Vec<(i64, ProblemInput)>> {
+    let problems = sqlx::query_as::<_, Problem>(&format!("{PROBLEM_SELECT} ORDER BY slug"))
+        .fetch_all(pool)
+        .await?;
+    let mut out = Vec::new();
+    for p in problems {
+        let cases = fetch_test_cases(pool, p.id).await?;
+        let translations = fetch_translations(pool, Some(p.id)).await?;
+        let hints = fetch_hints(pool, p.id).await?;
+        let answers = fetch_answers(pool, p.id).await?;
+        let input = ProblemInput {
+            quiz: fetch_quiz(pool, p.id).await?,
+            blanks: fetch_blanks(pool, p.id).await?,
+            parsons: fetch_parsons(pool, p.id).await?,
+            ..ProblemInput::from_db(&p, &cases, &translations, &hints, &answers)
+        };
+        out.push((p.id, input));
+    }
+    Ok(out)
+}
+
+/* ==================== import / export ==================== */
+
+pub struct ImportOptions {
+    /// パッケージに無い問題を DB から消す（提出のある問題は消さない）
+    pub prune: bool,
+    /// 差分の表示だけ
+    pub dry_run: bool,
+}
+
+/// パッケージを slug 単位で DB に同期し、差分を表示する。検証エラーがあれば Err
+pub async fn import(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    root: &Path,
+    opts: &ImportOptions,
+) -> anyhow::Result<()> {
+    let packages = read_all(root)?;
+    let existing = load_all(pool).await?;
+
+    let mut failed = 0;
+    let mut unchanged = 0;
+    for pkg in &packages {
+        let current = existing.iter().find(|(_, p)| p.slug == pkg.slug);
+        let (label, id) = match current {
+            Some((_, p)) if p == pkg => {
+                unchanged += 1;
+                continue;
+            }
+            Some((id, _)) => ("changed", Some(*id)),
+            None => ("added", None),
+        };
+
+        let errors = validate_problem(pool, toolchains, pkg, id).await?;
+        if !errors.is_empty() {
+            failed += 1;
+            println!("invalid   {}", pkg.slug);
+            // コンパイルエラーは長いので先頭数行だけ
+            for e in errors {
+                println!("            {}:", e.field);
+                for line in e.message.lines().filter(|l| !l.trim().is_empty()).take(4) {
+                    println!("              {line}");
+                }
+            }
+            continue;
+        }
+        if !opts.dry_run {
+            save_problem(pool, pkg, id).await?;
+        }
+        println!("{label:<9} {}", pkg.slug);
+    }
+
+    for (id, p) in existing.iter().filter(|(_, p)| packages.iter().all(|k| k.slug != p.slug)) {
+        if !opts.prune {
+            println!("only-db   {} (use --prune to remove)", p.slug);
+            continue;
+        }
+        let (subs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM submissions WHERE problem_id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+        if subs > 0 {
+            println!("kept      {} (has {subs} submissions)", p.slug);
+            continue;
+        }
+        if !opts.dry_run {
+            sqlx::query("DELETE FROM problems WHERE id = ?").bind(id).execute(pool).await?;
+        }
+        println!("removed   {}", p.slug);
+    }
+
+    println!("{unchanged} unchanged{}", if opts.dry_run { " (dry run)" } else { "" });
+    if failed > 0 {
+        anyhow::bail!("{failed} package(s) failed validation");
+    }
+    Ok(())
+}
+
+/// DB の全問題を root/<slug>/ に書き出す
+pub async fn export(pool: &SqlitePool, root: &Path) -> anyhow::Result<()> {
+    for (_, p) in load_all(pool).await? {
+        write_package(&root.join(&p.slug), &p)?;
+        println!("exported  {}", p.slug);
+    }
+    Ok(())
+}
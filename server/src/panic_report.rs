@@ -0,0 +1,255 @@
+use serde::Serialize;
+
+/* ==================== パニック解析 ==================== */
+//
+// RUST_BACKTRACE=1 で実行したときの stderr から
+//   thread 'main' panicked at main.rs:2:5:
+//   index out of bounds: the len is 3 but the index is 7
+//   stack backtrace:
+//      3: main::get
+//                at /tmp/run-.../main.rs:2:5
+// のような部分を拾い、ユーザコード（main.rs）のフレームだけを残す。
+// 提出コードはエディタの全文なので、main.rs の行番号はそのままエディタの行番号になる。
+
+/// ユーザコードのファイル名（runner が work_dir 内でこの名前でコンパイルする）
+pub const USER_SOURCE: &str = "main.rs";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PanicReport {
+    pub thread: String,
+    pub message: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// ユーザコード内のフレームのみ（内側→外側）
+    pub backtrace: Vec<Frame>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Frame {
+    pub function: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl PanicReport {
+    /// stderr からパニック情報を取り出す。パニックしていなければ None
+    pub fn parse(stderr: &str) -> Option<PanicReport> {
+        let lines: Vec<&str> = stderr.lines().collect();
+        let head = lines.iter().position(|l| l.starts_with("thread '") && l.contains(" panicked at "))?;
+        let header = lines[head];
+
+        let thread = header["thread '".len()..]
+            .split('\'')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        // 1.73 以降: "panicked at main.rs:2:5:" の次の行からメッセージ
+        // それ以前: "panicked at 'msg', main.rs:2:5"
+        let after = &header[header.find(" panicked at ").unwrap() + " panicked at ".len()..];
+        let (message, loc) = if let Some(loc) = after.strip_suffix(':') {
+            let msg: Vec<&str> = lines[head + 1..]
+                .iter()
+                .take_while(|l| !l.starts_with("stack backtrace:") && !l.starts_with("note: "))
+                .copied()
+                .collect();
+            (msg.join("\n"), loc)
+        } else {
+            match after.rsplit_once("', ") {
+                Some((msg, loc)) => (msg.trim_start_matches('\'').to_string(), loc),
+                None => (after.to_string(), ""),
+            }
+        };
+
+        let (line, column) = match parse_location(loc) {
+            Some((path, l, c)) if is_user_source(path) => (Some(l), Some(c)),
+            _ => (None, None),
+        };
+
+        Some(PanicReport {
+            thread,
+            message,
+            line,
+            column,
+            backtrace: parse_user_frames(&lines[head..]),
+        })
+    }
+
+    /// 画面表示用（生の backtrace の代わりに出す）
+    pub fn render(&self) -> String {
+        let mut s = format!("thread '{}' panicked", self.thread);
+        if let (Some(l), Some(c)) = (self.line, self.column) {
+            s.push_str(&format!(" at {USER_SOURCE}:{l}:{c}"));
+        }
+        s.push_str(":\n");
+        s.push_str(&self.message);
+        s.push('\n');
+        if !self.backtrace.is_empty() {
+            s.push_str("stack backtrace (user code):\n");
+            for f in &self.backtrace {
+                s.push_str(&format!("  {} ({USER_SOURCE}:{}:{})\n", f.function, f.line, f.column));
+            }
+        }
+        s
+    }
+}
+
+/// "   3: main::get\n             at ./main.rs:2:5" の組からユーザコードのものだけ拾う
+fn parse_user_frames(lines: &[&str]) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut current: Option<String> = None;
+    for l in lines {
+        let t = l.trim_start();
+        if let Some(path_loc) = t.strip_prefix("at ") {
+            if let (Some(func), Some((path, line, column))) = (current.take(), parse_location(path_loc)) {
+                if is_user_source(path) {
+                    frames.push(Frame { function: func, line, column });
+                }
+            }
+        } else if let Some((idx, func)) = t.split_once(": ") {
+            if idx.chars().all(|c| c.is_ascii_digit()) {
+                current = Some(func.to_string());
+            }
+        }
+    }
+    frames
+}
+
+/// "path:line:col" を分解
+fn parse_location(loc: &str) -> Option<(&str, u32, u32)> {
+    let mut it = loc.trim().rsplitn(3, ':');
+    let column = it.next()?.parse().ok()?;
+    let line = it.next()?.parse().ok()?;
+    let path = it.next()?;
+    Some((path, line, column))
+}
+
+/// パニック位置は相対パス、バックトレースは work_dir の絶対パスで出るので両方受ける。
+/// std のフレームは /rustc/<hash>/library/... なので除外される
+fn is_user_source(path: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    path == USER_SOURCE
+        || (!path.starts_with("/rustc/") && path.strip_suffix(USER_SOURCE).is_some_and(|d| d.ends_with('/')))
+}
+
+/* ==================== 終了ステータス ==================== */
+
+/// よく出るシグナルだけ名前を付ける
+pub fn signal_name(sig: i32) -> Option<&'static str> {
+    Some(match sig {
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGILL => "SIGILL",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGPIPE => "SIGPIPE",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1.73 以降の形式で、std のフレームを挟んだ stderr
+    fn stderr(at: &str, message: &str, frames: &[(&str, &str)]) -> String {
+        let mut s = format!("thread 'main' panicked at {at}:\n{message}\nstack backtrace:\n");
+        let mut all = vec![
+            ("rust_begin_unwind", "/rustc/0123abcd/library/std/src/panicking.rs:665:5"),
+            ("core::panicking::panic_fmt", "/rustc/0123abcd/library/core/src/panicking.rs:76:14"),
+        ];
+        all.extend_from_slice(frames);
+        all.push(("core::ops::function::FnOnce::call_once", "/rustc/0123abcd/library/core/src/ops/function.rs:250:5"));
+        for (i, (func, loc)) in all.iter().enumerate() {
+            s.push_str(&format!("{i:>4}: {func}\n             at {loc}\n"));
+        }
+        s.push_str("note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.\n");
+        s
+    }
+
+    fn lines(frames: &[Frame]) -> Vec<(&str, u32, u32)> {
+        frames.iter().map(|f| (f.function.as_str(), f.line, f.column)).collect()
+    }
+
+    #[test]
+    fn parses_message_location_and_user_frames() {
+        let err = stderr(
+            "main.rs:2:5",
+            "index out of bounds: the len is 3 but the index is 7",
+            &[("main::get", "/tmp/run-1/main.rs:2:5"), ("main::main", "./main.rs:6:13")],
+        );
+        let r = PanicReport::parse(&err).unwrap();
+        assert_eq!(r.thread, "main");
+        assert_eq!(r.message, "index out of bounds: the len is 3 but the index is 7");
+        assert_eq!((r.line, r.column), (Some(2), Some(5)));
+        // std（/rustc/...）のフレームは落ちる
+        assert_eq!(lines(&r.backtrace), [("main::get", 2, 5), ("main::main", 6, 13)]);
+    }
+
+    #[test]
+    fn keeps_multi_line_messages() {
+        let err = stderr("main.rs:3:5", "assertion `left == right` failed\n  left: 1\n right: 2", &[]);
+        let r = PanicReport::parse(&err).unwrap();
+        assert_eq!(r.message, "assertion `left == right` failed\n  left: 1\n right: 2");
+        assert!(r.backtrace.is_empty());
+    }
+
+    #[test]
+    fn parses_the_old_single_line_format() {
+        let r = PanicReport::parse("thread 'main' panicked at 'boom', main.rs:4:9\nnote: run with ...\n").unwrap();
+        assert_eq!(r.message, "boom");
+        assert_eq!((r.line, r.column), (Some(4), Some(9)));
+    }
+
+    #[test]
+    fn panics_outside_user_code_have_no_location() {
+        let err = stderr(
+            "/rustc/0123abcd/library/core/src/option.rs:2012:5",
+            "called `Option::unwrap()` on a `None` value",
+            &[("main::main", "/tmp/run-1/main.rs:8:30")],
+        );
+        let r = PanicReport::parse(&err).unwrap();
+        assert_eq!((r.line, r.column), (None, None));
+        assert_eq!(lines(&r.backtrace), [("main::main", 8, 30)]);
+    }
+
+    #[test]
+    fn no_panic_is_none() {
+        assert!(PanicReport::parse("").is_none());
+        assert!(PanicReport::parse("warning: unused variable: `x`\n").is_none());
+    }
+
+    #[test]
+    fn lines_in_fixed_top_stay_editor_lines() {
+        // エディタ全文 = fixed_top（1〜4 行目）+ 編集範囲 + fixed_bottom。パニックは fixed_top の 2 行目
+        let fixed_top = "fn get(v: &[i32], i: usize) -> i32 {\n    v[i]\n}\nfn main() {\n";
+        let editable = "    let v = vec![1, 2, 3];\n    println!(\"{}\", get(&v, 7));\n";
+        let code = format!("{fixed_top}{editable}}}\n");
+        assert_eq!(code.lines().nth(1), Some("    v[i]"));
+        assert_eq!(code.lines().nth(5), Some("    println!(\"{}\", get(&v, 7));"));
+
+        let err = stderr(
+            "main.rs:2:5",
+            "index out of bounds: the len is 3 but the index is 7",
+            &[("main::get", "/tmp/run-1/main.rs:2:5"), ("main::main", "/tmp/run-1/main.rs:6:20")],
+        );
+        let r = PanicReport::parse(&err).unwrap();
+        assert_eq!(r.line, Some(2));
+        assert_eq!(lines(&r.backtrace), [("main::get", 2, 5), ("main::main", 6, 20)]);
+        assert_eq!(
+            r.render(),
+            "thread 'main' panicked at main.rs:2:5:\nindex out of bounds: the len is 3 but the index is 7\n\
+             stack backtrace (user code):\n  main::get (main.rs:2:5)\n  main::main (main.rs:6:20)\n"
+        );
+    }
+
+    #[test]
+    fn does_not_mistake_other_files_for_user_code() {
+        assert!(is_user_source("main.rs"));
+        assert!(is_user_source("./main.rs"));
+        assert!(is_user_source("/tmp/run-1/main.rs"));
+        assert!(!is_user_source("/tmp/run-1/not_main.rs"));
+        assert!(!is_user_source("/rustc/0123abcd/library/std/src/main.rs"));
+    }
+}
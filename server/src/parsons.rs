@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::admin::{field_error, FieldError};
+use crate::judge::{judge_code, JudgeResult};
+use crate::problem::{Problem, TestCase};
+use crate::toolchain::Toolchain;
+
+/* ==================== 並べ替え（Parsons） ==================== */
+//
+// kind = "parsons" の問題。正しい順の行と、紛らわしい行（distractor）を parsons_lines に持つ。
+// 学習者には行を決まった順で混ぜて見せ（混ぜ方は問題ごとに固定）、選んだ行の並びと字下げを /api/run に送ってもらう。
+// サーバが行をつなげて 1 本のプログラムにし、コード問題と同じく判定する（なので正しい順が複数あってもよい）。
+// check_indent なら、字下げが波括弧の深さと合っていなければコンパイルせずに WrongAnswer。
+// 提出は submissions.code に選んだ行の本文と字下げの JSON を残す（再判定では最新の行の中から選び直したものとして扱う）。
+
+/// 字下げ 1 段
+const INDENT: &str = "    ";
+/// 送られてくる字下げの上限
+const MAX_INDENT: u32 = 16;
+
+/// 問題の中身（管理 API の入力・problem.toml の [parsons] と同じ形）
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Parsons {
+    /// 字下げも採点する（メソッドチェーンの継続行などを字下げする問題では false に）
+    #[serde(default = "default_check_indent")]
+    pub check_indent: bool,
+    /// 正しい順の行（テーブルの配列なので最後に置く）
+    pub lines: Vec<ParsonsLine>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ParsonsLine {
+    /// 前後の空白を除いた 1 行
+    pub code: String,
+    /// 正解での字下げの段数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub indent: u32,
+    /// 正解には使わない行
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distractor: bool,
+}
+
+fn default_check_indent() -> bool {
+    true
+}
+
+// problem.toml を行ごとに `indent = 0` だらけにしない
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// 学習者向け（混ぜた順。添字が行の id。正しい順・distractor かどうかは含めない）
+#[derive(Serialize)]
+pub struct ParsonsView {
+    pub check_indent: bool,
+    pub lines: Vec<String>,
+}
+
+/// 学習者が選んだ 1 行（id は ParsonsView.lines の添字）
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct Pick {
+    pub id: usize,
+    #[serde(default)]
+    pub indent: u32,
+}
+
+/// 並べた 1 行（submissions.code に残す形）
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Placed {
+    pub code: String,
+    pub indent: u32,
+}
+
+impl Parsons {
+    /// 見せる順（元の添字の並び）。問題ごとに固定で、正しい順のままにはしない
+    fn display_order(&self, problem_id: i64) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.lines.len()).collect();
+        order.sort_by_key(|&i| fnv1a(&format!("{problem_id}:{i}:{}", self.lines[i].code)));
+        if order.windows(2).all(|w| w[0] < w[1]) {
+            order.rotate_left(1);
+        }
+        order
+    }
+
+    pub fn view(&self, problem_id: i64) -> ParsonsView {
+        let lines = self.display_order(problem_id).into_iter().map(|i| self.lines[i].code.clone()).collect();
+        ParsonsView { check_indent: self.check_indent, lines }
+    }
+
+    /// 選んだ id を行の本文に直す（同じ行は 1 回まで）
+    pub fn place(&self, problem_id: i64, picks: &[Pick]) -> Result<Vec<Placed>, Vec<FieldError>> {
+        let order = self.display_order(problem_id);
+        let mut used = vec![false; order.len()];
+        let mut errors = Vec::new();
+        if picks.is_empty() {
+            errors.push(field_error("lines", "required"));
+        }
+        for (n, p) in picks.iter().enumerate() {
+            if p.id >= order.len() {
+                errors.push(field_error(&format!("lines[{n}].id"), "no such line"));
+            } else if std::mem::replace(&mut used[p.id], true) {
+                errors.push(field_error(&format!("lines[{n}].id"), "used more than once"));
+            }
+            if p.indent > MAX_INDENT {
+                errors.push(field_error(&format!("lines[{n}].indent"), format!("at most {MAX_INDENT}")));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(picks
+            .iter()
+            .map(|p| Placed { code: self.lines[order[p.id]].code.clone(), indent: p.indent })
+            .collect())
+    }
+
+    /// 模範の並び（distractor 以外を正しい順・正しい字下げで）
+    pub fn reference(&self) -> Vec<Placed> {
+        self.lines
+            .iter()
+            .filter(|l| !l.distractor)
+            .map(|l| Placed { code: l.code.clone(), indent: l.indent })
+            .collect()
+    }
+
+    /// 並べた行が問題の行から選んだものか・字下げが合っているか。だめなら学習者に見せる理由
+    fn check(&self, placed: &[Placed]) -> Result<(), String> {
+        let mut left: Vec<&str> = self.lines.iter().map(|l| l.code.as_str()).collect();
+        for (n, p) in placed.iter().enumerate() {
+            match left.iter().position(|c| *c == p.code) {
+                Some(i) => {
+                    left.swap_remove(i);
+                }
+                None => return Err(format!("Line {} is not one of the given lines", n + 1)),
+            }
+        }
+        if self.check_indent {
+            let expected = expected_indents(placed.iter().map(|p| p.code.as_str()));
+            if let Some(n) = placed.iter().zip(&expected).position(|(p, e)| p.indent != *e) {
+                return Err(format!("Line {} is not indented correctly", n + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 行をつなげて 1 本のプログラムにする
+pub fn assemble(placed: &[Placed]) -> String {
+    placed
+        .iter()
+        .map(|p| format!("{}{}\n", INDENT.repeat(p.indent as usize), p.code))
+        .collect()
+}
+
+/// 波括弧の深さから決まる各行の字下げ（`}` で始まる行は 1 段浅い）。
+/// 文字列中の括弧も数えるが、書式文字列の `{}` は行内で釣り合うので困らない
+fn expected_indents<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<u32> {
+    let mut depth: i64 = 0;
+    let mut out = Vec::new();
+    for line in lines {
+        let line = line.trim();
+        let own = if line.starts_with('}') { depth - 1 } else { depth };
+        out.push(own.max(0) as u32);
+        depth += line.matches('{').count() as i64 - line.matches('}').count() as i64;
+    }
+    out
+}
+
+/// 見せる順を決めるためだけのハッシュ（Rust のバージョンで変わらないもの）
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/* ==================== 判定 ==================== */
+
+/// submissions.code に残す形
+pub fn stored(placed: &[Placed]) -> String {
+    json!({ "lines": placed }).to_string()
+}
+
+/// submissions.code から並べた行を読む（読めなければ None）
+pub fn parse_stored(code: &str) -> Option<Vec<Placed>> {
+    #[derive(Deserialize)]
+    struct Stored {
+        lines: Vec<Placed>,
+    }
+    serde_json::from_str::<Stored>(code).ok().map(|s| s.lines)
+}
+
+/// 行と字下げを確かめてからつなげて判定する（だめならコンパイルせずに WrongAnswer）
+pub async fn judge_parsons(
+    problem: &Problem,
+    cases: &[TestCase],
+    parsons: &Parsons,
+    placed: &[Placed],
+    toolchain: &Toolchain,
+) -> anyhow::Result<JudgeResult> {
+    match parsons.check(placed) {
+        Ok(()) => judge_code(problem, cases, &assemble(placed), toolchain).await,
+        Err(message) => Ok(JudgeResult::rejected(message)),
+    }
+}
+
+/* ==================== 検証・保存 ==================== */
+
+pub fn validate(parsons: &Parsons) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (i, l) in parsons.lines.iter().enumerate() {
+        if l.code.trim().is_empty() {
+            errors.push(field_error(&format!("parsons.lines[{i}].code"), "required"));
+        } else if l.code.contains('\n') {
+            errors.push(field_error(&format!("parsons.lines[{i}].code"), "must be a single line"));
+        } else if l.code != l.code.trim() {
+            errors.push(field_error(&format!("parsons.lines[{i}].code"), "use indent instead of leading spaces"));
+        }
+        if l.indent > MAX_INDENT {
+            errors.push(field_error(&format!("parsons.lines[{i}].indent"), format!("at most {MAX_INDENT}")));
+        }
+    }
+    let solution: Vec<(usize, &ParsonsLine)> =
+        parsons.lines.iter().enumerate().filter(|(_, l)| !l.distractor).collect();
+    if solution.len() < 2 {
+        errors.push(field_error("parsons.lines", "at least 2 lines that are not distractors"));
+    }
+    if parsons.check_indent {
+        let expected = expected_indents(solution.iter().map(|(_, l)| l.code.as_str()));
+        for ((i, l), e) in solution.iter().zip(expected) {
+            if l.indent != e {
+                let message = format!("should be {e} to match the braces");
+                errors.push(field_error(&format!("parsons.lines[{i}].indent"), message));
+            }
+        }
+    }
+    errors
+}
+
+pub async fn fetch_parsons(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Option<Parsons>> {
+    let item: Option<(bool,)> = sqlx::query_as("SELECT check_indent FROM parsons_items WHERE problem_id = ?")
+        .bind(problem_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some((check_indent,)) = item else {
+        return Ok(None);
+    };
+    let lines: Vec<(String, i64, bool)> =
+        sqlx::query_as("SELECT code, indent, distractor FROM parsons_lines WHERE problem_id = ? ORDER BY position")
+            .bind(problem_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(Some(Parsons {
+        check_indent,
+        lines: lines
+            .into_iter()
+            .map(|(code, indent, distractor)| ParsonsLine { code, indent: indent.max(0) as u32, distractor })
+            .collect(),
+    }))
+}
+
+/// 全置換（None なら行を消すだけ）。save_problem のトランザクション内で呼ぶ
+pub async fn save_parsons(conn: &mut SqliteConnection, problem_id: i64, parsons: Option<&Parsons>) -> sqlx::Result<()> {
+    for table in ["parsons_items", "parsons_lines"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE problem_id = ?"))
+            .bind(problem_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let Some(parsons) = parsons else {
+        return Ok(());
+    };
+    sqlx::query("INSERT INTO parsons_items (problem_id, check_indent) VALUES (?, ?)")
+        .bind(problem_id)
+        .bind(parsons.check_indent)
+        .execute(&mut *conn)
+        .await?;
+    for (i, l) in parsons.lines.iter().enumerate() {
+        sqlx::query("INSERT INTO parsons_lines (problem_id, position, code, indent, distractor) VALUES (?, ?, ?, ?, ?)")
+            .bind(problem_id)
+            .bind(i as i64)
+            .bind(&l.code)
+            .bind(i64::from(l.indent))
+            .bind(l.distractor)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(code: &str, indent: u32, distractor: bool) -> ParsonsLine {
+        ParsonsLine { code: code.into(), indent, distractor }
+    }
+
+    fn placed(lines: &[(&str, u32)]) -> Vec<Placed> {
+        lines.iter().map(|&(code, indent)| Placed { code: code.into(), indent }).collect()
+    }
+
+    /// 偶数の合計（distractor 2 行つき）
+    fn sum_evens() -> Parsons {
+        Parsons {
+            check_indent: true,
+            lines: vec![
+                line("fn main() {", 0, false),
+                line("let mut sum = 0;", 1, false),
+                line("for n in 1..=10 {", 1, false),
+                line("if n % 2 == 0 {", 2, false),
+                line("sum += n;", 3, false),
+                line("}", 2, false),
+                line("}", 1, false),
+                line("println!(\"{}\", sum);", 1, false),
+                line("}", 0, false),
+                line("let sum = 0;", 1, true),
+                line("if n % 2 == 1 {", 2, true),
+            ],
+        }
+    }
+
+    #[test]
+    fn expected_indents_follow_braces() {
+        let lines = ["fn main() {", "let v = vec![1];", "for x in &v {", "println!(\"{x}\");", "}", "}"];
+        assert_eq!(expected_indents(lines.into_iter()), [0, 1, 1, 2, 1, 0]);
+        // `} else {` は 1 段浅く、次の行は元の深さ
+        let lines = ["if a {", "x();", "} else {", "y();", "}"];
+        assert_eq!(expected_indents(lines.into_iter()), [0, 1, 0, 1, 0]);
+        // 閉じすぎても負にはならない
+        assert_eq!(expected_indents(["}", "x();"].into_iter()), [0, 0]);
+    }
+
+    #[test]
+    fn assemble_indents_each_line() {
+        let code = assemble(&placed(&[("fn main() {", 0), ("println!(\"{}\", 1);", 1), ("}", 0)]));
+        assert_eq!(code, "fn main() {\n    println!(\"{}\", 1);\n}\n");
+        assert_eq!(assemble(&[]), "");
+    }
+
+    #[test]
+    fn reference_skips_distractors() {
+        let p = sum_evens();
+        let reference = p.reference();
+        assert_eq!(reference.len(), 9);
+        assert!(p.check(&reference).is_ok());
+        assert!(assemble(&reference).contains("\n            sum += n;\n"));
+        assert!(validate(&p).is_empty());
+    }
+
+    #[test]
+    fn check_catches_indent_mismatch_with_distractors() {
+        let p = sum_evens();
+        // distractor を正しい深さに置くのは構わない（中身はコンパイル・実行で判定する）
+        let mut lines = placed(&[
+            ("fn main() {", 0),
+            ("let sum = 0;", 1),
+            ("for n in 1..=10 {", 1),
+            ("if n % 2 == 1 {", 2),
+            ("sum += n;", 3),
+            ("}", 2),
+            ("}", 1),
+            ("println!(\"{}\", sum);", 1),
+            ("}", 0),
+        ]);
+        assert_eq!(p.check(&lines), Ok(()));
+        // distractor の字下げがずれていれば、その行で止まる
+        lines[3].indent = 1;
+        assert_eq!(p.check(&lines), Err("Line 4 is not indented correctly".into()));
+        // 字下げを採点しない問題なら通す
+        let relaxed = Parsons { check_indent: false, ..sum_evens() };
+        assert_eq!(relaxed.check(&lines), Ok(()));
+    }
+
+    #[test]
+    fn check_rejects_lines_that_were_not_given() {
+        let p = sum_evens();
+        let lines = placed(&[("fn main() {", 0), ("let mut total = 0;", 1), ("}", 0)]);
+        assert_eq!(p.check(&lines), Err("Line 2 is not one of the given lines".into()));
+        // 同じ行は問題にある数までしか使えない
+        let lines = placed(&[("fn main() {", 0), ("fn main() {", 1), ("}", 1), ("}", 0)]);
+        assert_eq!(p.check(&lines), Err("Line 2 is not one of the given lines".into()));
+    }
+
+    #[test]
+    fn validate_ignores_distractor_indents() {
+        let mut p = sum_evens();
+        p.lines[10].indent = 7;
+        assert!(validate(&p).is_empty());
+        p.lines[4].indent = 2;
+        let errors = validate(&p);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "parsons.lines[4].indent");
+    }
+
+    #[test]
+    fn place_maps_display_ids_back_to_lines() {
+        let p = sum_evens();
+        let view = p.view(4);
+        assert_eq!(view.lines.len(), p.lines.len());
+        let want = view.lines.iter().position(|c| c == "sum += n;").unwrap();
+        let got = p.place(4, &[Pick { id: want, indent: 3 }]).ok().unwrap();
+        assert_eq!((got[0].code.as_str(), got[0].indent), ("sum += n;", 3));
+        assert!(p.place(4, &[Pick { id: want, indent: 0 }, Pick { id: want, indent: 0 }]).is_err());
+        assert!(p.place(4, &[Pick { id: 99, indent: 0 }]).is_err());
+        assert!(p.place(4, &[]).is_err());
+    }
+
+    #[test]
+    fn stored_lines_round_trip() {
+        let lines = placed(&[("fn main() {", 0), ("}", 0)]);
+        let back = parse_stored(&stored(&lines)).unwrap();
+        assert_eq!(assemble(&back), assemble(&lines));
+        assert!(parse_stored("fn main() {}").is_none());
+    }
+}
@@ -0,0 +1,410 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqlitePool};
+
+use crate::blanks::BlanksView;
+use crate::locale;
+use crate::markdown;
+use crate::parsons::ParsonsView;
+use crate::quiz::QuizView;
+use crate::runner::{CompileOptions, RunLimits, DEFAULT_OUTPUT_LIMIT_BYTES};
+
+/* ==================== データモデル ==================== */
+
+#[derive(FromRow, Serialize)]
+pub struct Problem {
+    pub id: i64,
+    pub slug: String,
+    // ★ 追加: 問題の種類（KINDS のどれか）
+    pub kind: String,
+    pub title: String,
+    pub description: String,
+    // ★ 追加: description（Markdown）をサニタイズ済み HTML にしたもの（DB には持たない）
+    #[sqlx(skip)]
+    pub description_html: String,
+    // ★ 追加: title / description がどのロケールのものか
+    #[sqlx(skip)]
+    pub locale: String,
+    // ★ 追加: ヒントの段数（中身は /api/problems/{id}/hints で 1 つずつ開く）
+    pub hint_count: i64,
+    pub starter_code: String,
+    pub expected_stdout: String,
+    pub fixed_top: Option<String>,
+    pub fixed_bottom: Option<String>,
+    // ★ 追加: 編集範囲マーカー（NULL可）
+    pub editable_start_marker: Option<String>,
+    pub editable_end_marker:   Option<String>,
+    // ★ 追加: リソース上限（NULL なら無制限）
+    pub memory_limit_kb:    Option<i64>,
+    pub cpu_time_limit_ms:  Option<i64>,
+    pub output_limit_bytes: Option<i64>,
+    // ★ 追加: コンパイル設定
+    pub edition:          String,
+    pub opt_level:        String,
+    pub debug_assertions: bool,
+    pub toolchain:        Option<String>,
+    // ★ 追加: 模範解答（学習者向けには返さない）
+    #[serde(skip_serializing)]
+    pub solution_code: Option<String>,
+    // ★ 追加: 分類（NULL可）とタグ
+    pub difficulty: Option<String>,
+    pub category:   Option<String>,
+    #[sqlx(try_from = "String")]
+    pub tags:       Tags,
+    // ★ 追加: 内容の版（保存で内容が変わるたびに増える）
+    pub revision:   i64,
+    pub created_at: String,
+    // ★ 追加: クイズの選択肢など（kind = "quiz" のときだけ。API で返す前に埋める）
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quiz: Option<QuizView>,
+    // ★ 追加: 穴埋めのテンプレートを穴で区切ったもの（kind = "blanks" のときだけ）
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blanks: Option<BlanksView>,
+    // ★ 追加: 並べ替えの行（混ぜた順。kind = "parsons" のときだけ）
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parsons: Option<ParsonsView>,
+}
+
+/// code = コードを書いて判定 / quiz = 選択式・短答式（quiz.rs）/ blanks = 穴埋め（blanks.rs）
+/// / parsons = 行の並べ替え（parsons.rs）
+pub const KINDS: &[&str] = &[KIND_CODE, KIND_QUIZ, KIND_BLANKS, KIND_PARSONS];
+pub const KIND_CODE: &str = "code";
+pub const KIND_QUIZ: &str = "quiz";
+pub const KIND_BLANKS: &str = "blanks";
+pub const KIND_PARSONS: &str = "parsons";
+
+pub const DIFFICULTIES: &[&str] = &["easy", "medium", "hard"];
+
+pub const CATEGORIES: &[&str] = &[
+    "basics", "ownership", "borrowing", "lifetimes", "structs", "enums", "pattern-matching", "traits",
+    "generics", "closures", "iterators", "collections", "error-handling", "modules", "smart-pointers",
+    "concurrency", "async", "macros", "unsafe",
+];
+
+/// problem_tags を GROUP_CONCAT したもの（"a,b"）を並べ替えて持つ
+#[derive(Serialize, Clone, Default, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct Tags(pub Vec<String>);
+
+impl From<String> for Tags {
+    fn from(csv: String) -> Self {
+        let mut tags: Vec<String> = csv.split(',').filter(|t| !t.is_empty()).map(str::to_string).collect();
+        tags.sort();
+        Tags(tags)
+    }
+}
+
+// problems の SELECT 句（WHERE / ORDER BY は呼び出し側で付ける）
+pub const PROBLEM_SELECT: &str = r#"
+    SELECT
+      id, slug, kind, title, description, starter_code, expected_stdout,
+      fixed_top, fixed_bottom,
+      editable_start_marker, editable_end_marker,
+      memory_limit_kb, cpu_time_limit_ms, output_limit_bytes,
+      edition, opt_level, debug_assertions, toolchain,
+      solution_code,
+      difficulty, category,
+      COALESCE((SELECT GROUP_CONCAT(tag, ',') FROM problem_tags WHERE problem_id = problems.id), '') AS tags,
+      (SELECT COUNT(*) FROM problem_hints WHERE problem_id = problems.id) AS hint_count,
+      revision, created_at
+    FROM problems
+"#;
+
+impl Problem {
+    /// API で返す前に description_html を埋める
+    pub fn with_rendered_description(mut self) -> Self {
+        self.description_html = markdown::render(&self.description);
+        self
+    }
+
+    pub fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            edition: self.edition.clone(),
+            opt_level: self.opt_level.clone(),
+            debug_assertions: self.debug_assertions,
+        }
+    }
+
+    // ★ 変更: 出力に加えてメモリ・CPU 時間の上限も実行時に課す
+    pub fn run_limits(&self) -> RunLimits {
+        RunLimits {
+            output_bytes: self
+                .output_limit_bytes
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(DEFAULT_OUTPUT_LIMIT_BYTES),
+            memory_kb: self.memory_limit_kb,
+            cpu_time_ms: self.cpu_time_limit_ms,
+        }
+    }
+}
+
+pub async fn fetch_problem(pool: &SqlitePool, id: i64) -> sqlx::Result<Problem> {
+    sqlx::query_as::<_, Problem>(&format!("{PROBLEM_SELECT} WHERE id = ?"))
+        .bind(id)
+        .fetch_one(pool)
+        .await
+}
+
+/// slug → (id, 現在の slug)。旧 slug なら現在の slug を返すので、呼び出し側でリダイレクトできる
+pub async fn resolve_slug(pool: &SqlitePool, slug: &str) -> sqlx::Result<Option<(i64, String)>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, slug FROM problems WHERE slug = ?1
+        UNION ALL
+        SELECT p.id, p.slug FROM problem_slug_history h JOIN problems p ON p.id = h.problem_id
+        WHERE h.slug = ?1
+        LIMIT 1
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+}
+
+/* ==================== 翻訳 ==================== */
+
+/// 既定ロケール（problems 本体）以外の title / description / ヒント
+#[derive(FromRow, Serialize, Clone)]
+pub struct Translation {
+    #[serde(skip)]
+    pub problem_id: i64,
+    pub locale: String,
+    pub title: String,
+    pub description: String,
+    /// problem_hint_translations（position 順）
+    #[sqlx(skip)]
+    pub hints: Vec<String>,
+}
+
+pub async fn fetch_translations(pool: &SqlitePool, problem_id: Option<i64>) -> sqlx::Result<Vec<Translation>> {
+    let mut translations = sqlx::query_as::<_, Translation>(
+        r#"
+        SELECT problem_id, locale, title, description
+        FROM problem_translations
+        WHERE ? IS NULL OR problem_id = ?
+        ORDER BY problem_id, locale
+        "#,
+    )
+    .bind(problem_id)
+    .bind(problem_id)
+    .fetch_all(pool)
+    .await?;
+    let hints: Vec<(i64, String, String)> = sqlx::query_as(
+        r#"
+        SELECT problem_id, locale, body
+        FROM problem_hint_translations
+        WHERE ? IS NULL OR problem_id = ?
+        ORDER BY problem_id, locale, position
+        "#,
+    )
+    .bind(problem_id)
+    .bind(problem_id)
+    .fetch_all(pool)
+    .await?;
+    for (id, locale, body) in hints {
+        if let Some(t) = translations.iter_mut().find(|t| t.problem_id == id && t.locale == locale) {
+            t.hints.push(body);
+        }
+    }
+    Ok(translations)
+}
+
+impl Problem {
+    /// chain（locale::negotiate の結果）の先頭から、翻訳があるロケールで差し替える。
+    /// description が空の翻訳は本体（既定ロケール）のものを使う
+    pub fn localize(mut self, translations: &[Translation], chain: &[&str]) -> Self {
+        self.locale = locale::DEFAULT_LOCALE.to_string();
+        if let Some(t) = pick_translation(translations, self.id, chain) {
+            self.title = t.title.clone();
+            if !t.description.trim().is_empty() {
+                self.description = t.description.clone();
+            }
+            self.locale = t.locale.clone();
+        }
+        self
+    }
+}
+
+/// chain のうち最初に翻訳があるロケールのもの（既定ロケールに着いたら本体を使うので None）
+pub fn pick_translation<'a>(
+    translations: &'a [Translation],
+    problem_id: i64,
+    chain: &[&str],
+) -> Option<&'a Translation> {
+    chain
+        .iter()
+        .take_while(|l| **l != locale::DEFAULT_LOCALE)
+        .find_map(|l| translations.iter().find(|t| t.problem_id == problem_id && t.locale == *l))
+}
+
+/* ==================== 一覧（軽量版） ==================== */
+
+/// 一覧用。本文・コードは含めない
+#[derive(FromRow, Serialize)]
+pub struct ProblemSummary {
+    pub id: i64,
+    pub slug: String,
+    pub kind: String,
+    pub title: String,
+    pub difficulty: Option<String>,
+    pub category: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub tags: Tags,
+    #[sqlx(skip)]
+    pub locale: String,
+}
+
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// 一覧の絞り込み（`tags` は全部付いている問題だけ）
+#[derive(Deserialize)]
+pub struct ProblemFilter {
+    pub difficulty: Option<String>,
+    pub category: Option<String>,
+    /// カンマ区切り
+    pub tags: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+#[derive(Serialize)]
+pub struct ProblemPage {
+    pub items: Vec<ProblemSummary>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+fn push_filter<'a>(qb: &mut QueryBuilder<'a, Sqlite>, f: &'a ProblemFilter, tags: &'a [&'a str]) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(d) = &f.difficulty {
+        qb.push(" AND difficulty = ").push_bind(d);
+    }
+    if let Some(c) = &f.category {
+        qb.push(" AND category = ").push_bind(c);
+    }
+    if !tags.is_empty() {
+        qb.push(" AND (SELECT COUNT(*) FROM problem_tags t WHERE t.problem_id = problems.id AND t.tag IN (");
+        let mut sep = qb.separated(", ");
+        for t in tags {
+            sep.push_bind(*t);
+        }
+        qb.push(")) = ").push_bind(tags.len() as i64);
+    }
+}
+
+pub async fn list_summaries(
+    pool: &SqlitePool,
+    f: &ProblemFilter,
+    chain: &[&str],
+) -> sqlx::Result<ProblemPage> {
+    let tags: Vec<&str> = f
+        .tags
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect();
+    let per_page = f.per_page.clamp(1, MAX_PER_PAGE);
+    let page = f.page.max(1);
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM problems");
+    push_filter(&mut count, f, &tags);
+    let (total,): (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    let mut qb = QueryBuilder::new(
+        r#"
+        SELECT id, slug, kind, title, difficulty, category,
+          COALESCE((SELECT GROUP_CONCAT(tag, ',') FROM problem_tags WHERE problem_id = problems.id), '') AS tags
+        FROM problems"#,
+    );
+    push_filter(&mut qb, f, &tags);
+    qb.push(" ORDER BY id LIMIT ").push_bind(per_page).push(" OFFSET ").push_bind((page - 1) * per_page);
+    let rows: Vec<ProblemSummary> = qb.build_query_as().fetch_all(pool).await?;
+
+    let translations = fetch_translations(pool, None).await?;
+    let items = rows
+        .into_iter()
+        .map(|mut p| {
+            p.locale = locale::DEFAULT_LOCALE.to_string();
+            if let Some(t) = pick_translation(&translations, p.id, chain) {
+                p.title = t.title.clone();
+                p.locale = t.locale.clone();
+            }
+            p
+        })
+        .collect();
+    Ok(ProblemPage { items, total, page, per_page })
+}
+
+/// 絞り込み UI 用: 使われている難易度・分類・タグと件数
+pub async fn facets(pool: &SqlitePool) -> sqlx::Result<serde_json::Value> {
+    let count = |sql: &'static str| sqlx::query_as::<_, (String, i64)>(sql).fetch_all(pool);
+    let difficulties = count(
+        "SELECT difficulty, COUNT(*) FROM problems WHERE difficulty IS NOT NULL GROUP BY difficulty",
+    )
+    .await?;
+    let categories = count(
+        "SELECT category, COUNT(*) FROM problems WHERE category IS NOT NULL GROUP BY category ORDER BY category",
+    )
+    .await?;
+    let tags = count("SELECT tag, COUNT(*) FROM problem_tags GROUP BY tag ORDER BY tag").await?;
+    let as_list = |v: Vec<(String, i64)>| {
+        v.into_iter().map(|(name, count)| json!({ "name": name, "count": count })).collect::<Vec<_>>()
+    };
+    Ok(json!({
+        "difficulties": as_list(difficulties),
+        "categories": as_list(categories),
+        "tags": as_list(tags),
+    }))
+}
+
+/* ==================== テストケース ==================== */
+
+#[derive(FromRow, Serialize, Clone)]
+pub struct TestCase {
+    pub position: i64,
+    pub stdin: String,
+    pub expected_stdout: String,
+}
+
+/// 判定に使うケース。test_cases が空なら problems.expected_stdout を唯一のケースとみなす
+pub async fn judge_cases(pool: &SqlitePool, problem: &Problem) -> sqlx::Result<Vec<TestCase>> {
+    let cases = fetch_test_cases(pool, problem.id).await?;
+    if !cases.is_empty() {
+        return Ok(cases);
+    }
+    Ok(vec![TestCase {
+        position: 0,
+        stdin: String::new(),
+        expected_stdout: problem.expected_stdout.clone(),
+    }])
+}
+
+pub async fn fetch_test_cases(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Vec<TestCase>> {
+    sqlx::query_as::<_, TestCase>(
+        r#"
+        SELECT position, stdin, expected_stdout
+        FROM test_cases
+        WHERE problem_id = ?
+        ORDER BY position
+        "#,
+    )
+    .bind(problem_id)
+    .fetch_all(pool)
+    .await
+}
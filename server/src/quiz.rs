@@ -0,0 +1,435 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::account::CurrentUser;
+use crate::course;
+use crate::admin::{field_error, validation_failed, FieldError};
+use crate::judge::Verdict;
+use crate::problem::fetch_problem;
+use crate::{save_submission, AppState, NewSubmission};
+
+/* ==================== クイズ（選択式・短答式） ==================== */
+//
+// kind = "quiz" の問題。問題文（description）に設問を書き、選択肢か受け付ける表記を quiz_* に持つ。
+// 解答はコード問題と同じく submissions に残す（code 列に QuizResponse の JSON、判定は Accepted / WrongAnswer）。
+// なので正解済みの判定（コースの進み具合・解答の公開）や再判定はコード問題と共通。
+// 選択肢の翻訳はまだ無い（どのロケールでも同じ本文を出す）。
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuizFormat {
+    /// 正解の選択肢がちょうど 1 つ
+    Single,
+    /// 正解の選択肢をすべて選ぶ（1 つ以上）
+    Multiple,
+    /// 文字列で答える（accepted のどれかと一致すれば正解）
+    Text,
+}
+
+impl QuizFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuizFormat::Single => "single",
+            QuizFormat::Multiple => "multiple",
+            QuizFormat::Text => "text",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [QuizFormat::Single, QuizFormat::Multiple, QuizFormat::Text]
+            .into_iter()
+            .find(|f| f.as_str() == s)
+    }
+}
+
+/// クイズの中身（管理 API の入力・problem.toml の [quiz] と同じ形。正解を含む）
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Quiz {
+    pub format: QuizFormat,
+    /// text のとき大文字・小文字を区別する
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// text で正解とする表記（前後の空白と連続する空白は無視して比べる）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted: Vec<String>,
+    /// single / multiple の選択肢（テーブルの配列なので最後に置く）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<Choice>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Choice {
+    pub body: String,
+    #[serde(default)]
+    pub correct: bool,
+}
+
+/// 学習者向け（正解は含めない）
+#[derive(Serialize)]
+pub struct QuizView {
+    pub format: QuizFormat,
+    pub choices: Vec<String>,
+}
+
+impl Quiz {
+    pub fn view(&self) -> QuizView {
+        QuizView { format: self.format, choices: self.choices.iter().map(|c| c.body.clone()).collect() }
+    }
+
+    fn correct_choices(&self) -> Vec<usize> {
+        self.choices.iter().enumerate().filter(|(_, c)| c.correct).map(|(i, _)| i).collect()
+    }
+
+    fn normalize(&self, s: &str) -> String {
+        let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        if self.case_sensitive {
+            s
+        } else {
+            s.to_lowercase()
+        }
+    }
+
+    /// 正解なら Accepted、それ以外は WrongAnswer
+    pub fn grade(&self, r: &QuizResponse) -> Verdict {
+        let ok = match self.format {
+            QuizFormat::Single | QuizFormat::Multiple => {
+                let mut chosen = r.choices.clone();
+                chosen.sort_unstable();
+                chosen.dedup();
+                chosen == self.correct_choices()
+            }
+            QuizFormat::Text => {
+                let given = self.normalize(&r.text);
+                // 空の表記では何も受け付けない（空の解答を正解にしない）
+                self.accepted.iter().filter(|a| !a.trim().is_empty()).any(|a| self.normalize(a) == given)
+            }
+        };
+        if ok {
+            Verdict::Accepted
+        } else {
+            Verdict::WrongAnswer
+        }
+    }
+
+    /// submissions.code に保存した解答を採点し直す（読めない JSON は WrongAnswer）
+    pub fn grade_stored(&self, code: &str) -> Verdict {
+        match serde_json::from_str::<QuizResponse>(code) {
+            Ok(r) => self.grade(&r),
+            Err(_) => Verdict::WrongAnswer,
+        }
+    }
+}
+
+/// 学習者の解答（choices は 0 始まりの添字）
+#[derive(Deserialize, Serialize, Default)]
+pub struct QuizResponse {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<usize>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+}
+
+/* ==================== 検証・保存 ==================== */
+
+/// 形式ごとに必要なものが揃っているか
+pub fn validate(quiz: &Quiz) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (i, c) in quiz.choices.iter().enumerate() {
+        if c.body.trim().is_empty() {
+            errors.push(field_error(&format!("quiz.choices[{i}].body"), "required"));
+        }
+    }
+    for (i, a) in quiz.accepted.iter().enumerate() {
+        if a.trim().is_empty() {
+            errors.push(field_error(&format!("quiz.accepted[{i}]"), "required"));
+        }
+    }
+    let correct = quiz.correct_choices().len();
+    match quiz.format {
+        QuizFormat::Single | QuizFormat::Multiple if quiz.choices.len() < 2 => {
+            errors.push(field_error("quiz.choices", "at least 2 choices"));
+        }
+        QuizFormat::Single if correct != 1 => {
+            errors.push(field_error("quiz.choices", "exactly one choice must be correct"));
+        }
+        QuizFormat::Multiple if correct == 0 => {
+            errors.push(field_error("quiz.choices", "at least one choice must be correct"));
+        }
+        QuizFormat::Text if !quiz.choices.is_empty() => {
+            errors.push(field_error("quiz.choices", "text questions have no choices"));
+        }
+        QuizFormat::Text if quiz.accepted.is_empty() => {
+            errors.push(field_error("quiz.accepted", "at least one accepted answer"));
+        }
+        _ => {}
+    }
+    if quiz.format != QuizFormat::Text && !quiz.accepted.is_empty() {
+        errors.push(field_error("quiz.accepted", "only for text questions"));
+    }
+    errors
+}
+
+pub async fn fetch_quiz(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Option<Quiz>> {
+    let item: Option<(String, bool)> =
+        sqlx::query_as("SELECT format, case_sensitive FROM quiz_items WHERE problem_id = ?")
+            .bind(problem_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some((format, case_sensitive)) = item else {
+        return Ok(None);
+    };
+    let format = QuizFormat::parse(&format)
+        .ok_or_else(|| sqlx::Error::Decode(format!("unknown quiz format: {format}").into()))?;
+    let choices: Vec<(String, bool)> =
+        sqlx::query_as("SELECT body, correct FROM quiz_choices WHERE problem_id = ? ORDER BY position")
+            .bind(problem_id)
+            .fetch_all(pool)
+            .await?;
+    let accepted: Vec<(String,)> =
+        sqlx::query_as("SELECT answer FROM quiz_accepted_answers WHERE problem_id = ? ORDER BY position")
+            .bind(problem_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(Some(Quiz {
+        format,
+        case_sensitive,
+        accepted: accepted.into_iter().map(|(a,)| a).collect(),
+        choices: choices.into_iter().map(|(body, correct)| Choice { body, correct }).collect(),
+    }))
+}
+
+/// 全置換（None ならクイズの行を消すだけ）。save_problem のトランザクション内で呼ぶ
+pub async fn save_quiz(conn: &mut SqliteConnection, problem_id: i64, quiz: Option<&Quiz>) -> sqlx::Result<()> {
+    for table in ["quiz_items", "quiz_choices", "quiz_accepted_answers"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE problem_id = ?"))
+            .bind(problem_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let Some(quiz) = quiz else {
+        return Ok(());
+    };
+    sqlx::query("INSERT INTO quiz_items (problem_id, format, case_sensitive) VALUES (?, ?, ?)")
+        .bind(problem_id)
+        .bind(quiz.format.as_str())
+        .bind(quiz.case_sensitive)
+        .execute(&mut *conn)
+        .await?;
+    for (i, c) in quiz.choices.iter().enumerate() {
+        sqlx::query("INSERT INTO quiz_choices (problem_id, position, body, correct) VALUES (?, ?, ?, ?)")
+            .bind(problem_id)
+            .bind(i as i64)
+            .bind(&c.body)
+            .bind(c.correct)
+            .execute(&mut *conn)
+            .await?;
+    }
+    for (i, a) in quiz.accepted.iter().enumerate() {
+        sqlx::query("INSERT INTO quiz_accepted_answers (problem_id, position, answer) VALUES (?, ?, ?)")
+            .bind(problem_id)
+            .bind(i as i64)
+            .bind(a)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/* ==================== ハンドラ ==================== */
+
+#[derive(Serialize)]
+struct QuizResult {
+    verdict: Verdict,
+    passed: bool,
+}
+
+/// 解答を採点して提出として保存する
+#[post("/api/problems/{id}/quiz")]
+async fn answer_quiz(
+    user: CurrentUser,
+    path: web::Path<i64>,
+    req: web::Json<QuizResponse>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let problem = match fetch_problem(&state.pool, id).await {
+        Ok(p) => p,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+    if let Err(resp) = course::ensure_unlocked(&state.pool, user.id, problem.id).await {
+        return resp;
+    }
+    let quiz = match fetch_quiz(&state.pool, id).await {
+        Ok(Some(q)) => q,
+        Ok(None) => {
+            return HttpResponse::BadRequest()
+                .json(json!({ "error": "not_a_quiz", "message": "this problem is answered with code" }))
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    };
+
+    // 範囲外の添字や、単一選択での複数回答は採点せずに返す
+    if req.choices.iter().any(|&i| i >= quiz.choices.len()) {
+        return validation_failed(vec![field_error("choices", "no such choice")]);
+    }
+    if quiz.format == QuizFormat::Single && req.choices.len() > 1 {
+        return validation_failed(vec![field_error("choices", "choose one")]);
+    }
+
+    let verdict = quiz.grade(&req);
+    let code = json!(*req).to_string();
+    save_submission(
+        &state.pool,
+        NewSubmission {
+            user_id: user.id,
+            problem_id: id,
+            problem_revision: problem.revision,
+            code: &code,
+            output: "",
+            verdict,
+            usage: None,
+            toolchain_version: None,
+        },
+    )
+    .await;
+
+    HttpResponse::Ok().json(QuizResult { verdict, passed: verdict == Verdict::Accepted })
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(answer_quiz);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choices(correct: &[bool]) -> Vec<Choice> {
+        correct.iter().enumerate().map(|(i, &c)| Choice { body: format!("choice {i}"), correct: c }).collect()
+    }
+
+    fn multiple() -> Quiz {
+        Quiz {
+            format: QuizFormat::Multiple,
+            case_sensitive: false,
+            accepted: vec![],
+            choices: choices(&[true, false, true]),
+        }
+    }
+
+    fn text(accepted: &[&str], case_sensitive: bool) -> Quiz {
+        Quiz {
+            format: QuizFormat::Text,
+            case_sensitive,
+            accepted: accepted.iter().map(|a| a.to_string()).collect(),
+            choices: vec![],
+        }
+    }
+
+    fn picked(choices: &[usize]) -> QuizResponse {
+        QuizResponse { choices: choices.to_vec(), ..Default::default() }
+    }
+
+    fn typed(text: &str) -> QuizResponse {
+        QuizResponse { text: text.into(), ..Default::default() }
+    }
+
+    /// 検証エラーの (field, message)
+    fn errors(quiz: &Quiz) -> Vec<(String, String)> {
+        validate(quiz).into_iter().map(|e| (e.field, e.message)).collect()
+    }
+
+    fn error(field: &str, message: &str) -> Vec<(String, String)> {
+        vec![(field.to_string(), message.to_string())]
+    }
+
+    #[test]
+    fn multiple_choice_ignores_order_and_duplicates() {
+        let q = multiple();
+        assert_eq!(q.grade(&picked(&[0, 2])), Verdict::Accepted);
+        assert_eq!(q.grade(&picked(&[2, 0])), Verdict::Accepted);
+        assert_eq!(q.grade(&picked(&[2, 0, 2, 0])), Verdict::Accepted);
+        // 足りない・余計なものがある・範囲外
+        assert_eq!(q.grade(&picked(&[0])), Verdict::WrongAnswer);
+        assert_eq!(q.grade(&picked(&[0, 1, 2])), Verdict::WrongAnswer);
+        assert_eq!(q.grade(&picked(&[0, 2, 9])), Verdict::WrongAnswer);
+        assert_eq!(q.grade(&picked(&[])), Verdict::WrongAnswer);
+    }
+
+    #[test]
+    fn single_choice_needs_the_one_correct_choice() {
+        let q = Quiz { format: QuizFormat::Single, choices: choices(&[false, true]), ..multiple() };
+        assert_eq!(q.grade(&picked(&[1])), Verdict::Accepted);
+        assert_eq!(q.grade(&picked(&[1, 1])), Verdict::Accepted);
+        assert_eq!(q.grade(&picked(&[0, 1])), Verdict::WrongAnswer);
+    }
+
+    #[test]
+    fn text_answers_normalize_whitespace() {
+        let q = text(&["Box<dyn Error>", "Rc<RefCell<T>>"], false);
+        assert_eq!(q.grade(&typed("  box<dyn   error> ")), Verdict::Accepted);
+        assert_eq!(q.grade(&typed("Box<dyn\tError>\n")), Verdict::Accepted);
+        assert_eq!(q.grade(&typed("rc<refcell<t>>")), Verdict::Accepted);
+        // 空白を詰めた別の表記にはしない
+        assert_eq!(q.grade(&typed("Box<dynError>")), Verdict::WrongAnswer);
+    }
+
+    #[test]
+    fn text_answers_can_be_case_sensitive() {
+        let q = text(&["String"], true);
+        assert_eq!(q.grade(&typed(" String ")), Verdict::Accepted);
+        assert_eq!(q.grade(&typed("string")), Verdict::WrongAnswer);
+    }
+
+    #[test]
+    fn empty_accepted_variants_accept_nothing() {
+        let q = text(&["Box<T>", "", "  "], false);
+        assert_eq!(q.grade(&typed("")), Verdict::WrongAnswer);
+        assert_eq!(q.grade(&typed("   ")), Verdict::WrongAnswer);
+        assert_eq!(q.grade(&typed("box<t>")), Verdict::Accepted);
+    }
+
+    #[test]
+    fn grade_stored_reads_the_saved_json() {
+        let q = multiple();
+        assert_eq!(q.grade_stored(r#"{"choices":[2,0]}"#), Verdict::Accepted);
+        assert_eq!(q.grade_stored(r#"{"choices":[1]}"#), Verdict::WrongAnswer);
+        assert_eq!(q.grade_stored("fn main() {}"), Verdict::WrongAnswer);
+        let stored = serde_json::to_string(&typed("Box<T>")).unwrap();
+        assert_eq!(text(&["box<t>"], false).grade_stored(&stored), Verdict::Accepted);
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_quizzes() {
+        assert!(errors(&multiple()).is_empty());
+        assert!(errors(&text(&["Box<T>"], false)).is_empty());
+    }
+
+    #[test]
+    fn validate_reports_each_problem() {
+        let mut q = multiple();
+        q.choices[1].body = " ".into();
+        assert_eq!(errors(&q), error("quiz.choices[1].body", "required"));
+
+        let q = Quiz { choices: choices(&[true]), ..multiple() };
+        assert_eq!(errors(&q), error("quiz.choices", "at least 2 choices"));
+
+        let q = Quiz { format: QuizFormat::Single, ..multiple() };
+        assert_eq!(errors(&q), error("quiz.choices", "exactly one choice must be correct"));
+
+        let q = Quiz { choices: choices(&[false, false]), ..multiple() };
+        assert_eq!(errors(&q), error("quiz.choices", "at least one choice must be correct"));
+
+        let q = Quiz { choices: choices(&[true, false]), ..text(&["a"], false) };
+        assert_eq!(errors(&q), error("quiz.choices", "text questions have no choices"));
+
+        assert_eq!(errors(&text(&[], false)), error("quiz.accepted", "at least one accepted answer"));
+        assert_eq!(errors(&text(&["Box<T>", ""], false)), error("quiz.accepted[1]", "required"));
+
+        let q = Quiz { accepted: vec!["a".into()], ..multiple() };
+        assert_eq!(errors(&q), error("quiz.accepted", "only for text questions"));
+    }
+}
@@ -0,0 +1,389 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+use std::collections::{hash_map::Entry, HashMap};
+use std::sync::Arc;
+
+use crate::admin::{field_error, validation_failed};
+use crate::auth::TeacherAuth;
+use crate::blanks::{self, fetch_blanks, Blank};
+use crate::judge::{judge_code, Verdict};
+use crate::parsons::{self, fetch_parsons, Parsons};
+use crate::problem::{fetch_problem, judge_cases, Problem, TestCase, KIND_BLANKS, KIND_PARSONS};
+use crate::quiz::{fetch_quiz, Quiz};
+use crate::runner::ResourceUsage;
+use crate::toolchain::ToolchainRegistry;
+use crate::AppState;
+
+/* ==================== 再判定ジョブ ==================== */
+//
+// 保存済みの提出を問題の最新版で判定し直す。対象は作成時に rejudge_results へ並べておき、
+// 判定したものから new_verdict / new_revision を埋める（途中で止まっても続きから再開できる）。
+// dry_run でなければ submissions の判定・出力・使った版も書き換える。
+
+/// 対象の選び方（条件はすべて AND。何も指定しなければ全提出）
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct Selection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub problem_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub submission_ids: Vec<i64>,
+    /// 最新版より前の版（または版不明）で判定された提出だけ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub only_stale: bool,
+}
+
+#[derive(Deserialize)]
+pub struct JobRequest {
+    #[serde(flatten)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// ツールチェーンが入っていなくて判定できなかった提出の new_verdict
+const SKIPPED: &str = "Skipped";
+
+/// ジョブを作って対象の提出を並べる（まだ判定しない）
+pub async fn create_job(pool: &SqlitePool, selection: &Selection, dry_run: bool) -> sqlx::Result<i64> {
+    let mut tx = pool.begin().await?;
+    let id = sqlx::query("INSERT INTO rejudge_jobs (selection, dry_run, status, created_at) VALUES (?, ?, 'queued', ?)")
+        .bind(json!(selection).to_string())
+        .bind(dry_run)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+    // problem_id / code が NULL の行（初期の壊れたデータ）は対象外
+    sqlx::query(
+        r#"
+        INSERT INTO rejudge_results (job_id, submission_id, old_verdict, old_revision)
+        SELECT ?1, s.id, s.verdict, s.problem_revision
+        FROM submissions s JOIN problems p ON p.id = s.problem_id
+        WHERE s.code IS NOT NULL
+          AND (?2 IS NULL OR s.problem_id = ?2)
+          AND (?3 = '[]' OR s.id IN (SELECT value FROM json_each(?3)))
+          AND (NOT ?4 OR s.problem_revision IS NULL OR s.problem_revision < p.revision)
+        "#,
+    )
+    .bind(id)
+    .bind(selection.problem_id)
+    .bind(json!(selection.submission_ids).to_string())
+    .bind(selection.only_stale)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+#[derive(FromRow)]
+struct Target {
+    submission_id: i64,
+    problem_id: i64,
+    code: String,
+}
+
+/// 未判定の対象を順に判定する。失敗したらジョブを failed にして Err
+pub async fn run_job(pool: &SqlitePool, toolchains: &ToolchainRegistry, job_id: i64) -> anyhow::Result<()> {
+    sqlx::query("UPDATE rejudge_jobs SET status = 'running' WHERE id = ?")
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+    let result = judge_pending(pool, toolchains, job_id).await;
+    let (status, error) = match &result {
+        Ok(()) => ("done", None),
+        Err(e) => ("failed", Some(e.to_string())),
+    };
+    sqlx::query("UPDATE rejudge_jobs SET status = ?, error = ?, finished_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(error)
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+    result
+}
+
+async fn judge_pending(pool: &SqlitePool, toolchains: &ToolchainRegistry, job_id: i64) -> anyhow::Result<()> {
+    let (dry_run,): (bool,) = sqlx::query_as("SELECT dry_run FROM rejudge_jobs WHERE id = ?")
+        .bind(job_id)
+        .fetch_one(pool)
+        .await?;
+    let targets = sqlx::query_as::<_, Target>(
+        r#"
+        SELECT r.submission_id, s.problem_id, s.code
+        FROM rejudge_results r JOIN submissions s ON s.id = r.submission_id
+        WHERE r.job_id = ? AND r.judged_at IS NULL
+        ORDER BY r.submission_id
+        "#,
+    )
+    .bind(job_id)
+    .fetch_all(pool)
+    .await?;
+
+    // 問題とケースは問題ごとに 1 回だけ読む（ジョブの間に編集されても最初に読んだ版で揃える）
+    let mut problems: HashMap<i64, Loaded> = HashMap::new();
+    for t in &targets {
+        if let Entry::Vacant(e) = problems.entry(t.problem_id) {
+            let problem = fetch_problem(pool, t.problem_id).await?;
+            e.insert(Loaded {
+                cases: judge_cases(pool, &problem).await?,
+                quiz: fetch_quiz(pool, problem.id).await?,
+                blanks: fetch_blanks(pool, problem.id).await?,
+                parsons: fetch_parsons(pool, problem.id).await?,
+                problem,
+            });
+        }
+        let Loaded { problem, cases, quiz, blanks: blank_defs, parsons: lines } = &problems[&t.problem_id];
+
+        // クイズは保存した解答を採点し直すだけ
+        let r = if let Some(quiz) = quiz {
+            Rejudged {
+                verdict: quiz.grade_stored(&t.code),
+                output: String::new(),
+                usage: None,
+                toolchain_version: None,
+            }
+        } else {
+            let Some(tc) = toolchains.get(problem.toolchain.as_deref()) else {
+                let mut conn = pool.acquire().await?;
+                record_result(&mut conn, job_id, t.submission_id, SKIPPED, None).await?;
+                continue;
+            };
+            // 穴埋め・並べ替えは保存した解答を最新の定義で組み立て直す（読めなければ空として扱う）
+            let r = match (problem.kind.as_str(), lines) {
+                (KIND_BLANKS, _) => {
+                    let values = blanks::parse_stored(&t.code).unwrap_or_default();
+                    blanks::judge_blanks(problem, cases, blank_defs, &values, tc).await?
+                }
+                (KIND_PARSONS, Some(lines)) => {
+                    let placed = parsons::parse_stored(&t.code).unwrap_or_default();
+                    parsons::judge_parsons(problem, cases, lines, &placed, tc).await?
+                }
+                _ => judge_code(problem, cases, &t.code, tc).await?,
+            };
+            Rejudged {
+                verdict: r.verdict,
+                output: r.output,
+                usage: r.usage,
+                toolchain_version: Some(tc.version.clone()),
+            }
+        };
+
+        let mut tx = pool.begin().await?;
+        if !dry_run {
+            sqlx::query(
+                r#"
+                UPDATE submissions SET
+                  output = ?, verdict = ?, max_rss_kb = ?, user_time_ms = ?, sys_time_ms = ?,
+                  toolchain_version = ?, problem_revision = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(&r.output)
+            .bind(r.verdict.as_str())
+            .bind(r.usage.map(|u| u.max_rss_kb))
+            .bind(r.usage.map(|u| u.user_time_ms))
+            .bind(r.usage.map(|u| u.sys_time_ms))
+            .bind(&r.toolchain_version)
+            .bind(problem.revision)
+            .bind(t.submission_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        record_result(&mut tx, job_id, t.submission_id, r.verdict.as_str(), Some(problem.revision)).await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// 判定に使う問題の中身（問題ごとに 1 回だけ読む）
+struct Loaded {
+    problem: Problem,
+    cases: Vec<TestCase>,
+    quiz: Option<Quiz>,
+    blanks: Vec<Blank>,
+    parsons: Option<Parsons>,
+}
+
+/// 判定し直した結果（submissions に書き戻す分）
+struct Rejudged {
+    verdict: Verdict,
+    output: String,
+    usage: Option<ResourceUsage>,
+    toolchain_version: Option<String>,
+}
+
+async fn record_result(
+    conn: &mut SqliteConnection,
+    job_id: i64,
+    submission_id: i64,
+    verdict: &str,
+    revision: Option<i64>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "UPDATE rejudge_results SET new_verdict = ?, new_revision = ?, judged_at = ? WHERE job_id = ? AND submission_id = ?",
+    )
+    .bind(verdict)
+    .bind(revision)
+    .bind(Utc::now().to_rfc3339())
+    .bind(job_id)
+    .bind(submission_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// サーバ停止で途中になったジョブを続きから流す（起動時に呼ぶ）
+pub async fn resume(pool: &SqlitePool, toolchains: &Arc<ToolchainRegistry>) -> sqlx::Result<()> {
+    let pending: Vec<(i64,)> =
+        sqlx::query_as("SELECT id FROM rejudge_jobs WHERE status IN ('queued', 'running') ORDER BY id")
+            .fetch_all(pool)
+            .await?;
+    for (id,) in pending {
+        println!("[rejudge] resuming job {id}");
+        spawn(pool.clone(), toolchains.clone(), id);
+    }
+    Ok(())
+}
+
+fn spawn(pool: SqlitePool, toolchains: Arc<ToolchainRegistry>, job_id: i64) {
+    actix_web::rt::spawn(async move {
+        if let Err(e) = run_job(&pool, &toolchains, job_id).await {
+            eprintln!("[rejudge] job {job_id} failed: {e}");
+        }
+    });
+}
+
+/* ==================== 結果 ==================== */
+
+#[derive(FromRow, Serialize)]
+pub struct JobSummary {
+    pub id: i64,
+    #[sqlx(try_from = "String")]
+    pub selection: JsonText,
+    pub dry_run: bool,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub total: i64,
+    pub judged: i64,
+    /// 判定が変わった数（Skipped は数えない）
+    pub changed: i64,
+}
+
+/// rejudge_jobs.selection（JSON 文字列）をそのまま JSON として返す
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct JsonText(serde_json::Value);
+
+impl From<String> for JsonText {
+    fn from(s: String) -> Self {
+        JsonText(serde_json::from_str(&s).unwrap_or_default())
+    }
+}
+
+const JOB_SELECT: &str = r#"
+    SELECT j.id, j.selection, j.dry_run, j.status, j.error, j.created_at, j.finished_at,
+      (SELECT COUNT(*) FROM rejudge_results WHERE job_id = j.id) AS total,
+      (SELECT COUNT(*) FROM rejudge_results WHERE job_id = j.id AND judged_at IS NOT NULL) AS judged,
+      (SELECT COUNT(*) FROM rejudge_results
+       WHERE job_id = j.id AND new_verdict IS NOT old_verdict AND new_verdict <> 'Skipped') AS changed
+    FROM rejudge_jobs j
+"#;
+
+pub async fn list_jobs(pool: &SqlitePool) -> sqlx::Result<Vec<JobSummary>> {
+    sqlx::query_as(&format!("{JOB_SELECT} ORDER BY j.id DESC LIMIT 50"))
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn fetch_job(pool: &SqlitePool, id: i64) -> sqlx::Result<JobSummary> {
+    sqlx::query_as(&format!("{JOB_SELECT} WHERE j.id = ?"))
+        .bind(id)
+        .fetch_one(pool)
+        .await
+}
+
+/// 判定が変わった提出（Skipped も含める）
+#[derive(FromRow, Serialize)]
+pub struct VerdictChange {
+    pub submission_id: i64,
+    pub problem_id: i64,
+    pub slug: String,
+    pub old_verdict: Option<String>,
+    pub new_verdict: String,
+    pub old_revision: Option<i64>,
+    pub new_revision: Option<i64>,
+}
+
+pub async fn job_changes(pool: &SqlitePool, id: i64) -> sqlx::Result<Vec<VerdictChange>> {
+    sqlx::query_as(
+        r#"
+        SELECT r.submission_id, p.id AS problem_id, p.slug, r.old_verdict, r.new_verdict,
+          r.old_revision, r.new_revision
+        FROM rejudge_results r
+        JOIN submissions s ON s.id = r.submission_id
+        JOIN problems p ON p.id = s.problem_id
+        WHERE r.job_id = ? AND r.new_verdict IS NOT NULL AND r.new_verdict IS NOT r.old_verdict
+        ORDER BY r.submission_id
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/* ==================== ハンドラ ==================== */
+
+/// ジョブを作ってバックグラウンドで流す（202 と id を返す。進み具合は GET で見る）
+#[post("/api/admin/rejudge")]
+async fn start_job(_: TeacherAuth, req: web::Json<JobRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Some(pid) = req.selection.problem_id {
+        match fetch_problem(&state.pool, pid).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => {
+                return validation_failed(vec![field_error("problem_id", "no such problem")]);
+            }
+            Err(e) => return HttpResponse::InternalServerError().body(format!("db error: {e}")),
+        }
+    }
+    match create_job(&state.pool, &req.selection, req.dry_run).await {
+        Ok(id) => {
+            spawn(state.pool.clone(), state.toolchains.clone(), id);
+            HttpResponse::Accepted().json(json!({ "id": id }))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[get("/api/admin/rejudge")]
+async fn get_jobs(_: TeacherAuth, state: web::Data<AppState>) -> impl Responder {
+    match list_jobs(&state.pool).await {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+#[get("/api/admin/rejudge/{id}")]
+async fn get_job(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+    let found = match fetch_job(&state.pool, id).await {
+        Ok(job) => job_changes(&state.pool, id).await.map(|changes| (job, changes)),
+        Err(e) => Err(e),
+    };
+    match found {
+        Ok((job, changes)) => HttpResponse::Ok().json(json!({ "job": job, "changes": changes })),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(start_job).service(get_jobs).service(get_job);
+}
@@ -0,0 +1,125 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+use crate::admin::ProblemInput;
+use crate::auth::TeacherAuth;
+use crate::package;
+use crate::AppState;
+
+/* ==================== 問題の版 ==================== */
+//
+// 保存のたびに内容（ProblemInput の JSON）を problem_revisions に積み、problems.revision を進める。
+// 内容が前の版と同じ保存（変更の無い再インポートなど）では版を増やさない。
+// 提出は判定に使った版を submissions.problem_revision に持つ。
+
+/// 内容が変わっていれば新しい版を積んで、現在の版番号を返す（save_problem のトランザクション内で呼ぶ）
+pub async fn record(conn: &mut SqliteConnection, problem_id: i64, input: &ProblemInput) -> sqlx::Result<i64> {
+    let content = serde_json::to_string(input).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+    let latest: Option<(i64, String)> = sqlx::query_as(
+        "SELECT revision, content FROM problem_revisions WHERE problem_id = ? ORDER BY revision DESC LIMIT 1",
+    )
+    .bind(problem_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    // 文字列ではなく読み直した値で比べる（項目が増えても既定値のままなら同じ版）
+    let same = |c: &str| serde_json::from_str::<ProblemInput>(c).is_ok_and(|prev| prev == *input);
+    if let Some((rev, _)) = latest.as_ref().filter(|(_, c)| same(c)) {
+        return Ok(*rev);
+    }
+
+    let rev = latest.map_or(1, |(r, _)| r + 1);
+    sqlx::query("INSERT INTO problem_revisions (problem_id, revision, content, created_at) VALUES (?, ?, ?, ?)")
+        .bind(problem_id)
+        .bind(rev)
+        .bind(&content)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE problems SET revision = ? WHERE id = ?")
+        .bind(rev)
+        .bind(problem_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(rev)
+}
+
+/// まだ版の無い問題（版管理より前に作られたもの）の現在の内容を版 1 として積む
+pub async fn backfill(pool: &SqlitePool) -> anyhow::Result<()> {
+    let (pending,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM problems WHERE revision = 0")
+        .fetch_one(pool)
+        .await?;
+    if pending == 0 {
+        return Ok(());
+    }
+    let problems = package::load_all(pool).await?;
+    let mut tx = pool.begin().await?;
+    for (id, input) in &problems {
+        let (rev,): (i64,) = sqlx::query_as("SELECT revision FROM problems WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if rev == 0 {
+            record(&mut tx, *id, input).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/* ==================== 履歴（管理者向け） ==================== */
+
+#[derive(FromRow, Serialize)]
+pub struct RevisionSummary {
+    pub revision: i64,
+    pub created_at: String,
+    /// この版で判定された提出の数
+    pub submissions: i64,
+}
+
+pub async fn list_revisions(pool: &SqlitePool, problem_id: i64) -> sqlx::Result<Vec<RevisionSummary>> {
+    sqlx::query_as(
+        r#"
+        SELECT r.revision, r.created_at,
+          (SELECT COUNT(*) FROM submissions s
+           WHERE s.problem_id = r.problem_id AND s.problem_revision = r.revision) AS submissions
+        FROM problem_revisions r
+        WHERE r.problem_id = ?
+        ORDER BY r.revision DESC
+        "#,
+    )
+    .bind(problem_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[get("/api/admin/problems/{id}/revisions")]
+async fn get_revisions(_: TeacherAuth, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    match list_revisions(&state.pool, path.into_inner()).await {
+        Ok(rows) if rows.is_empty() => HttpResponse::NotFound().finish(),
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/// その版の内容（ProblemInput の形）
+#[get("/api/admin/problems/{id}/revisions/{revision}")]
+async fn get_revision(_: TeacherAuth, path: web::Path<(i64, i64)>, state: web::Data<AppState>) -> impl Responder {
+    let (id, revision) = path.into_inner();
+    let found: Result<Option<(String,)>, _> =
+        sqlx::query_as("SELECT content FROM problem_revisions WHERE problem_id = ? AND revision = ?")
+            .bind(id)
+            .bind(revision)
+            .fetch_optional(&state.pool)
+            .await;
+    match found {
+        Ok(Some((content,))) => HttpResponse::Ok().content_type("application/json").body(content),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_revisions).service(get_revision);
+}
@@ -0,0 +1,528 @@
+use serde::Serialize;
+use std::{
+    io::{Read, Write},
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command as StdCommand, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::fs;
+
+use crate::panic_report::USER_SOURCE;
+use crate::toolchain::Toolchain;
+
+/* ==================== 設定 ==================== */
+
+/// 実行時間（壁時計）の上限。これを超えたら SIGKILL
+pub const RUN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// wait4(WNOHANG) のポーリング間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// 出力上限（stdout / stderr それぞれ）。problems.output_limit_bytes が NULL のとき
+pub const DEFAULT_OUTPUT_LIMIT_BYTES: usize = 64 * 1024;
+
+/// パイプからの読み取り単位
+const READ_CHUNK: usize = 8 * 1024;
+
+/// 子を止めた後、リーダが EOF に達するのを待つ上限（プロセスグループから抜けた孫がパイプを握っていても戻る）
+const READER_GRACE: Duration = Duration::from_millis(200);
+
+/// 1 回の実行に課す上限。メモリと CPU 時間は子プロセスの setrlimit でカーネルに強制させ、
+/// 終了後の rusage でもう一度確かめる（judge::judge_case）
+#[derive(Clone, Copy, Debug)]
+pub struct RunLimits {
+    /// stdout / stderr それぞれのバイト数
+    pub output_bytes: usize,
+    /// RLIMIT_DATA（ヒープと匿名 mmap）。None なら無制限
+    pub memory_kb: Option<i64>,
+    /// RLIMIT_CPU（秒単位に切り上げ）。None でも RUN_TIMEOUT と同じ秒数で止める
+    pub cpu_time_ms: Option<i64>,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        RunLimits { output_bytes: DEFAULT_OUTPUT_LIMIT_BYTES, memory_kb: None, cpu_time_ms: None }
+    }
+}
+
+impl RunLimits {
+    /// pre_exec（fork 後・exec 前の子）で呼ぶ。setrlimit はシグナル安全
+    fn apply(&self) -> std::io::Result<()> {
+        let cpu_ms = self.cpu_time_ms.unwrap_or(RUN_TIMEOUT.as_millis() as i64).max(1);
+        let cpu_secs = ((cpu_ms + 999) / 1000) as libc::rlim_t;
+        // soft で SIGXCPU、それでも止まらなければ 1 秒後に SIGKILL
+        set_rlimit(libc::RLIMIT_CPU, cpu_secs, cpu_secs + 1)?;
+        if let Some(kb) = self.memory_kb {
+            // 超えた確保は失敗し、Rust の既定のアロケータエラーで abort する
+            let bytes = (kb.max(0) as libc::rlim_t).saturating_mul(1024);
+            set_rlimit(libc::RLIMIT_DATA, bytes, bytes)?;
+        }
+        // SIGXCPU / SIGABRT で作業ディレクトリにコアを吐かせない
+        set_rlimit(libc::RLIMIT_CORE, 0, 0)
+    }
+}
+
+// setrlimit の resource の型は glibc だけ専用の型で、musl などは c_int
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RlimitResource = libc::c_int;
+
+fn set_rlimit(resource: RlimitResource, soft: libc::rlim_t, hard: libc::rlim_t) -> std::io::Result<()> {
+    let lim = libc::rlimit { rlim_cur: soft, rlim_max: hard };
+    // SAFETY: lim は有効な rlimit
+    if unsafe { libc::setrlimit(resource, &lim) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/* ==================== コンパイル設定 ==================== */
+
+pub const SUPPORTED_EDITIONS: &[&str] = &["2015", "2018", "2021", "2024"];
+pub const SUPPORTED_OPT_LEVELS: &[&str] = &["0", "1", "2", "3", "s", "z"];
+
+/// 問題ごとの rustc 設定（problems の edition / opt_level / debug_assertions）。
+/// どの rustc を使うかは toolchain::ToolchainRegistry で引いた Toolchain で別に渡す
+#[derive(Clone, Debug)]
+pub struct CompileOptions {
+    pub edition: String,
+    pub opt_level: String,
+    /// true なら debug_assert! と整数オーバーフロー検査が有効になる
+    pub debug_assertions: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            edition: "2021".into(),
+            opt_level: "2".into(),
+            debug_assertions: false,
+        }
+    }
+}
+
+impl CompileOptions {
+    /// DB の値をそのまま rustc に渡すので、ここで弾いておく
+    pub fn validate(&self) -> Result<(), String> {
+        if !SUPPORTED_EDITIONS.contains(&self.edition.as_str()) {
+            return Err(format!("unsupported edition: {}", self.edition));
+        }
+        if !SUPPORTED_OPT_LEVELS.contains(&self.opt_level.as_str()) {
+            return Err(format!("unsupported opt_level: {}", self.opt_level));
+        }
+        Ok(())
+    }
+
+    fn rustc_args(&self) -> Vec<String> {
+        vec![
+            format!("--edition={}", self.edition),
+            "-C".into(),
+            format!("opt-level={}", self.opt_level),
+            "-C".into(),
+            format!("debug-assertions={}", if self.debug_assertions { "on" } else { "off" }),
+        ]
+    }
+}
+
+/* ==================== 結果 ==================== */
+
+/// 1 回の実行で消費したリソース（wait4 の rusage から取得）
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub struct ResourceUsage {
+    /// ピーク RSS（KiB）
+    pub max_rss_kb: i64,
+    pub user_time_ms: i64,
+    pub sys_time_ms: i64,
+    pub wall_time_ms: i64,
+}
+
+impl ResourceUsage {
+    pub fn cpu_time_ms(&self) -> i64 {
+        self.user_time_ms + self.sys_time_ms
+    }
+}
+
+pub struct RunOutcome {
+    pub compiled: bool,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// stdout / stderr のどちらかが上限を超えた（プロセスは kill 済み、出力は切り詰め済み）
+    pub output_limited: bool,
+    // ★ 追加: RLIMIT_CPU を使い切ってカーネルに止められた（SIGXCPU）
+    pub cpu_limited: bool,
+    // ★ 追加: RLIMIT_DATA を超える確保に失敗して abort した
+    pub memory_limited: bool,
+    /// 正常終了したときの終了コード
+    pub exit_code: Option<i32>,
+    /// シグナルで終了したときのシグナル番号（タイムアウト等で kill した場合も含む）
+    pub signal: Option<i32>,
+    /// 実行まで到達したときだけ Some（コンパイルエラー時は None）
+    pub usage: Option<ResourceUsage>,
+}
+
+/* ==================== コンパイル＆実行 ==================== */
+
+/// コンパイル済みのバイナリ。テストケースごとに何度でも実行できる。
+/// drop 時に作業ディレクトリごと消す
+pub struct Program {
+    work_dir: PathBuf,
+    bin: PathBuf,
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+/// コンパイルだけ行う。失敗時は Ok(Err(rustc の stderr))
+pub async fn compile(
+    code: &str,
+    opts: &CompileOptions,
+    toolchain: &Toolchain,
+) -> anyhow::Result<Result<Program, String>> {
+    if let Err(e) = opts.validate() {
+        anyhow::bail!("invalid compile options: {e}");
+    }
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    let work_dir = PathBuf::from(format!("/tmp/run-{}", nanos));
+    fs::create_dir_all(&work_dir).await?;
+    // 以降で失敗しても作業ディレクトリは消える
+    let program = Program { bin: work_dir.join("app-bin"), work_dir };
+
+    let src = program.work_dir.join(USER_SOURCE);
+    fs::write(&src, code).await?;
+
+    // work_dir で相対パスのままコンパイルし、パニック位置を "main.rs:L:C" にする。
+    // 行テーブルだけ付けてバックトレースにファイル・行を出す
+    let compile_out = toolchain
+        .rustc()
+        .current_dir(&program.work_dir)
+        .args(opts.rustc_args())
+        .arg(USER_SOURCE)
+        .arg("-C")
+        .arg("debuginfo=line-tables-only")
+        .arg("-o")
+        .arg(&program.bin)
+        .output()
+        .await?;
+
+    if !compile_out.status.success() {
+        return Ok(Err(String::from_utf8_lossy(&compile_out.stderr).to_string()));
+    }
+    Ok(Ok(program))
+}
+
+impl Program {
+    /// stdin を与えて 1 回実行する
+    pub async fn run(&self, stdin: &str, limits: RunLimits) -> anyhow::Result<RunOutcome> {
+        let bin = self.bin.clone();
+        let input = stdin.as_bytes().to_vec();
+        // wait4 はブロッキングなので専用スレッドで
+        let exec = match tokio::task::spawn_blocking(move || execute(&bin, &input, RUN_TIMEOUT, limits)).await? {
+            Ok(x) => x,
+            Err(e) => {
+                return Ok(RunOutcome {
+                    compiled: true,
+                    timed_out: false,
+                    stdout: String::new(),
+                    stderr: format!("exec error: {e}"),
+                    output_limited: false,
+                    cpu_limited: false,
+                    memory_limited: false,
+                    exit_code: None,
+                    signal: None,
+                    usage: None,
+                })
+            }
+        };
+
+        let signal = libc::WIFSIGNALED(exec.status).then(|| libc::WTERMSIG(exec.status));
+        // ★ 変更: stderr は提出コードが自由に書けるので、文言を含むだけでは MLE にしない（is_alloc_failure）
+        let alloc_failed = limits.memory_kb.is_some_and(|kb| is_alloc_failure(&exec.stderr, kb, exec.usage.max_rss_kb));
+        let stderr = captured_to_string(exec.stderr, limits.output_bytes);
+        Ok(RunOutcome {
+            compiled: true,
+            timed_out: exec.timed_out,
+            stdout: captured_to_string(exec.stdout, limits.output_bytes),
+            output_limited: exec.output_limited,
+            cpu_limited: !exec.timed_out && signal == Some(libc::SIGXCPU),
+            memory_limited: signal == Some(libc::SIGABRT) && alloc_failed,
+            stderr,
+            exit_code: libc::WIFEXITED(exec.status).then(|| libc::WEXITSTATUS(exec.status)),
+            signal,
+            usage: Some(exec.usage),
+        })
+    }
+}
+
+/// コンパイルして 1 回だけ実行する（playground 用）
+pub async fn run_user_code(
+    code: &str,
+    opts: &CompileOptions,
+    toolchain: &Toolchain,
+    stdin: &str,
+    limits: RunLimits,
+) -> anyhow::Result<RunOutcome> {
+    match compile(code, opts, toolchain).await? {
+        Ok(program) => program.run(stdin, limits).await,
+        Err(stderr) => Ok(RunOutcome::failed_compile(stderr)),
+    }
+}
+
+impl RunOutcome {
+    pub fn failed_compile(stderr: String) -> Self {
+        RunOutcome {
+            compiled: false,
+            timed_out: false,
+            stdout: String::new(),
+            stderr,
+            output_limited: false,
+            cpu_limited: false,
+            memory_limited: false,
+            exit_code: None,
+            signal: None,
+            usage: None,
+        }
+    }
+}
+
+/* ==================== 実行（wait4 で回収） ==================== */
+
+struct Execution {
+    /// wait4 が返した生のステータス
+    status: libc::c_int,
+    timed_out: bool,
+    output_limited: bool,
+    stdout: Captured,
+    stderr: Captured,
+    usage: ResourceUsage,
+}
+
+/// 上限付きで読み取ったパイプの中身
+#[derive(Default, Clone)]
+struct Captured {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+/// バイナリを起動し、wait4 で子プロセスを回収して rusage を得る。
+/// tokio の Child だと回収を tokio 側に握られるため、ここでは std の Command を使う。
+fn execute(bin: &Path, stdin: &[u8], limit: Duration, limits: RunLimits) -> std::io::Result<Execution> {
+    let started = Instant::now();
+    let mut cmd = StdCommand::new(bin);
+    cmd.env("RUST_BACKTRACE", "1")
+        .stdin(if stdin.is_empty() { Stdio::null() } else { Stdio::piped() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // ★ 追加: メモリと CPU 時間の上限はカーネルに強制させる（wait4 の rusage は事後の確認用）
+    // SAFETY: クロージャは fork 後の子で setrlimit を呼ぶだけ（メモリ確保もロックもしない）
+    unsafe {
+        cmd.pre_exec(move || limits.apply());
+    }
+    // ★ 追加: 子が起動したプロセスもまとめて止められるよう、子を新しいプロセスグループ（pgid = pid）の長にする
+    cmd.process_group(0);
+    let mut child = cmd.spawn()?;
+    let pid = child.id() as libc::pid_t;
+
+    // 子が読まずに詰まっても止まらないよう、書き込みも別スレッドで（書き終えたら閉じて EOF）
+    if let Some(mut pipe) = child.stdin.take() {
+        let input = stdin.to_vec();
+        thread::spawn(move || {
+            let _ = pipe.write_all(&input);
+        });
+    }
+
+    // どちらかのリーダが上限に達したら立てる。kill は回収前のこのスレッドで行う
+    let overflow = Arc::new(AtomicBool::new(false));
+    let stdout_reader = spawn_reader(child.stdout.take(), limits.output_bytes, overflow.clone());
+    let stderr_reader = spawn_reader(child.stderr.take(), limits.output_bytes, overflow.clone());
+
+    let mut status: libc::c_int = 0;
+    // SAFETY: rusage は POD なのでゼロ初期化で問題ない
+    let mut ru: libc::rusage = unsafe { std::mem::zeroed() };
+    let mut timed_out = false;
+
+    loop {
+        // 終了したかだけを見て、まだ回収しない（WNOWAIT）。ゾンビが pid と pgid を握っているうちにグループを止めるため
+        // SAFETY: siginfo_t は POD。pid は自分で spawn した子プロセス
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let r = unsafe {
+            libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOHANG | libc::WNOWAIT)
+        };
+        if r < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(e);
+        }
+        // SAFETY: waitid が埋めた（終了していなければゼロのまま）
+        if unsafe { info.si_pid() } == pid {
+            break;
+        }
+        let over = overflow.load(Ordering::Relaxed);
+        if over || started.elapsed() >= limit {
+            timed_out = !over;
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    // 正常終了でも、子が残したプロセス（孫）がパイプを握ったまま生き残らないようグループごと止めてから回収する。
+    // 子はまだ回収していないので pgid が別のプロセスに再利用されることはない
+    // SAFETY: 上と同じ。status / ru は有効なポインタ
+    unsafe {
+        libc::killpg(pid, libc::SIGKILL);
+        libc::wait4(pid, &mut status, 0, &mut ru);
+    }
+    let wall = started.elapsed();
+
+    // グループから抜けたプロセスがパイプを握っていても待ち続けない
+    let deadline = Instant::now() + READER_GRACE;
+    let stdout = stdout_reader.finish(deadline);
+    let stderr = stderr_reader.finish(deadline);
+
+    Ok(Execution {
+        status,
+        timed_out,
+        output_limited: stdout.truncated || stderr.truncated,
+        stdout,
+        stderr,
+        usage: ResourceUsage {
+            // Linux の ru_maxrss は KiB 単位
+            max_rss_kb: ru.ru_maxrss,
+            user_time_ms: timeval_ms(&ru.ru_utime),
+            sys_time_ms: timeval_ms(&ru.ru_stime),
+            wall_time_ms: wall.as_millis() as i64,
+        },
+    })
+}
+
+/// 読み取り中のパイプ。中身はスレッドと共有し、待ち切れなければそこまでの分を返す
+struct Reader {
+    captured: Arc<Mutex<Captured>>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl Reader {
+    /// EOF まで（遅くとも deadline まで）待って中身を返す。間に合わなければ読み取りスレッドは置いていく
+    fn finish(self, deadline: Instant) -> Captured {
+        while !self.handle.is_finished() && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        let captured = self.captured.lock().map(|c| c.clone()).unwrap_or_default();
+        captured
+    }
+}
+
+/// パイプを少しずつ読み、`limit` バイトを超えた時点で読み取りをやめて `overflow` を立てる。
+/// メモリに載るのは常に高々 `limit + READ_CHUNK` バイト。
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>, limit: usize, overflow: Arc<AtomicBool>) -> Reader {
+    let captured = Arc::new(Mutex::new(Captured::default()));
+    let out = captured.clone();
+    let handle = thread::spawn(move || {
+        let Some(mut p) = pipe else { return };
+        let mut chunk = [0u8; READ_CHUNK];
+        loop {
+            let n = match p.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let Ok(mut out) = out.lock() else { break };
+            let room = limit - out.bytes.len();
+            if n > room {
+                out.bytes.extend_from_slice(&chunk[..room]);
+                out.truncated = true;
+                overflow.store(true, Ordering::Relaxed);
+                break;
+            }
+            out.bytes.extend_from_slice(&chunk[..n]);
+        }
+    });
+    Reader { captured, handle }
+}
+
+/// std の既定のアロケータエラーは "memory allocation of N bytes failed" を出し、RUST_BACKTRACE があれば
+/// バックトレースを続けて abort する。次をすべて満たすときだけ上限による確保の失敗とみなす。
+///   - その行の後ろにはバックトレース（"stack backtrace:"・字下げした行・"note: "）しか無い
+///   - 実際の使用量（max RSS）に N バイトを足すと上限を超える（上限内に収まる確保が失敗したとは言わない）
+fn is_alloc_failure(stderr: &Captured, limit_kb: i64, max_rss_kb: i64) -> bool {
+    if stderr.truncated {
+        return false;
+    }
+    let text = String::from_utf8_lossy(&stderr.bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let Some((at, bytes)) = lines.iter().enumerate().rev().find_map(|(i, l)| {
+        let n = l.strip_prefix("memory allocation of ")?.strip_suffix(" bytes failed")?;
+        n.parse::<u64>().ok().map(|n| (i, n))
+    }) else {
+        return false;
+    };
+    let rest = &lines[at + 1..];
+    let backtrace_only = rest.is_empty()
+        || (rest[0] == "stack backtrace:"
+            && rest[1..].iter().all(|l| l.starts_with(' ') || l.starts_with("note: ")));
+    let requested_kb = i64::try_from(bytes / 1024).unwrap_or(i64::MAX);
+    backtrace_only && max_rss_kb.saturating_add(requested_kb) > limit_kb
+}
+
+/// 切り詰めた場合は末尾にマーカーを付ける
+fn captured_to_string(c: Captured, limit: usize) -> String {
+    let mut s = String::from_utf8_lossy(&c.bytes).to_string();
+    if c.truncated {
+        if !s.ends_with('\n') {
+            s.push('\n');
+        }
+        s.push_str(&format!("... [output truncated: exceeded {limit} bytes]\n"));
+    }
+    s
+}
+
+fn timeval_ms(tv: &libc::timeval) -> i64 {
+    tv.tv_sec * 1000 + tv.tv_usec / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured(s: &str) -> Captured {
+        Captured { bytes: s.as_bytes().to_vec(), truncated: false }
+    }
+
+    const OOM: &str = "memory allocation of 67108864 bytes failed\nstack backtrace:\n   0: std::alloc::rust_oom\n\
+                       \x20            at /rustc/abc/library/std/src/alloc.rs:424:5\n\
+                       note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.\n";
+
+    #[test]
+    fn allocator_failures_over_the_limit() {
+        assert!(is_alloc_failure(&captured(OOM), 65536, 41100));
+        assert!(is_alloc_failure(&captured("memory allocation of 536870912 bytes failed\n"), 65536, 1000));
+        // 前に学習者の出力があってもよい
+        assert!(is_alloc_failure(&captured(&format!("progress 1\n{OOM}")), 65536, 41100));
+    }
+
+    #[test]
+    fn forged_or_fitting_allocations_are_not_failures() {
+        // 上限内に収まる大きさ
+        assert!(!is_alloc_failure(&captured("memory allocation of 5 bytes failed\n"), 65536, 1000));
+        // 後ろにバックトレース以外の行がある
+        let forged = "memory allocation of 536870912 bytes failed\nmore\n";
+        assert!(!is_alloc_failure(&captured(forged), 65536, 1000));
+        // 文言の一部だけ・切り詰められた stderr
+        assert!(!is_alloc_failure(&captured("memory allocation of many bytes failed\n"), 65536, 1000));
+        let truncated = Captured { truncated: true, ..captured(OOM) };
+        assert!(!is_alloc_failure(&truncated, 65536, 41100));
+    }
+}
@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+use crate::locale;
+use crate::problem::Tags;
+
+/* ==================== 全文検索 ==================== */
+//
+// problem_search（FTS5, trigram）を引く。索引は save_problem のたびに reindex で作り直す。
+// trigram は 3 文字未満の語を MATCH できないので、短い語は LIKE で絞り込む。
+
+/// snippet / highlight の目印（本文に出てこない私用領域の文字）。HTML エスケープ後に <mark> に置き換える
+const MARK_OPEN: char = '\u{E000}';
+const MARK_CLOSE: char = '\u{E001}';
+
+pub const MAX_RESULTS: i64 = 50;
+
+/// 1 問分の索引を作り直す（`id` が None なら全問題）
+pub async fn reindex(conn: &mut SqliteConnection, id: Option<i64>) -> sqlx::Result<()> {
+    sqlx::query("DELETE FROM problem_search WHERE ? IS NULL OR problem_id = ?")
+        .bind(id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    // 既定ロケール（problems 本体）と各翻訳
+    sqlx::query(
+        r#"
+        INSERT INTO problem_search (problem_id, locale, title, description, tags, starter_code)
+        SELECT p.id, ?, p.title, p.description,
+               COALESCE((SELECT GROUP_CONCAT(tag, ' ') FROM problem_tags WHERE problem_id = p.id), ''),
+               p.starter_code
+        FROM problems p
+        WHERE ? IS NULL OR p.id = ?
+        "#,
+    )
+    .bind(locale::DEFAULT_LOCALE)
+    .bind(id)
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO problem_search (problem_id, locale, title, description, tags, starter_code)
+        SELECT p.id, t.locale, t.title, t.description,
+               COALESCE((SELECT GROUP_CONCAT(tag, ' ') FROM problem_tags WHERE problem_id = p.id), ''),
+               p.starter_code
+        FROM problems p JOIN problem_translations t ON t.problem_id = p.id
+        WHERE ? IS NULL OR p.id = ?
+        "#,
+    )
+    .bind(id)
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(FromRow)]
+struct Hit {
+    problem_id: i64,
+    locale: String,
+    title: String,
+    snippet: String,
+    rank: f64,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub id: i64,
+    pub slug: String,
+    /// 一致箇所を <mark> で囲んだ HTML（エスケープ済み）
+    pub title_html: String,
+    pub snippet_html: String,
+    pub locale: String,
+    pub difficulty: Option<String>,
+    pub category: Option<String>,
+    pub tags: Tags,
+    /// bm25（小さいほど良い）。短い語だけの検索では 0
+    pub rank: f64,
+}
+
+#[derive(FromRow)]
+struct Meta {
+    slug: String,
+    difficulty: Option<String>,
+    category: Option<String>,
+    #[sqlx(try_from = "String")]
+    tags: Tags,
+}
+
+/// 空白区切りの語をすべて含む問題を、良い順に（同じ問題はロケール違いを 1 件にまとめる）
+pub async fn search(
+    pool: &SqlitePool,
+    q: &str,
+    limit: i64,
+    chain: &[&str],
+) -> sqlx::Result<Vec<SearchResult>> {
+    let terms: Vec<&str> = q.split_whitespace().collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (long, short): (Vec<&str>, Vec<&str>) = terms.iter().partition(|t| t.chars().count() >= 3);
+
+    // 短い語は 4 列のどれかに含まれること
+    let mut like = String::new();
+    for _ in &short {
+        like.push_str(concat!(
+            " AND (title LIKE ? ESCAPE '\\' OR description LIKE ? ESCAPE '\\'",
+            " OR tags LIKE ? ESCAPE '\\' OR starter_code LIKE ? ESCAPE '\\')"
+        ));
+    }
+    let sql = if long.is_empty() {
+        format!(
+            "SELECT problem_id, locale, title, description || char(10) || starter_code AS snippet, 0.0 AS rank
+             FROM problem_search WHERE 1 = 1 {like} ORDER BY problem_id"
+        )
+    } else {
+        format!(
+            "SELECT problem_id, locale,
+                    highlight(problem_search, 2, char(57344), char(57345)) AS title,
+                    snippet(problem_search, -1, char(57344), char(57345), '…', 48) AS snippet,
+                    rank
+             FROM problem_search WHERE problem_search MATCH ? {like} ORDER BY rank"
+        )
+    };
+
+    let mut query = sqlx::query_as::<_, Hit>(&sql);
+    if !long.is_empty() {
+        // 各語を "..." で囲んでフレーズにする（Rc<RefCell<T>> などの記号を演算子として解釈させない）
+        let m: Vec<String> = long.iter().map(|t| format!("\"{}\"", t.replace('"', "\"\""))).collect();
+        query = query.bind(m.join(" "));
+    }
+    for t in &short {
+        let pat = format!("%{}%", t.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        for _ in 0..4 {
+            query = query.bind(pat.clone());
+        }
+    }
+    let hits = query.fetch_all(pool).await?;
+
+    // 問題ごとに 1 件: 順位が最良のもの。ただし希望ロケールの行があればそちらの表示を使う
+    let mut out: Vec<(Hit, usize)> = Vec::new();
+    let pref = |l: &str| chain.iter().position(|c| *c == l).unwrap_or(chain.len());
+    for h in hits {
+        match out.iter_mut().find(|(o, _)| o.problem_id == h.problem_id) {
+            Some((o, p)) if pref(&h.locale) < *p => {
+                let rank = o.rank;
+                *p = pref(&h.locale);
+                *o = Hit { rank, ..h };
+            }
+            Some(_) => {}
+            None => {
+                let p = pref(&h.locale);
+                out.push((h, p));
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+    for (h, _) in out.into_iter().take(limit.clamp(1, MAX_RESULTS) as usize) {
+        let meta = sqlx::query_as::<_, Meta>(
+            r#"
+            SELECT slug, difficulty, category,
+              COALESCE((SELECT GROUP_CONCAT(tag, ',') FROM problem_tags WHERE problem_id = problems.id), '') AS tags
+            FROM problems WHERE id = ?
+            "#,
+        )
+        .bind(h.problem_id)
+        .fetch_one(pool)
+        .await?;
+        let (title_html, snippet_html) = if long.is_empty() {
+            (mark_terms(&h.title, &short), mark_terms(&excerpt(&h.snippet, short[0]), &short))
+        } else {
+            (marks_to_html(&h.title), marks_to_html(&h.snippet))
+        };
+        results.push(SearchResult {
+            id: h.problem_id,
+            slug: meta.slug,
+            title_html,
+            snippet_html,
+            locale: h.locale,
+            difficulty: meta.difficulty,
+            category: meta.category,
+            tags: meta.tags,
+            rank: h.rank,
+        });
+    }
+    Ok(results)
+}
+
+/// 最初の出現箇所の前後だけ切り出す（FTS の snippet と同じくらいの長さ）
+fn excerpt(text: &str, term: &str) -> String {
+    const AROUND: usize = 24;
+    let chars: Vec<char> = text.chars().collect();
+    // ★ 変更: 位置は元の文字列の上で探す（見つからなければ先頭から）
+    let (at, len) = match find_ignore_case(text, term).first() {
+        Some(r) => (text[..r.start].chars().count(), text[r.clone()].chars().count()),
+        None => (0, term.chars().count()),
+    };
+    let start = at.saturating_sub(AROUND);
+    let end = (at + len + AROUND).min(chars.len());
+    let mut s: String = chars[start..end].iter().collect();
+    if start > 0 {
+        s.insert(0, '…');
+    }
+    if end < chars.len() {
+        s.push('…');
+    }
+    s
+}
+
+/// 目印をエスケープ後に <mark> へ
+fn marks_to_html(s: &str) -> String {
+    escape(s).replace(MARK_OPEN, "<mark>").replace(MARK_CLOSE, "</mark>")
+}
+
+/// FTS を使わなかったとき用: 語の出現箇所を <mark> で囲む（大文字小文字は区別しない）
+fn mark_terms(s: &str, terms: &[&str]) -> String {
+    let mut out = s.to_string();
+    for t in terms {
+        let found = find_ignore_case(&out, t);
+        if found.is_empty() {
+            continue;
+        }
+        let mut marked = String::new();
+        let mut last = 0;
+        for r in found {
+            marked.push_str(&out[last..r.start]);
+            marked.push(MARK_OPEN);
+            marked.push_str(&out[r.clone()]);
+            marked.push(MARK_CLOSE);
+            last = r.end;
+        }
+        marked.push_str(&out[last..]);
+        out = marked;
+    }
+    marks_to_html(&out)
+}
+
+/// 大文字小文字を区別せずに needle の出現箇所（重ならないもの）を s のバイト範囲で返す。空の語は何にも一致しない。
+///
+/// 丸ごと to_lowercase すると長さの変わる文字（'\u{212A}' KELVIN SIGN → 'k' など）で位置がずれるので、
+/// 1 文字ずつ小文字にして比べ、範囲は必ず s の文字境界になるようにする。
+fn find_ignore_case(s: &str, needle: &str) -> Vec<Range<usize>> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    let mut found = Vec::new();
+    if needle.is_empty() {
+        return found;
+    }
+    let mut from = 0;
+    for (start, _) in s.char_indices() {
+        if start < from {
+            continue;
+        }
+        if let Some(len) = match_len(&s[start..], &needle) {
+            found.push(start..start + len);
+            from = start + len;
+        }
+    }
+    found
+}
+
+/// s の先頭が（小文字にして）needle と一致すれば、その長さ（バイト数）。文字の途中で終わる一致は数えない
+fn match_len(s: &str, needle: &[char]) -> Option<usize> {
+    let mut rest = needle;
+    for (i, c) in s.char_indices() {
+        for l in c.to_lowercase() {
+            match rest.split_first() {
+                Some((n, tail)) if *n == l => rest = tail,
+                _ => return None,
+            }
+        }
+        if rest.is_empty() {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mark_terms_ignores_case() {
+        assert_eq!(mark_terms("String::from(\"a\")", &["string"]), "<mark>String</mark>::from(&quot;a&quot;)");
+        assert_eq!(mark_terms("Vec vec", &["VEC"]), "<mark>Vec</mark> <mark>vec</mark>");
+    }
+
+    #[test]
+    fn mark_terms_keeps_char_boundaries_when_lowercase_changes_length() {
+        // KELVIN SIGN（3 バイト）は 'k'（1 バイト）に、'İ' は 2 文字になる
+        assert_eq!(mark_terms("\u{212A}ey ok", &["k"]), "<mark>\u{212A}</mark>ey o<mark>k</mark>");
+        assert_eq!(mark_terms("İx ix", &["ix"]), "İx <mark>ix</mark>");
+        assert_eq!(mark_terms("İx", &["i\u{307}"]), "<mark>İ</mark>x");
+    }
+
+    #[test]
+    fn mark_terms_skips_empty_terms() {
+        assert_eq!(mark_terms("a<b", &["", "b"]), "a&lt;<mark>b</mark>");
+    }
+
+    #[test]
+    fn excerpt_centers_on_the_first_match() {
+        let text = format!("{}\u{212A}ey{}", "x".repeat(40), "y".repeat(40));
+        let s = excerpt(&text, "key");
+        assert!(s.starts_with('…') && s.ends_with('…'));
+        assert!(s.contains("\u{212A}ey"));
+        assert_eq!(excerpt("short", ""), "short");
+    }
+}
@@ -0,0 +1,151 @@
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::admin::contains_block;
+use crate::answer::fetch_answers;
+use crate::blanks::{self, fetch_blanks};
+use crate::judge::{judge_code, Verdict};
+use crate::parsons::{self, fetch_parsons};
+use crate::problem::{judge_cases, Problem, KIND_BLANKS, KIND_CODE, KIND_PARSONS, KIND_QUIZ, PROBLEM_SELECT};
+use crate::toolchain::ToolchainRegistry;
+
+/* ==================== 問題の自己検査 ==================== */
+//
+// 実際の判定（judge_code）に模範解答・別解と初期コードを通して、
+//   - 模範解答 / 別解が全ケース Accepted にならない（expected_stdout の typo など）
+//   - 初期コードがそのまま Accepted になってしまう
+//   - 固定領域が初期コード / 模範解答に含まれない
+// を見つける。穴埋め・並べ替えは模範の中身から組み立てたものも模範解答として通す。
+
+#[derive(Serialize, Debug)]
+pub struct Issue {
+    /// no_solution / solution_fails / answer_fails / starter_passes / fixed_region_mismatch / toolchain_missing
+    pub kind: &'static str,
+    pub message: String,
+}
+
+fn issue(kind: &'static str, message: impl Into<String>) -> Issue {
+    Issue { kind, message: message.into() }
+}
+
+#[derive(Serialize)]
+pub struct CheckReport {
+    pub id: i64,
+    pub slug: String,
+    pub ok: bool,
+    pub issues: Vec<Issue>,
+}
+
+/// 1 問を検査する
+pub async fn check_problem(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    problem: &Problem,
+) -> anyhow::Result<CheckReport> {
+    // クイズは判定に通すコードが無い（正解の形は保存時の検証で確かめている）
+    if problem.kind == KIND_QUIZ {
+        return Ok(report(problem, Vec::new()));
+    }
+    let mut issues = Vec::new();
+    // ★ 追加: 穴埋め・並べ替えは模範解答が無ければ模範の中身から組み立てたものを模範解答とみなす
+    let filled = match problem.kind.as_str() {
+        KIND_BLANKS => blanks::reference_code(&problem.starter_code, &fetch_blanks(pool, problem.id).await?),
+        KIND_PARSONS => fetch_parsons(pool, problem.id).await?.map(|p| parsons::assemble(&p.reference())),
+        _ => None,
+    };
+    let solution = problem.solution_code.as_deref().filter(|s| !s.trim().is_empty()).or(filled.as_deref());
+
+    // 固定領域（エディタで書き換えられない部分）は両方のコードに同じ形で入っているはず
+    for (field, block) in [("fixed_top", &problem.fixed_top), ("fixed_bottom", &problem.fixed_bottom)] {
+        let Some(b) = block.as_deref().filter(|b| !b.trim().is_empty()) else { continue };
+        for (name, code) in [("starter code", Some(problem.starter_code.as_str())), ("solution", solution)] {
+            if code.is_some_and(|c| !contains_block(c, b)) {
+                issues.push(issue("fixed_region_mismatch", format!("{field} does not occur in the {name}")));
+            }
+        }
+    }
+
+    let Some(toolchain) = toolchains.get(problem.toolchain.as_deref()) else {
+        let name = problem.toolchain.as_deref().unwrap_or_default();
+        issues.push(issue("toolchain_missing", format!("toolchain not installed: {name}")));
+        return Ok(report(problem, issues));
+    };
+    let cases = judge_cases(pool, problem).await?;
+
+    match solution {
+        None => issues.push(issue("no_solution", "no reference solution")),
+        Some(code) => {
+            let r = judge_code(problem, &cases, code, toolchain).await?;
+            if r.verdict != Verdict::Accepted {
+                let case = r.cases.last().map(|c| format!(" on case {}", c.position)).unwrap_or_default();
+                issues.push(issue(
+                    "solution_fails",
+                    format!("solution got {}{case}:\n{}", r.verdict.as_str(), r.output),
+                ));
+            }
+        }
+    }
+
+    for (i, a) in fetch_answers(pool, problem.id).await?.iter().enumerate() {
+        let r = judge_code(problem, &cases, &a.code, toolchain).await?;
+        if r.verdict != Verdict::Accepted {
+            let case = r.cases.last().map(|c| format!(" on case {}", c.position)).unwrap_or_default();
+            issues.push(issue(
+                "answer_fails",
+                format!("answer {} ({}) got {}{case}:\n{}", i + 1, a.title, r.verdict.as_str(), r.output),
+            ));
+        }
+    }
+
+    // 穴埋めのテンプレートは穴が残っているのでそもそもコンパイルできない（並べ替えには初期コードが無い）
+    if problem.kind == KIND_CODE {
+        let r = judge_code(problem, &cases, &problem.starter_code, toolchain).await?;
+        if r.verdict == Verdict::Accepted {
+            issues.push(issue("starter_passes", "starter code is accepted without any change"));
+        }
+    }
+
+    Ok(report(problem, issues))
+}
+
+fn report(problem: &Problem, issues: Vec<Issue>) -> CheckReport {
+    CheckReport { id: problem.id, slug: problem.slug.clone(), ok: issues.is_empty(), issues }
+}
+
+/// 全問題（`slugs` が空でなければその問題だけ）を検査する（slug 順）
+pub async fn check_all(
+    pool: &SqlitePool,
+    toolchains: &ToolchainRegistry,
+    slugs: &[&str],
+) -> anyhow::Result<Vec<CheckReport>> {
+    let mut problems = sqlx::query_as::<_, Problem>(&format!("{PROBLEM_SELECT} ORDER BY slug"))
+        .fetch_all(pool)
+        .await?;
+    if !slugs.is_empty() {
+        problems.retain(|p| slugs.contains(&p.slug.as_str()));
+    }
+    let mut reports = Vec::new();
+    for p in &problems {
+        reports.push(check_problem(pool, toolchains, p).await?);
+    }
+    Ok(reports)
+}
+
+/// `server check` 用の表示。問題のある問題が 1 つでもあれば Err
+pub fn print_reports(reports: &[CheckReport]) -> anyhow::Result<()> {
+    for r in reports {
+        println!("{:<6} {}", if r.ok { "ok" } else { "FAIL" }, r.slug);
+        for i in &r.issues {
+            let mut lines = i.message.lines();
+            println!("         {}: {}", i.kind, lines.next().unwrap_or_default());
+            for line in lines.filter(|l| !l.trim().is_empty()).take(4) {
+                println!("           {line}");
+            }
+        }
+    }
+    let failed = reports.iter().filter(|r| !r.ok).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} problem(s) failed self-check");
+    }
+    Ok(())
+}
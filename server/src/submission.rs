@@ -0,0 +1,162 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqlitePool};
+
+use crate::account::CurrentUser;
+use crate::auth::{Role, TeacherAuth};
+use crate::AppState;
+
+/* ==================== 提出の閲覧 ==================== */
+//
+// 学習者は自分の提出だけ（/api/submissions）、teacher 以上は全員の提出（/api/admin/submissions）。
+// 1 件の詳細（コード・出力つき）は本人か teacher 以上だけが見られる。
+// 利用者ができる前の提出（user_id が NULL）は teacher 以上の一覧にだけ出る。
+
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// 一覧の絞り込み（user_id は teacher 以上の一覧でだけ効く）
+#[derive(Deserialize)]
+pub struct SubmissionFilter {
+    pub problem_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub verdict: Option<String>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_per_page")]
+    pub per_page: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_per_page() -> i64 {
+    20
+}
+
+/// 一覧の 1 行（コード・出力は含めない）
+#[derive(FromRow, Serialize)]
+pub struct SubmissionSummary {
+    pub id: i64,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub problem_id: Option<i64>,
+    pub slug: Option<String>,
+    pub problem_revision: Option<i64>,
+    pub verdict: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubmissionPage {
+    pub items: Vec<SubmissionSummary>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(FromRow, Serialize)]
+pub struct SubmissionDetail {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub summary: SubmissionSummary,
+    pub code: Option<String>,
+    pub output: Option<String>,
+    pub max_rss_kb: Option<i64>,
+    pub user_time_ms: Option<i64>,
+    pub sys_time_ms: Option<i64>,
+    pub toolchain_version: Option<String>,
+}
+
+const FROM: &str =
+    " FROM submissions s LEFT JOIN users u ON u.id = s.user_id LEFT JOIN problems p ON p.id = s.problem_id";
+const SUMMARY_COLUMNS: &str =
+    "SELECT s.id, s.user_id, u.username, s.problem_id, p.slug, s.problem_revision, s.verdict, s.created_at";
+
+fn push_filter<'a>(qb: &mut QueryBuilder<'a, Sqlite>, f: &'a SubmissionFilter, user_id: Option<i64>) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(u) = user_id {
+        qb.push(" AND s.user_id = ").push_bind(u);
+    }
+    if let Some(p) = f.problem_id {
+        qb.push(" AND s.problem_id = ").push_bind(p);
+    }
+    if let Some(v) = &f.verdict {
+        qb.push(" AND s.verdict = ").push_bind(v);
+    }
+}
+
+/// 新しい順。user_id が Some ならその利用者の提出だけ
+pub async fn list_submissions(
+    pool: &SqlitePool,
+    f: &SubmissionFilter,
+    user_id: Option<i64>,
+) -> sqlx::Result<SubmissionPage> {
+    let per_page = f.per_page.clamp(1, MAX_PER_PAGE);
+    let page = f.page.max(1);
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*)");
+    count.push(FROM);
+    push_filter(&mut count, f, user_id);
+    let (total,): (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    let mut qb = QueryBuilder::new(SUMMARY_COLUMNS);
+    qb.push(FROM);
+    push_filter(&mut qb, f, user_id);
+    qb.push(" ORDER BY s.id DESC LIMIT ").push_bind(per_page).push(" OFFSET ").push_bind((page - 1) * per_page);
+    let items = qb.build_query_as().fetch_all(pool).await?;
+    Ok(SubmissionPage { items, total, page, per_page })
+}
+
+pub async fn fetch_submission(pool: &SqlitePool, id: i64) -> sqlx::Result<Option<SubmissionDetail>> {
+    let sql = format!(
+        "{SUMMARY_COLUMNS}, s.code, s.output, s.max_rss_kb, s.user_time_ms, s.sys_time_ms, s.toolchain_version{FROM} \
+         WHERE s.id = ?"
+    );
+    sqlx::query_as(&sql).bind(id).fetch_optional(pool).await
+}
+
+/* ==================== ハンドラ ==================== */
+
+/// 自分の提出
+#[get("/api/submissions")]
+async fn my_submissions(
+    user: CurrentUser,
+    filter: web::Query<SubmissionFilter>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match list_submissions(&state.pool, &filter, Some(user.id)).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/// 本人か teacher 以上だけ
+#[get("/api/submissions/{id}")]
+async fn get_submission(user: CurrentUser, path: web::Path<i64>, state: web::Data<AppState>) -> impl Responder {
+    match fetch_submission(&state.pool, path.into_inner()).await {
+        Ok(Some(s)) if s.summary.user_id == Some(user.id) || user.role >= Role::Teacher => HttpResponse::Ok().json(s),
+        Ok(Some(_)) => HttpResponse::Forbidden()
+            .json(json!({ "error": "forbidden", "message": "this submission belongs to another user" })),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+/// 全員の提出（user_id で絞り込める）
+#[get("/api/admin/submissions")]
+async fn all_submissions(
+    _: TeacherAuth,
+    filter: web::Query<SubmissionFilter>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    match list_submissions(&state.pool, &filter, filter.user_id).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::InternalServerError().body(format!("db error: {e}")),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(my_submissions).service(get_submission).service(all_submissions);
+}
@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+/* ==================== ツールチェイン一覧 ==================== */
+//
+// 起動時に一度だけ作る。problems.toolchain / playground の toolchain はこの名前で引く。
+//
+//   TOOLCHAINS="stable,nightly"                    … rustup のツールチェイン名
+//   TOOLCHAINS="stable=/opt/rust/stable/bin/rustc" … rustc を直接指定
+//
+// 未設定なら `rustup toolchain list` から拾い、rustup も無ければ PATH の rustc だけを使う。
+// 先頭（rustup の場合は default 印の付いたもの）が既定になる。
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Toolchain {
+    pub name: String,
+    /// `rustc -V` の出力（例: "rustc 1.86.0 (05f9846f8 2025-03-31)"）
+    pub version: String,
+    #[serde(skip)]
+    invocation: Invocation,
+}
+
+#[derive(Clone, Debug)]
+enum Invocation {
+    /// `rustc +<name>`（rustup のプロキシ経由）
+    Rustup(String),
+    /// rustc のパスを直接
+    Path(PathBuf),
+}
+
+impl Toolchain {
+    /// この toolchain の rustc を起動する Command（引数は呼び出し側で足す）
+    pub fn rustc(&self) -> Command {
+        match &self.invocation {
+            Invocation::Rustup(name) => {
+                let mut cmd = Command::new("rustc");
+                cmd.arg(format!("+{name}"));
+                cmd
+            }
+            Invocation::Path(p) => Command::new(p),
+        }
+    }
+}
+
+pub struct ToolchainRegistry {
+    toolchains: Vec<Toolchain>,
+}
+
+impl ToolchainRegistry {
+    pub async fn detect() -> anyhow::Result<Self> {
+        let candidates = match std::env::var("TOOLCHAINS") {
+            Ok(v) if !v.trim().is_empty() => parse_env(&v),
+            _ => match rustup_toolchains().await {
+                Some(v) if !v.is_empty() => v,
+                _ => vec![("default".to_string(), Invocation::Path(PathBuf::from("rustc")))],
+            },
+        };
+
+        let mut toolchains = Vec::new();
+        for (name, invocation) in candidates {
+            let mut tc = Toolchain { name, version: String::new(), invocation };
+            match version_of(&tc).await {
+                Ok(v) => {
+                    tc.version = v;
+                    toolchains.push(tc);
+                }
+                Err(e) => eprintln!("[toolchain] skip {}: {e}", tc.name),
+            }
+        }
+        if toolchains.is_empty() {
+            anyhow::bail!("no usable rustc found (TOOLCHAINS / rustup / PATH)");
+        }
+        Ok(ToolchainRegistry { toolchains })
+    }
+
+    pub fn list(&self) -> &[Toolchain] {
+        &self.toolchains
+    }
+
+    pub fn default_toolchain(&self) -> &Toolchain {
+        &self.toolchains[0]
+    }
+
+    /// None なら既定の toolchain
+    pub fn get(&self, name: Option<&str>) -> Option<&Toolchain> {
+        match name {
+            None => Some(self.default_toolchain()),
+            Some(n) => self.toolchains.iter().find(|t| t.name == n),
+        }
+    }
+}
+
+fn parse_env(v: &str) -> Vec<(String, Invocation)> {
+    v.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| match s.split_once('=') {
+            Some((name, path)) => (name.trim().to_string(), Invocation::Path(PathBuf::from(path.trim()))),
+            None => (s.to_string(), Invocation::Rustup(s.to_string())),
+        })
+        .collect()
+}
+
+/// `rustup toolchain list` の各行（"nightly-x86_64-unknown-linux-gnu (default)" など）を拾う。
+/// 表示名はホストの三つ組を落としたもの（"nightly"）にする
+async fn rustup_toolchains() -> Option<Vec<(String, Invocation)>> {
+    let out = Command::new("rustup").args(["toolchain", "list"]).output().await.ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let host_sep = format!("-{}-", std::env::consts::ARCH);
+
+    let mut list: Vec<(String, Invocation)> = Vec::new();
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let Some(full) = line.split_whitespace().next() else { continue };
+        let short = match full.find(&host_sep) {
+            Some(i) => &full[..i],
+            None => full,
+        };
+        let entry = (short.to_string(), Invocation::Rustup(full.to_string()));
+        if line.contains("default") {
+            list.insert(0, entry);
+        } else {
+            list.push(entry);
+        }
+    }
+    Some(list)
+}
+
+async fn version_of(tc: &Toolchain) -> anyhow::Result<String> {
+    let out = tc.rustc().arg("-V").output().await?;
+    if !out.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
@@ -19,6 +19,57 @@ let editableEndLine   = Infinity;
 
 let decorations = [];
 
+/* ---------- UI 文言（/api/i18n） ---------- */
+// ?lang=en で言語を固定。無ければ Accept-Language でサーバが決める
+const LANG = new URLSearchParams(location.search).get('lang');
+const langQuery = () => (LANG ? `?lang=${encodeURIComponent(LANG)}` : '');
+
+let uiStrings = {};
+
+// tr('status.loaded', { title }) → "問題を読み込みました: ..."
+function tr(key, vars = {}) {
+  const s = uiStrings[key] ?? key;
+  return s.replace(/\{(\w+)\}/g, (m, k) => (k in vars ? String(vars[k]) : m));
+}
+
+async function loadUiStrings() {
+  try {
+    const r = await fetch(`/api/i18n${langQuery()}`);
+    if (!r.ok) throw new Error(`failed to fetch ui strings: ${r.status}`);
+    const data = await r.json();
+    uiStrings = data.strings || {};
+    document.documentElement.lang = data.locale;
+
+    // data-i18n の付いた要素の文言を差し替え
+    for (const el of document.querySelectorAll('[data-i18n]')) {
+      el.textContent = tr(el.dataset.i18n);
+    }
+    for (const el of document.querySelectorAll('[data-i18n-placeholder]')) {
+      el.placeholder = tr(el.dataset.i18nPlaceholder);
+    }
+
+    // 言語切り替え（選ぶと ?lang= 付きで読み直す）
+    const sel = document.getElementById('langSelect');
+    if (sel) {
+      sel.innerHTML = '';
+      for (const l of data.supported || []) {
+        const opt = document.createElement('option');
+        opt.value = l;
+        opt.textContent = l;
+        opt.selected = l === data.locale;
+        sel.appendChild(opt);
+      }
+      sel.addEventListener('change', () => {
+        const u = new URL(location.href);
+        u.searchParams.set('lang', sel.value);
+        location.href = u.toString();
+      });
+    }
+  } catch (e) {
+    console.error(e);
+  }
+}
+
 /* ---------- ユーティリティ ---------- */
 function setStatus(kind, text) {
   const $s = document.getElementById('status');
@@ -218,14 +269,48 @@ function installGuardsOnce() {
   });
 }
 
+/* ---------- URL（/p/{slug}） ---------- */
+// 表示中の問題を URL に載せる（?lang= などのクエリはそのまま）
+function slugFromPath() {
+  const m = location.pathname.match(/^\/p\/([^/]+)\/?$/);
+  return m ? decodeURIComponent(m[1]) : null;
+}
+
+function setProblemUrl(slug, replace) {
+  const url = `/p/${encodeURIComponent(slug)}${location.search}`;
+  if (location.pathname + location.search === url) return;
+  if (replace) history.replaceState(null, '', url);
+  else history.pushState(null, '', url);
+}
+
 /* ---------- 問題のロード＆反映 ---------- */
-async function selectProblem(id) {
+async function selectProblem(id, { replace = false } = {}) {
+  const raw = await showProblem(`/api/problems/${id}${langQuery()}`);
+  if (raw) setProblemUrl(raw.slug, replace);
+}
+
+// 旧 slug はサーバが現在の slug へ 301 で転送する（fetch が自動で追う）ので、URL も書き換える
+async function selectProblemBySlug(slug) {
+  const raw = await showProblem(`/api/problems/by-slug/${encodeURIComponent(slug)}${langQuery()}`);
+  if (raw) {
+    document.getElementById('problemSelect').value = raw.id;
+    setProblemUrl(raw.slug, true);
+  }
+  return raw;
+}
+
+// 取得できたら問題を返す（失敗時は null。ステータス表示は済ませる）
+async function showProblem(url) {
   await monacoReady;
   const desc = document.getElementById('problemDesc');
 
   try {
-    const r = await fetch(`/api/problems/${id}`);
-    if (!r.ok) throw new Error(`failed to fetch problem ${id}: ${r.status}`);
+    const r = await fetch(url);
+    if (r.status === 404) {
+      setStatus('warn', tr('status.not_found'));
+      return null;
+    }
+    if (!r.ok) throw new Error(`failed to fetch problem ${url}: ${r.status}`);
     const raw = await r.json();
 
     const starter = decode(raw.starter_code ?? raw.starterCode ?? '');
@@ -245,7 +330,7 @@ async function selectProblem(id) {
     }
 
     isRestoring = true;
-    editor.setValue(starter || '// 初期コードが空です。\n');
+    editor.setValue(starter || tr('editor.empty_starter') + '\n');
     isRestoring = false;
 
     lastGoodText = editor.getValue();                  // 初期スナップショット
@@ -253,14 +338,499 @@ async function selectProblem(id) {
     updateEditableDecoration();
     installGuardsOnce();
 
-    desc.textContent = raw.description ?? '';
-    setStatus('info', `問題を読み込みました: ${raw.title}`);
+    // description_html はサーバ側でサニタイズ済み
+    desc.innerHTML = raw.description_html ?? '';
+    currentProblemId = raw.id;
+    // クイズ・穴埋めはエディターの代わりに専用の解答欄を出す
+    document.getElementById('editorPanel').hidden = (raw.kind ?? 'code') !== 'code';
+    renderQuiz(raw.quiz ?? null);
+    renderBlanks(raw.blanks ?? null);
+    renderParsons(raw.parsons ?? null);
+    await loadHints(raw.id);
+    await loadAnswers(raw.id);
+    setStatus('info', tr('status.loaded', { title: raw.title }));
+    return raw;
+  } catch (e) {
+    console.error(e);
+    setStatus('danger', tr('status.load_failed'));
+    return null;
+  }
+}
+
+/* ---------- アカウント（Cookie のセッション） ---------- */
+let currentUser = null;
+// サーバで使えるログイン方法（/api/account/methods）
+let loginMethods = { password: true, oidc: false };
+
+function renderAccount() {
+  document.getElementById('loginForm').hidden = currentUser !== null;
+  document.getElementById('accountInfo').hidden = currentUser === null;
+  document.getElementById('passwordForm').hidden = true;
+  // パスワードでのログインを止めているなら IdP のボタンだけ
+  for (const id of ['loginUsername', 'loginPassword', 'loginBtn', 'registerBtn', 'passwordToggle']) {
+    document.getElementById(id).hidden = !loginMethods.password;
+  }
+  const sso = document.getElementById('ssoLogin');
+  sso.hidden = !loginMethods.oidc;
+  // ログイン後は今のページへ戻る
+  sso.href = `/api/oidc/login?return_to=${encodeURIComponent(location.pathname + location.search)}`;
+  // teacher / admin なら役割も出す
+  const role = currentUser && currentUser.role !== 'student' ? ` (${currentUser.role})` : '';
+  document.getElementById('accountName').textContent = (currentUser?.display_name ?? '') + role;
+  renderHintReportPanel();
+}
+
+async function loadAccount() {
+  const [r, m] = await Promise.all([fetch('/api/account'), fetch('/api/account/methods')]);
+  currentUser = r.ok ? await r.json() : null;
+  if (m.ok) loginMethods = await m.json();
+  renderAccount();
+}
+
+// IdP でのログインに失敗すると /?login_error=<code> に戻ってくる
+function showLoginError() {
+  const params = new URLSearchParams(location.search);
+  const code = params.get('login_error');
+  if (!code) return;
+  setStatus('warn', tr('account.sso_failed', { code }));
+  params.delete('login_error');
+  const query = params.toString();
+  history.replaceState(null, '', location.pathname + (query ? `?${query}` : ''));
+}
+
+// エラー応答（422 なら項目ごと）を 1 行に
+async function errorMessage(r) {
+  const body = await r.json().catch(() => null);
+  if (body?.fields) return body.fields.map((f) => `${f.field}: ${f.message}`).join(', ');
+  return body?.message ?? `${r.status}`;
+}
+
+// コースの前提の問題をまだ解いていない（403 problem_locked）なら知らせて true
+async function showLocked(r) {
+  if (r.status !== 403) return false;
+  const body = await r.clone().json().catch(() => null);
+  if (body?.error !== 'problem_locked') return false;
+  setStatus('warn', tr('course.locked', { requires: body.requires.join(', ') }));
+  return true;
+}
+
+// ヒント・解答はログインした利用者ごとなので読み直す
+async function reloadProgress() {
+  if (currentProblemId === null) return;
+  await loadHints(currentProblemId);
+  await loadAnswers(currentProblemId);
+}
+
+async function submitLogin(path) {
+  const username = document.getElementById('loginUsername').value.trim();
+  const password = document.getElementById('loginPassword').value;
+  try {
+    const r = await fetch(`/api/account/${path}`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ username, password }),
+    });
+    if (!r.ok) { setStatus('warn', await errorMessage(r)); return; }
+    currentUser = await r.json();
+    document.getElementById('loginPassword').value = '';
+    renderAccount();
+    setStatus('success', tr('account.welcome', { name: currentUser.display_name }));
+    await reloadProgress();
+  } catch (e) {
+    console.error(e);
+    setStatus('danger', tr('status.server_error'));
+  }
+}
+
+async function logout() {
+  try {
+    await fetch('/api/account/logout', { method: 'POST' });
+    currentUser = null;
+    renderAccount();
+    setStatus('info', tr('account.logged_out'));
+    await reloadProgress();
+  } catch (e) {
+    console.error(e);
+  }
+}
+
+async function changePassword() {
+  const current_password = document.getElementById('currentPassword').value;
+  const new_password = document.getElementById('newPassword').value;
+  try {
+    const r = await fetch('/api/account/password', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ current_password, new_password }),
+    });
+    if (!r.ok) { setStatus('warn', await errorMessage(r)); return; }
+    document.getElementById('currentPassword').value = '';
+    document.getElementById('newPassword').value = '';
+    document.getElementById('passwordForm').hidden = true;
+    setStatus('success', tr('account.password_changed'));
+  } catch (e) {
+    console.error(e);
+    setStatus('danger', tr('status.server_error'));
+  }
+}
+
+/* ---------- ヒント（1 つずつ開く） ---------- */
+let currentProblemId = null;
+
+function renderHints(s) {
+  const list = document.getElementById('hintList');
+  const btn  = document.getElementById('hintBtn');
+  document.getElementById('problemHints').hidden = s.total === 0;
+
+  list.innerHTML = '';
+  for (const h of s.revealed) {
+    const li = document.createElement('li');
+    li.textContent = h.body;
+    list.appendChild(li);
+  }
+
+  btn.hidden = s.next_penalty === null;
+  btn.dataset.penalty = s.next_penalty ?? 0;
+  if (s.next_penalty !== null) {
+    btn.textContent = tr('hint.reveal', { n: s.revealed.length + 1, total: s.total })
+      + (s.next_penalty > 0 ? tr('hint.penalty', { penalty: s.next_penalty }) : '');
+  }
+}
+
+async function loadHints(id) {
+  const r = await fetch(`/api/problems/${id}/hints${langQuery()}`);
+  if (!r.ok) throw new Error(`failed to fetch hints ${id}: ${r.status}`);
+  renderHints(await r.json());
+}
+
+async function revealHint() {
+  if (currentProblemId === null) return;
+  const penalty = Number(document.getElementById('hintBtn').dataset.penalty);
+  if (penalty > 0 && !confirm(tr('hint.confirm_penalty', { penalty }))) return;
+  try {
+    const r = await fetch(`/api/problems/${currentProblemId}/hints/next${langQuery()}`, { method: 'POST' });
+    if (r.status === 401) { setStatus('warn', tr('account.login_required')); return; }
+    if (await showLocked(r)) return;
+    if (!r.ok) throw new Error(`failed to reveal hint: ${r.status}`);
+    renderHints(await r.json());
+  } catch (e) {
+    console.error(e);
+  }
+}
+
+/* ---------- 解答（正解後 / ギブアップ後に自分のコードと比較） ---------- */
+let answerSheet = null;
+let diffEditor = null;
+
+async function loadAnswers(id) {
+  const r = await fetch(`/api/problems/${id}/answers`);
+  // 401 = 未ログイン（解答はまだ見られない）
+  if (r.status === 403 || r.status === 401) {
+    answerSheet = null;
+  } else if (r.ok) {
+    answerSheet = await r.json();
+  } else {
+    throw new Error(`failed to fetch answers ${id}: ${r.status}`);
+  }
+  renderAnswers();
+}
+
+// 模範解答 → 別解の順
+function answerChoices() {
+  if (!answerSheet) return [];
+  const list = answerSheet.reference
+    ? [{ title: tr('answer.reference'), explanation_html: '', code: answerSheet.reference }]
+    : [];
+  return list.concat(answerSheet.answers);
+}
+
+function renderAnswers() {
+  const choices = answerChoices();
+  const sel = document.getElementById('answerSelect');
+  document.getElementById('answerPanel').hidden = false;
+  document.getElementById('giveUpBtn').hidden = answerSheet !== null;
+  document.getElementById('answerView').hidden = choices.length === 0;
+  renderQuizSolution(answerSheet?.quiz ?? null);
+  sel.hidden = choices.length < 2;
+
+  sel.innerHTML = '';
+  choices.forEach((a, i) => {
+    const opt = document.createElement('option');
+    opt.value = i;
+    opt.textContent = a.title;
+    sel.appendChild(opt);
+  });
+  if (choices.length > 0) showAnswer(0);
+}
+
+function showAnswer(i) {
+  const a = answerChoices()[i];
+  if (!a) return;
+  document.getElementById('answerTitle').textContent = a.title;
+  // explanation_html はサーバ側でサニタイズ済み
+  document.getElementById('answerExplanation').innerHTML = a.explanation_html ?? '';
+
+  if (!diffEditor) {
+    diffEditor = monaco.editor.createDiffEditor(document.getElementById('answerDiff'), {
+      theme: 'vs-dark',
+      readOnly: true,
+      automaticLayout: true,
+      renderSideBySide: true,
+    });
+  }
+  // 提出がまだ無ければ（ギブアップ直後など）エディタの中身と比べる
+  const own = answerSheet.own_code ?? editor.getValue();
+  const old = diffEditor.getModel();
+  diffEditor.setModel({
+    original: monaco.editor.createModel(own, 'rust'),
+    modified: monaco.editor.createModel(a.code, 'rust'),
+  });
+  if (old) { old.original.dispose(); old.modified.dispose(); }
+}
+
+async function giveUp() {
+  if (currentProblemId === null || !confirm(tr('answer.confirm_give_up'))) return;
+  try {
+    const r = await fetch(`/api/problems/${currentProblemId}/give-up`, { method: 'POST' });
+    if (r.status === 401) { setStatus('warn', tr('account.login_required')); return; }
+    if (await showLocked(r)) return;
+    if (!r.ok) throw new Error(`give up failed: ${r.status}`);
+    answerSheet = await r.json();
+    renderAnswers();
   } catch (e) {
     console.error(e);
-    setStatus('danger', '問題の読み込みに失敗しました');
   }
 }
 
+/* ---------- ヒントの使用状況（講師向け） ---------- */
+function renderHintReportPanel() {
+  const teacher = currentUser !== null && currentUser.role !== 'student';
+  document.getElementById('hintReportPanel').hidden = !teacher;
+  if (teacher) loadHintReport();
+}
+
+async function loadHintReport() {
+  const box = document.getElementById('hintReport');
+  const r = await fetch('/api/admin/reports/hints');
+  if (!r.ok) {
+    box.textContent = tr('report.load_failed');
+    return;
+  }
+  const rows = await r.json();
+  box.innerHTML = '';
+  if (rows.length === 0) {
+    box.textContent = tr('report.no_hints');
+    return;
+  }
+  // 問題ごとに見出し（合計）と、利用者ごとの表
+  for (const p of rows) {
+    const h = document.createElement('h3');
+    h.className = 'report-problem';
+    h.textContent = tr('report.problem_summary', {
+      title: p.title, users: p.users, revealed: p.revealed, penalty: p.penalty,
+    });
+    box.appendChild(h);
+    if (p.by_user.length === 0) continue;
+
+    const table = document.createElement('table');
+    table.className = 'report-table';
+    const head = table.insertRow();
+    for (const key of ['report.user', 'report.revealed', 'report.penalty', 'report.last_revealed']) {
+      const th = document.createElement('th');
+      th.textContent = tr(key);
+      head.appendChild(th);
+    }
+    for (const u of p.by_user) {
+      const row = table.insertRow();
+      const name = u.user_id === null ? tr('report.anonymous') : `${u.display_name} (${u.username})`;
+      const last = u.last_revealed_at ? new Date(u.last_revealed_at).toLocaleString() : '';
+      for (const text of [name, `${u.revealed}/${p.hints}`, String(u.penalty), last]) {
+        row.insertCell().textContent = text;
+      }
+    }
+    box.appendChild(table);
+  }
+}
+
+/* ---------- クイズ（選択式・短答式） ---------- */
+let currentQuiz = null;
+
+// クイズならエディターの代わりに選択肢 / 入力欄を出す
+function renderQuiz(q) {
+  currentQuiz = q;
+  document.getElementById('quizPanel').hidden = q === null;
+  document.getElementById('quizSolution').hidden = true;
+  if (!q) return;
+
+  const list = document.getElementById('quizChoices');
+  const text = document.getElementById('quizText');
+  list.innerHTML = '';
+  q.choices.forEach((body, i) => {
+    const li = document.createElement('li');
+    const label = document.createElement('label');
+    const input = document.createElement('input');
+    input.type = q.format === 'multiple' ? 'checkbox' : 'radio';
+    input.name = 'quizChoice';
+    input.value = i;
+    const span = document.createElement('span');
+    span.textContent = body;
+    label.append(input, span);
+    li.appendChild(label);
+    list.appendChild(li);
+  });
+  list.hidden = q.format === 'text';
+  text.hidden = q.format !== 'text';
+  text.value = '';
+}
+
+// 正解後 / ギブアップ後: 正解の選択肢に印を付け、短答なら受け付ける表記を出す
+function renderQuizSolution(q) {
+  const box = document.getElementById('quizSolution');
+  box.hidden = !q || !currentQuiz;
+  if (box.hidden) return;
+  document.querySelectorAll('#quizChoices li').forEach((li, i) => {
+    li.classList.toggle('quiz-correct', !!q.choices[i]?.correct);
+  });
+  box.textContent = q.format === 'text'
+    ? tr('quiz.accepted', { answers: q.accepted.join(' / ') })
+    : tr('quiz.marked');
+}
+
+async function submitQuiz() {
+  if (currentProblemId === null || !currentQuiz) return;
+  const body = currentQuiz.format === 'text'
+    ? { text: document.getElementById('quizText').value }
+    : { choices: [...document.querySelectorAll('input[name=quizChoice]:checked')].map((el) => Number(el.value)) };
+  if (!body.text && !(body.choices || []).length) {
+    setStatus('warn', tr('quiz.empty'));
+    return;
+  }
+  const $btn = document.getElementById('quizBtn');
+  try {
+    $btn.disabled = true;
+    const r = await fetch(`/api/problems/${currentProblemId}/quiz`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify(body),
+    });
+    if (r.status === 401) { setStatus('warn', tr('account.login_required')); return; }
+    if (await showLocked(r)) return;
+    if (!r.ok) throw new Error(`quiz answer failed: ${r.status}`);
+    const data = await r.json();
+    if (data.passed) {
+      setStatus('success', tr('quiz.correct'));
+      await loadAnswers(currentProblemId);
+    } else {
+      setStatus('warn', tr('quiz.incorrect'));
+    }
+  } catch (e) {
+    console.error(e);
+    setStatus('danger', tr('status.server_error'));
+  } finally {
+    $btn.disabled = false;
+  }
+}
+
+/* ---------- 穴埋め ---------- */
+let currentBlanks = null;
+
+// テンプレートの穴の位置に入力欄を置く（中身は実行ボタンで /api/run に送る）
+function renderBlanks(b) {
+  currentBlanks = b;
+  document.getElementById('blanksPanel').hidden = b === null;
+  const pre = document.getElementById('blanksTemplate');
+  pre.innerHTML = '';
+  if (!b) return;
+
+  b.parts.forEach((part, i) => {
+    pre.appendChild(document.createTextNode(part));
+    if (i === b.parts.length - 1) return;
+    const input = document.createElement('input');
+    input.type = 'text';
+    input.className = 'blank-input';
+    input.spellcheck = false;
+    input.size = 6;
+    input.setAttribute('aria-label', tr('blanks.label', { n: i + 1 }));
+    // 入力に合わせて幅を広げる
+    input.addEventListener('input', () => { input.size = Math.max(6, input.value.length + 1); });
+    input.addEventListener('keydown', (e) => { if (e.key === 'Enter') runServer(); });
+    pre.appendChild(input);
+  });
+}
+
+function blankValues() {
+  return [...document.querySelectorAll('#blanksTemplate .blank-input')].map((el) => el.value);
+}
+
+/* ---------- 並べ替え（Parsons） ---------- */
+let currentParsons = null;
+let parsonsPicks = [];   // [{ id, indent }]（id は currentParsons.lines の添字）
+
+function renderParsons(p) {
+  currentParsons = p;
+  parsonsPicks = [];
+  document.getElementById('parsonsPanel').hidden = p === null;
+  if (p) drawParsons();
+}
+
+function parsonsButton(label, key, onClick) {
+  const b = document.createElement('button');
+  b.type = 'button';
+  b.className = 'btn parsons-btn';
+  b.textContent = label;
+  b.title = tr(key);
+  b.setAttribute('aria-label', tr(key));
+  b.addEventListener('click', onClick);
+  return b;
+}
+
+// 左: まだ使っていない行（クリックで末尾に追加）/ 右: 並べた行（字下げ・移動・取り外し）
+function drawParsons() {
+  const pool = document.getElementById('parsonsPool');
+  const program = document.getElementById('parsonsProgram');
+  pool.innerHTML = '';
+  program.innerHTML = '';
+  const used = new Set(parsonsPicks.map((x) => x.id));
+
+  currentParsons.lines.forEach((code, id) => {
+    if (used.has(id)) return;
+    const li = document.createElement('li');
+    li.className = 'parsons-line';
+    li.textContent = code;
+    li.addEventListener('click', () => {
+      // 直前の行の字下げを引き継ぐ
+      const indent = parsonsPicks.length ? parsonsPicks[parsonsPicks.length - 1].indent : 0;
+      parsonsPicks.push({ id, indent });
+      drawParsons();
+    });
+    pool.appendChild(li);
+  });
+
+  parsonsPicks.forEach((pick, i) => {
+    const li = document.createElement('li');
+    li.className = 'parsons-line';
+    const code = document.createElement('span');
+    code.className = 'parsons-code';
+    code.textContent = '    '.repeat(pick.indent) + currentParsons.lines[pick.id];
+    const move = (to) => {
+      if (to < 0 || to >= parsonsPicks.length) return;
+      [parsonsPicks[i], parsonsPicks[to]] = [parsonsPicks[to], parsonsPicks[i]];
+      drawParsons();
+    };
+    const tools = document.createElement('span');
+    tools.className = 'parsons-tools';
+    tools.append(
+      parsonsButton('←', 'parsons.outdent', () => { pick.indent = Math.max(0, pick.indent - 1); drawParsons(); }),
+      parsonsButton('→', 'parsons.indent', () => { pick.indent += 1; drawParsons(); }),
+      parsonsButton('↑', 'parsons.up', () => move(i - 1)),
+      parsonsButton('↓', 'parsons.down', () => move(i + 1)),
+      parsonsButton('✕', 'parsons.remove', () => { parsonsPicks.splice(i, 1); drawParsons(); }),
+    );
+    li.append(code, tools);
+    program.appendChild(li);
+  });
+}
+
 /* ---------- 問題一覧のロード ---------- */
 async function loadProblems() {
   await monacoReady;
@@ -268,59 +838,152 @@ async function loadProblems() {
   const desc = document.getElementById('problemDesc');
 
   try {
-    const res = await fetch('/api/problems');
+    // 一覧はページング付きの要約（選択肢に出すだけなので 1 ページに最大件数）
+    const q = new URLSearchParams({ per_page: 100 });
+    if (LANG) q.set('lang', LANG);
+    const res = await fetch(`/api/problems?${q}`);
     if (!res.ok) throw new Error('failed to fetch problems');
-    const problems = await res.json();
+    const problems = (await res.json()).items;
 
     sel.innerHTML = '';
     for (const p of problems) {
       const opt = document.createElement('option');
       opt.value = p.id;
-      opt.textContent = `${p.id}: ${p.title}`;
+      opt.textContent = `${p.id}: ${p.title}` + (p.difficulty ? ` [${p.difficulty}]` : '');
       sel.appendChild(opt);
     }
 
-    if (problems.length > 0) {
-      await selectProblem(problems[0].id);
+    // /p/{slug} で開かれたらその問題、それ以外は先頭の問題
+    const slug = slugFromPath();
+    if (slug) {
+      await selectProblemBySlug(slug);
+    } else if (problems.length > 0) {
+      await selectProblem(problems[0].id, { replace: true });
     } else {
       isRestoring = true; editor.setValue(''); isRestoring = false;
       lastGoodText = '';
       desc.textContent = '';
-      setStatus('warn', '利用可能な問題がありません');
+      setStatus('warn', tr('status.no_problems'));
     }
 
     sel.addEventListener('change', async () => {
       const id = Number(sel.value);
       if (!Number.isNaN(id)) await selectProblem(id);
     });
+
+    // ブラウザの戻る / 進む
+    window.addEventListener('popstate', async () => {
+      const s = slugFromPath();
+      if (s) await selectProblemBySlug(s);
+    });
   } catch (e) {
     console.error(e);
-    setStatus('danger', '問題一覧の取得に失敗しました');
+    setStatus('danger', tr('status.list_failed'));
   }
 }
 
+/* ---------- 検索 ---------- */
+// title_html / snippet_html はサーバ側でエスケープ済み（<mark> だけ入っている）
+function installSearch() {
+  const input = document.getElementById('problemSearch');
+  const list  = document.getElementById('searchResults');
+  let timer = null;
+
+  input.addEventListener('input', () => {
+    clearTimeout(timer);
+    timer = setTimeout(async () => {
+      const q = input.value.trim();
+      if (!q) { list.hidden = true; list.innerHTML = ''; return; }
+      try {
+        const params = new URLSearchParams({ q });
+        if (LANG) params.set('lang', LANG);
+        const r = await fetch(`/api/problems/search?${params}`);
+        if (!r.ok) throw new Error(`search failed: ${r.status}`);
+        const { items } = await r.json();
+
+        list.innerHTML = '';
+        if (items.length === 0) {
+          const li = document.createElement('li');
+          li.className = 'search-empty';
+          li.textContent = tr('search.no_results');
+          list.appendChild(li);
+        }
+        for (const it of items) {
+          const li = document.createElement('li');
+          li.innerHTML = `<div class="search-title">${it.title_html}</div><div class="search-snippet">${it.snippet_html}</div>`;
+          li.addEventListener('click', async () => {
+            document.getElementById('problemSelect').value = it.id;
+            list.hidden = true;
+            await selectProblem(it.id);
+          });
+          list.appendChild(li);
+        }
+        list.hidden = false;
+      } catch (e) {
+        console.error(e);
+      }
+    }, 250);
+  });
+}
+
 /* ---------- 実行 ---------- */
+// 複数テストケースのとき、落ちたケース番号（1 始まり）
+function failedCaseLabel(cases) {
+  if (!cases || cases.length < 2) return '';
+  return tr('verdict.failed_case', { n: cases.length });
+}
+
+// 実行時エラーの表示（パニック位置 / シグナル / 終了コード）
+function runtimeErrorLabel(d) {
+  if (d.panic && d.panic.line) return tr('runtime.panic_at', { line: d.panic.line });
+  if (d.panic)                 return tr('runtime.panic');
+  if (d.signal != null)        return tr('runtime.signal', { signal: d.signal_name || 'signal ' + d.signal });
+  return tr('runtime.exit_code', { code: d.exit_code });
+}
+
+// 実行リソースの表示（例: " — 3 ms / 1.9 MB"）
+function formatUsage(u) {
+  if (!u) return '';
+  const cpu = u.user_time_ms + u.sys_time_ms;
+  const mb  = (u.max_rss_kb / 1024).toFixed(1);
+  return ` — ${cpu} ms / ${mb} MB`;
+}
+
+
 async function runServer() {
-  const $btnRun = document.getElementById('runBtn');
+  const $btnRun = document.getElementById(currentBlanks ? 'blanksBtn' : currentParsons ? 'parsonsBtn' : 'runBtn');
   const $output = document.getElementById('output');
   await monacoReady;
 
   const sel = document.getElementById('problemSelect');
   const pid = Number(sel && sel.value);
-  if (!pid) { setStatus('danger', '問題が選択されていません'); return; }
+  if (!pid) { setStatus('danger', tr('status.no_problem_selected')); return; }
 
-  const code = editor.getValue();
+  // 穴埋めは穴の中身だけを送る（テンプレートへの差し込みはサーバ側）
+  let body = { problem_id: pid, code: editor.getValue() };
+  if (currentBlanks) {
+    const blanks = blankValues();
+    if (blanks.some((v) => !v.trim())) { setStatus('warn', tr('blanks.empty')); return; }
+    body = { problem_id: pid, blanks };
+  }
+  // 並べ替えは選んだ行の id と字下げだけを送る（プログラムの組み立てはサーバ側）
+  if (currentParsons) {
+    if (parsonsPicks.length === 0) { setStatus('warn', tr('parsons.empty')); return; }
+    body = { problem_id: pid, lines: parsonsPicks };
+  }
 
   try {
     $btnRun.disabled = true;
-    setStatus('info', '実行中...');
+    setStatus('info', tr('status.running'));
 
     const resp = await fetch('/api/run', {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ problem_id: pid, code }),
+      body: JSON.stringify(body),
     });
 
+    if (resp.status === 401) { setStatus('warn', tr('account.login_required')); return; }
+    if (await showLocked(resp)) return;
     if (!resp.ok) {
       const txt = await resp.text().catch(() => '');
       throw new Error(`run error: ${resp.status} ${txt}`);
@@ -329,18 +992,21 @@ async function runServer() {
     const data = await resp.json();
     $output.textContent = data.output || ((data.stdout || '') + (data.stderr || ''));
 
-    if (data.timed_out) {
-      setStatus('danger', 'タイムアウト (2s)');
-    } else if (!data.compiled) {
-      setStatus('danger', 'コンパイルエラー');
-    } else if (data.passed) {
-      setStatus('success', '正解！');
-    } else {
-      setStatus('warn', '不正解（出力不一致）');
+    const usage = formatUsage(data.usage);
+    switch (data.verdict) {
+      case 'Accepted':            setStatus('success', tr('verdict.accepted', { usage })); break;
+      case 'CompileError':        setStatus('danger', tr('verdict.compile_error')); break;
+      case 'TimeLimitExceeded':   setStatus('danger', data.timed_out ? tr('verdict.timeout') : tr('verdict.cpu_limit', { usage })); break;
+      case 'MemoryLimitExceeded': setStatus('danger', tr('verdict.memory_limit', { usage })); break;
+      case 'OutputLimitExceeded': setStatus('danger', tr('verdict.output_limit')); break;
+      case 'RuntimeError':        setStatus('danger', runtimeErrorLabel(data)); break;
+      default:                    setStatus('warn', tr('verdict.wrong_answer', { cases: failedCaseLabel(data.cases), usage }));
     }
+    // 正解したら解答が見られるようになる
+    if (data.verdict === 'Accepted') await loadAnswers(pid);
   } catch (e) {
     console.error(e);
-    setStatus('danger', 'サーバエラー');
+    setStatus('danger', tr('status.server_error'));
   } finally {
     $btnRun.disabled = false;
   }
@@ -350,5 +1016,31 @@ async function runServer() {
 document.addEventListener('DOMContentLoaded', () => {
   const btn = document.getElementById('runBtn');
   if (btn) btn.addEventListener('click', runServer);
-  loadProblems();
+  document.getElementById('hintBtn').addEventListener('click', revealHint);
+  document.getElementById('giveUpBtn').addEventListener('click', giveUp);
+  document.getElementById('quizBtn').addEventListener('click', submitQuiz);
+  document.getElementById('blanksBtn').addEventListener('click', runServer);
+  document.getElementById('parsonsBtn').addEventListener('click', runServer);
+  document.getElementById('quizText').addEventListener('keydown', (e) => {
+    if (e.key === 'Enter') submitQuiz();
+  });
+  document.getElementById('answerSelect').addEventListener('change', (e) => showAnswer(Number(e.target.value)));
+  document.getElementById('loginForm').addEventListener('submit', (e) => {
+    e.preventDefault();
+    submitLogin('login');
+  });
+  document.getElementById('registerBtn').addEventListener('click', () => submitLogin('register'));
+  document.getElementById('logoutBtn').addEventListener('click', logout);
+  document.getElementById('passwordToggle').addEventListener('click', () => {
+    const form = document.getElementById('passwordForm');
+    form.hidden = !form.hidden;
+  });
+  document.getElementById('passwordForm').addEventListener('submit', (e) => {
+    e.preventDefault();
+    changePassword();
+  });
+  document.getElementById('hintReportBtn').addEventListener('click', loadHintReport);
+  installSearch();
+  // 文言が揃ってから問題を読む（ステータス表示に使うため）。ヒント・解答は利用者ごとなので先にログイン状態を
+  loadUiStrings().then(loadAccount).then(loadProblems).then(showLoginError);
 });